@@ -1,6 +1,8 @@
 mod file;
 mod rank;
 
+use serde::Serializer;
+
 pub fn from_string(string: &str) -> Result<u8, String> {
     if string.len() != 2 {
         return Err(format!("Invalid Algebric Notation \"{}\"", string));
@@ -17,6 +19,29 @@ pub fn to_string(square: u8) -> String {
     format!("{}{}", file, rank)
 }
 
+/// Parses a move in UCI notation such as "e2e4" or "e7e8q" into its squares and promotion letter.
+pub fn from_uci(uci: &str) -> Result<(u8, u8, Option<char>), String> {
+    if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+        return Err(format!("Invalid Move \"{}\"", uci));
+    }
+    let from = from_string(&uci[0..2])?;
+    let to = from_string(&uci[2..4])?;
+    Ok((from, to, uci[4..].chars().next()))
+}
+
+pub fn to_uci(from: u8, to: u8, promotion: Option<char>) -> String {
+    let mut uci = format!("{}{}", to_string(from), to_string(to));
+    if let Some(promotion) = promotion {
+        uci.push(promotion.to_ascii_lowercase());
+    }
+    uci
+}
+
+/// Serialises a 0x88 square in algebraic notation.
+pub fn serialize_square<S: Serializer>(square: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&to_string(*square as u8))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -34,7 +59,7 @@ mod tests {
 
     #[rustfmt::skip]
     const BOARD_128: [u8; 64] = [
-        0,   01,  02,  03,  04,  05,  06,  07,
+        0,   1,   2,   3,   4,   5,   6,   7,
         16,  17,  18,  19,  20,  21,  22,  23,
         32,  33,  34,  35,  36,  37,  38,  39,
         48,  49,  50,  51,  52,  53,  54,  55,
@@ -58,6 +83,15 @@ mod tests {
         }
     }
 
+    #[test]
+    fn uci_notation() {
+        assert_eq!(from_uci("e2e4").unwrap(), (20, 52, None));
+        assert_eq!(from_uci("e7e8q").unwrap(), (100, 116, Some('q')));
+        assert_eq!(to_uci(100, 116, Some('Q')), "e7e8q");
+        assert!(from_uci("e2").is_err());
+        assert!(from_uci("e2e9").is_err());
+    }
+
     #[test]
     fn invalid_algebraic_notation() {
         assert!(from_string("a").is_err());
@@ -25,13 +25,13 @@ mod tests {
     #[test]
     fn test_is_valid_rank() {
         for rank in 0..8 {
-            assert_eq!(super::is_valid(RANK_START + rank), true);
+            assert!(super::is_valid(RANK_START + rank));
         }
     }
 
     #[test]
     fn test_invalid_rank() {
-        assert_eq!(super::is_valid(RANK_START + 9), false);
-        assert_eq!(super::is_valid(RANK_START - 1), false);
+        assert!(!super::is_valid(RANK_START + 9));
+        assert!(!super::is_valid(RANK_START - 1));
     }
 }
@@ -0,0 +1,204 @@
+use serde::Serialize;
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+
+use crate::search::{Score, Search, SearchLimits};
+use crate::ChessEngine;
+
+/// A line of the analysis, for the UI.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisLine {
+    /// The rank of the line, the best line being 1.
+    pub multi_pv: usize,
+    /// The score for the side to move.
+    pub score: Score,
+    pub depth: u32,
+    /// The principal variation in SAN.
+    pub pv: Vec<String>,
+    /// The principal variation in UCI notation.
+    pub pv_uci: Vec<String>,
+    pub nodes: u64,
+}
+
+/// Analyses a copy of the engine's position a few positions at a time, keeping the best
+/// `multi_pv` lines of the last finished iteration for the UI to poll between steps.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct AnalysisSession {
+    /// The position analysed, to write the lines out.
+    position: ChessEngine,
+    /// The copy searched. Between two steps, the moves on the path being searched are made on it.
+    engine: ChessEngine,
+    search: Search,
+    multi_pv: usize,
+    max_depth: u32,
+}
+
+#[wasm_bindgen]
+impl AnalysisSession {
+    /// Searches about `node_budget` more positions and returns whether there is still more
+    /// to search.
+    #[wasm_bindgen(js_name = "step")]
+    pub fn step(&mut self, node_budget: u32) -> bool {
+        self.search.step(&mut self.engine, node_budget.into())
+    }
+
+    /// The lines of the last finished iteration, best first.
+    #[wasm_bindgen(js_name = "lines")]
+    pub fn lines_js(&mut self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.lines()).unwrap()
+    }
+
+    /// The depth of the last finished iteration, 0 before the first one.
+    #[wasm_bindgen(js_name = "depth")]
+    pub fn depth(&self) -> u32 {
+        self.search.depth()
+    }
+
+    /// The positions searched so far.
+    #[wasm_bindgen(js_name = "nodes")]
+    pub fn nodes(&self) -> u64 {
+        self.search.nodes
+    }
+
+    #[wasm_bindgen(js_name = "multiPv")]
+    pub fn multi_pv(&self) -> usize {
+        self.multi_pv
+    }
+
+    /// Changes the number of lines, the search starting over from depth 1.
+    #[wasm_bindgen(js_name = "setMultiPv")]
+    pub fn set_multi_pv(&mut self, multi_pv: usize) {
+        self.multi_pv = multi_pv.max(1);
+        self.start();
+    }
+
+    #[wasm_bindgen(js_name = "fen")]
+    pub fn fen(&self) -> String {
+        self.position.get_fen()
+    }
+}
+
+impl AnalysisSession {
+    pub fn new(engine: &ChessEngine, multi_pv: usize, max_depth: u32) -> Self {
+        let mut session = Self {
+            position: engine.clone(),
+            engine: engine.clone(),
+            search: Search::new(),
+            multi_pv: multi_pv.max(1),
+            max_depth: max_depth.max(1),
+        };
+        session.start();
+        session
+    }
+
+    /// Starts the search over, dropping the lines found so far.
+    fn start(&mut self) {
+        let limits = SearchLimits {
+            depth: Some(self.max_depth),
+            multi_pv: self.multi_pv,
+            ..Default::default()
+        };
+        self.search.start(&mut self.engine, limits);
+    }
+
+    /// The lines of the last finished iteration with their moves written out.
+    pub fn lines(&mut self) -> Vec<AnalysisLine> {
+        let engine = &mut self.position;
+        self.search
+            .lines()
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let mut undos = Vec::new();
+                let mut pv = Vec::new();
+                for &r#move in &line.pv {
+                    pv.push(engine.move_to_san(r#move));
+                    undos.push(engine.make(r#move));
+                }
+                for undo in undos.into_iter().rev() {
+                    engine.unmake(undo);
+                }
+                AnalysisLine {
+                    multi_pv: index + 1,
+                    score: Score::from_internal(line.score),
+                    depth: line.depth,
+                    pv,
+                    pv_uci: line.pv.iter().map(|r#move| r#move.to_uci()).collect(),
+                    nodes: line.nodes,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_analysis_session() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1")
+            .unwrap();
+        let mut session = chess_position.analysis_session(3, 3);
+        assert!(session.lines().is_empty());
+        let mut steps = 0;
+        let mut nodes = 0;
+        while session.step(100) {
+            steps += 1;
+            // a step ends once its budget is spent, within the positions of a single move
+            assert!(session.nodes() - nodes <= 102);
+            nodes = session.nodes();
+            assert!(session
+                .lines()
+                .iter()
+                .all(|line| line.depth == session.depth()));
+            assert_eq!(session.fen(), chess_position.get_fen());
+        }
+        assert!(steps > 3);
+        assert!(!session.step(100));
+        assert_eq!(session.depth(), 3);
+
+        let lines = session.lines();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].multi_pv, 1);
+        assert_eq!(lines[0].score, Score::Mate(1));
+        assert_eq!(lines[0].pv, ["Ra8#"]);
+        assert_eq!(lines[0].pv_uci, ["a1a8"]);
+        assert!(lines[1..]
+            .iter()
+            .all(|line| matches!(line.score, Score::Cp(_)) && line.depth == 3));
+        assert_ne!(lines[1].pv[0], lines[2].pv[0]);
+        assert!(lines[1..].iter().all(|line| line.pv[0] != "Ra8#"));
+        assert_eq!(session.fen(), chess_position.get_fen());
+
+        session.set_multi_pv(1);
+        assert_eq!(session.depth(), 0);
+        while session.step(100) {}
+        assert_eq!(session.lines().len(), 1);
+    }
+
+    #[test]
+    fn test_analysis_few_moves() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, usize)> = vec![
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 0),
+            ("k7/8/8/8/8/8/1Q6/7K b - - 0 1", 1),
+            ("7k/8/8/8/8/8/8/K7 w - - 0 1", 3),
+        ];
+        for (fen, moves) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let mut session = chess_position.analysis_session(5, 2);
+            while session.step(1000) {}
+            let lines = session.lines();
+            assert_eq!(
+                lines.iter().filter(|line| !line.pv.is_empty()).count(),
+                moves,
+                "{fen}"
+            );
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Plays the engine against itself and writes the quiet positions of its games, with their
+//! search scores and results, as training data for the evaluation.
+//!
+//! selfplay [--games N] [--depth N] [--nodes N] [--random-plies N] [--max-plies N]
+//!          [--seed N] [--threads N] [--hash MB] [--output FILE] [--text FILE]
+//!
+//! `--output` writes the 32-byte binary records and `--text` lines of "FEN | score | result";
+//! with neither, the records go to "selfplay.bin".
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+
+use chess_engine::{ChessEngine, SelfPlayConfig};
+
+struct Args {
+    games: u32,
+    config: SelfPlayConfig,
+    seed: u32,
+    threads: String,
+    hash: String,
+    output: Option<String>,
+    text: Option<String>,
+}
+
+fn parse<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing Value for \"{name}\""))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid Value \"{value}\" for \"{name}\""))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        games: 100,
+        config: SelfPlayConfig::default(),
+        seed: 1,
+        threads: "1".to_string(),
+        hash: "16".to_string(),
+        output: None,
+        text: None,
+    };
+    let mut depth_set = false;
+    let mut arguments = std::env::args().skip(1);
+    while let Some(name) = arguments.next() {
+        let value = arguments.next();
+        match name.as_str() {
+            "--games" => args.games = parse(&name, value)?,
+            "--depth" => {
+                args.config.depth = Some(parse(&name, value)?);
+                depth_set = true;
+            }
+            "--nodes" => args.config.nodes = Some(parse(&name, value)?),
+            "--random-plies" => args.config.random_plies = parse(&name, value)?,
+            "--max-plies" => args.config.max_plies = parse(&name, value)?,
+            "--seed" => args.seed = parse(&name, value)?,
+            "--threads" => args.threads = parse(&name, value)?,
+            "--hash" => args.hash = parse(&name, value)?,
+            "--output" => args.output = Some(parse(&name, value)?),
+            "--text" => args.text = Some(parse(&name, value)?),
+            _ => return Err(format!("Unknown Argument \"{name}\"")),
+        }
+    }
+    // a node budget alone searches as deep as it allows
+    if args.config.nodes.is_some() && !depth_set {
+        args.config.depth = None;
+    }
+    if args.output.is_none() && args.text.is_none() {
+        args.output = Some("selfplay.bin".to_string());
+    }
+    Ok(args)
+}
+
+fn create(path: &Option<String>) -> Result<Option<BufWriter<File>>, String> {
+    path.as_ref()
+        .map(|path| {
+            File::create(path)
+                .map(BufWriter::new)
+                .map_err(|err| format!("Cannot Write \"{path}\": {err}"))
+        })
+        .transpose()
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let mut engine = ChessEngine::new();
+    engine.set_option("Threads", &args.threads)?;
+    engine.set_option("Hash", &args.hash)?;
+    engine.set_seed(args.seed);
+    let mut output = create(&args.output)?;
+    let mut text = create(&args.text)?;
+
+    let mut total = 0;
+    for game in 1..=args.games {
+        let positions = engine.self_play_game(args.config);
+        for position in &positions {
+            if let Some(output) = &mut output {
+                let bytes = position.to_bytes()?;
+                output.write_all(&bytes).map_err(|err| err.to_string())?;
+            }
+            if let Some(text) = &mut text {
+                writeln!(text, "{}", position.to_text()).map_err(|err| err.to_string())?;
+            }
+        }
+        total += positions.len();
+        let result = positions.first().map_or("-".to_string(), |position| {
+            format!("{:.1}", position.result)
+        });
+        eprintln!(
+            "game {game}/{}: {} positions, result {result}, {total} in all",
+            args.games,
+            positions.len()
+        );
+    }
+    for writer in [&mut output, &mut text].into_iter().flatten() {
+        writer.flush().map_err(|err| err.to_string())?;
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,99 @@
+//! Tunes the weights of the handcrafted evaluation on positions labelled with the results of
+//! their games and writes them to a weights file the engine loads with `loadWeights`.
+//!
+//! tune POSITIONS... [--weights FILE] [--epochs N] [--learning-rate X] [--output FILE]
+//!
+//! Positions are read from the binary records of `selfplay` when the file ends in ".bin",
+//! else from lines of "FEN | result" or "FEN | score | result". Tuning starts from the
+//! weights of `--weights`, or the built-in ones, and writes to "weights.txt" by default.
+
+use std::str::FromStr;
+
+use chess_engine::{Tuner, Weights};
+
+struct Args {
+    positions: Vec<String>,
+    weights: Option<String>,
+    epochs: u32,
+    learning_rate: f64,
+    output: String,
+}
+
+fn parse<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing Value for \"{name}\""))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid Value \"{value}\" for \"{name}\""))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        positions: Vec::new(),
+        weights: None,
+        epochs: 1000,
+        learning_rate: 1.0,
+        output: "weights.txt".to_string(),
+    };
+    let mut arguments = std::env::args().skip(1);
+    while let Some(name) = arguments.next() {
+        if !name.starts_with("--") {
+            args.positions.push(name);
+            continue;
+        }
+        let value = arguments.next();
+        match name.as_str() {
+            "--weights" => args.weights = Some(parse(&name, value)?),
+            "--epochs" => args.epochs = parse(&name, value)?,
+            "--learning-rate" => args.learning_rate = parse(&name, value)?,
+            "--output" => args.output = parse(&name, value)?,
+            _ => return Err(format!("Unknown Argument \"{name}\"")),
+        }
+    }
+    if args.positions.is_empty() {
+        return Err("Missing Positions".to_string());
+    }
+    Ok(args)
+}
+
+fn run() -> Result<(), String> {
+    let args = parse_args()?;
+    let weights = match &args.weights {
+        Some(path) => Weights::from_file(path)?,
+        None => Weights::default(),
+    };
+    let mut tuner = Tuner::new(weights);
+    tuner.learning_rate = args.learning_rate;
+    for path in &args.positions {
+        let count = tuner.add_file(path)?;
+        eprintln!("{path}: {count} positions");
+    }
+    if tuner.is_empty() {
+        return Err("Missing Positions".to_string());
+    }
+
+    let scale = tuner.fit_scale();
+    eprintln!("scale {scale:.4}, error {:.6}", tuner.error());
+    for epoch in 1..=args.epochs {
+        let error = tuner.step();
+        if epoch % 50 == 0 || epoch == args.epochs {
+            eprintln!("epoch {epoch}: error {error:.6}");
+        }
+    }
+    let error = tuner.error();
+    let text = format!(
+        "# tuned on {} positions, error {error:.6}\n{}",
+        tuner.len(),
+        tuner.weights().to_text()
+    );
+    std::fs::write(&args.output, text)
+        .map_err(|err| format!("Cannot Write Weights \"{}\": {err}", args.output))?;
+    eprintln!("error {error:.6}, weights written to {}", args.output);
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
@@ -4,6 +4,9 @@ const WHITE_KING_MASK: u16 = 0xFF00;
 // The first 0-7 bits are white king, and 8-15 position is for black king
 pub type KingPosition = u16;
 
+/// Both kings off the board, used while a position is being set up.
+pub const NO_KING_POSITION: KingPosition = 0x8888;
+
 pub trait KingPositionTrait {
     fn get_king_position(&self, is_white: bool) -> usize;
     fn set_king_position(&mut self, pos: usize, is_white: bool);
@@ -4,7 +4,7 @@ use std::ops::{Index, IndexMut};
 
 use crate::piece::Piece;
 use crate::{castling::CastlingTrait, fen_trait::FenParser, piece::PieceTrait};
-use king_position::{KingPosition, KingPositionTrait};
+use king_position::{KingPosition, KingPositionTrait, NO_KING_POSITION};
 use utils::abs;
 use utils::is_offboard;
 
@@ -87,7 +87,7 @@ impl FenParser for Board {
         // fen starts with rank 8
         let mut rank = 7;
         let mut file = 0;
-        self.king_position = KingPosition::default();
+        self.king_position = NO_KING_POSITION;
         let mut board = [Piece::Empty; BOARD_SIZE];
 
         for ch in value.chars() {
@@ -183,41 +183,189 @@ impl std::fmt::Display for Board {
 
 impl Board {
     pub fn is_check(&self, white_king: bool) -> bool {
-        self.is_attacked(
-            self.king_position.get_king_position(white_king),
-            !white_king,
-        )
+        self.checker(white_king).is_some()
     }
 
-    pub fn is_insufficient_material(&self) -> bool {
-        let mut white_bishop = false;
-        let mut black_bishop = false;
-        let mut white_knight = false;
-        let mut black_knight = false;
-        let mut white_pieces = 0;
-        let mut black_pieces = 0;
-        for i in 0..BOARD_SIZE {
-            match self[i] {
-                Piece::WhiteBishop => white_bishop = true,
-                Piece::BlackBishop => black_bishop = true,
-                Piece::WhiteKnight => white_knight = true,
-                Piece::BlackKnight => black_knight = true,
-                Piece::WhitePawn | Piece::WhiteRook | Piece::WhiteQueen | Piece::WhiteKing => {
-                    white_pieces += 1
+    /// Returns the square of a piece giving check to the given king, if any.
+    pub fn checker(&self, white_king: bool) -> Option<usize> {
+        let pos = self.king_position.get_king_position(white_king);
+        if is_offboard(pos) {
+            return None;
+        }
+        self.attacker(pos, !white_king)
+    }
+
+    /// Returns the square of the given king, if it is on the board.
+    pub fn king(&self, white_king: bool) -> Option<usize> {
+        let pos = self.king_position.get_king_position(white_king);
+        (!is_offboard(pos)).then_some(pos)
+    }
+
+    /// Lists the squares the piece on `from` could move to, stopping sliders at the first piece.
+    /// The `is_legal_*_move` checks still decide which of them are legal.
+    pub fn candidate_targets(&self, from: usize) -> Vec<usize> {
+        let piece = self.board[from];
+        let (offsets, slides): (&[isize], bool) = if piece.is_knight() {
+            (&[14, 18, 31, 33, -14, -18, -31, -33], false)
+        } else if piece.is_bishop() {
+            (&[15, 17, -15, -17], true)
+        } else if piece.is_rook() {
+            (&[16, 1, -16, -1], true)
+        } else if piece.is_queen() {
+            (&[15, 17, 16, 1, -15, -17, -16, -1], true)
+        } else if piece.is_king() {
+            (&[15, 17, 16, 1, -15, -17, -16, -1, 2, -2], false)
+        } else if piece.is_white() {
+            (&[16, 32, 15, 17], false)
+        } else if piece.is_black() {
+            (&[-16, -32, -15, -17], false)
+        } else {
+            (&[], false)
+        };
+        let mut targets = Vec::new();
+        for &offset in offsets {
+            let mut to = from as isize + offset;
+            while to >= 0 && !is_offboard(to as usize) {
+                targets.push(to as usize);
+                if !slides || !self[to as usize].is_empty() {
+                    break;
+                }
+                to += offset;
+            }
+        }
+        targets
+    }
+
+    /// Lists the squares the piece on `from` attacks, whatever is on them: the diagonal ones
+    /// for pawns and the neighbouring ones for kings.
+    pub fn attacks(&self, from: usize) -> Vec<usize> {
+        let piece = self.board[from];
+        let offsets: &[isize] = if piece.is_king() {
+            &[15, 17, 16, 1, -15, -17, -16, -1]
+        } else if piece.is_pawn() && piece.is_white() {
+            &[15, 17]
+        } else if piece.is_pawn() {
+            &[-15, -17]
+        } else {
+            return self.candidate_targets(from);
+        };
+        offsets
+            .iter()
+            .map(|&offset| from as isize + offset)
+            .filter(|&to| to >= 0 && !is_offboard(to as usize))
+            .map(|to| to as usize)
+            .collect()
+    }
+
+    /// Lists the squares of every piece of colour `attacked_by` that attacks `pos`.
+    pub fn attackers(&self, pos: usize, attacked_by: bool) -> Vec<usize> {
+        (0..BOARD_SIZE)
+            .filter(|&from| !is_offboard(from))
+            .filter(|&from| !self[from].is_empty() && self[from].is_white() == attacked_by)
+            .filter(|&from| self.attacks(from).contains(&pos))
+            .collect()
+    }
+
+    /// Places `piece` on `pos`, replacing whatever was there.
+    pub fn put_piece(&mut self, pos: usize, piece: Piece) {
+        let replaced = self.board[pos];
+        self.board[pos] = piece;
+        if replaced.is_king() || piece.is_king() {
+            self.update_king_position();
+        }
+    }
+
+    /// Removes the piece on `pos` and returns it.
+    pub fn remove_piece(&mut self, pos: usize) -> Piece {
+        let removed = self.board[pos];
+        self.put_piece(pos, Piece::Empty);
+        removed
+    }
+
+    /// Removes every piece, including the captured pieces.
+    pub fn clear(&mut self) {
+        self.board = [Piece::Empty; BOARD_SIZE];
+        self.king_position = NO_KING_POSITION;
+    }
+
+    /// Lists the problems that make the piece placement unplayable.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for white in [true, false] {
+            let color = if white { "white" } else { "black" };
+            let squares = (0..BOARD_SIZE)
+                .filter(|&pos| !is_offboard(pos))
+                .filter(|&pos| !self[pos].is_empty() && self[pos].is_white() == white);
+            let (mut kings, mut pawns, mut pieces) = (0, 0, 0);
+            for pos in squares {
+                pieces += 1;
+                if self[pos].is_king() {
+                    kings += 1;
                 }
-                Piece::BlackPawn | Piece::BlackRook | Piece::BlackQueen | Piece::BlackKing => {
-                    black_pieces += 1
+                if self[pos].is_pawn() {
+                    pawns += 1;
                 }
+            }
+            if kings != 1 {
+                problems.push(format!("Expected 1 {color} king, Found {kings}"));
+            }
+            if pawns > 8 {
+                problems.push(format!("Too many {color} pawns \"{pawns}\""));
+            }
+            if pieces > 16 {
+                problems.push(format!("Too many {color} pieces \"{pieces}\""));
+            }
+        }
+        for pos in (0..8).chain(112..120) {
+            if self[pos].is_pawn() {
+                problems.push(format!(
+                    "Pawn on first or last rank at \"{}\"",
+                    crate::algebric_notation::to_string(pos as u8)
+                ));
+            }
+        }
+        problems
+    }
+
+    fn update_king_position(&mut self) {
+        self.king_position = NO_KING_POSITION;
+        for pos in (0..BOARD_SIZE).filter(|&pos| !is_offboard(pos)) {
+            if self[pos].is_king() {
+                self.king_position
+                    .set_king_position(pos, self[pos].is_white());
+            }
+        }
+    }
+
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut white_bishops = 0;
+        let mut black_bishops = 0;
+        let mut white_knights = 0;
+        let mut black_knights = 0;
+        let mut other_pieces = 0;
+        // captured pieces are kept off the board and do not count
+        for i in (0..BOARD_SIZE).filter(|&i| !is_offboard(i)) {
+            match self[i] {
+                Piece::WhiteBishop => white_bishops += 1,
+                Piece::BlackBishop => black_bishops += 1,
+                Piece::WhiteKnight => white_knights += 1,
+                Piece::BlackKnight => black_knights += 1,
+                Piece::WhitePawn
+                | Piece::WhiteRook
+                | Piece::WhiteQueen
+                | Piece::BlackPawn
+                | Piece::BlackRook
+                | Piece::BlackQueen => other_pieces += 1,
                 _ => {}
             }
         }
-        white_pieces <= 1 && black_pieces <= 1 // only kings on the board
-        || white_pieces == 1 && black_pieces == 0 && (white_bishop || white_knight) // only white king and one minor piece
-        || white_pieces == 0 && black_pieces == 1 && (black_bishop || black_knight) // only black king and one minor piece
-        || white_pieces == 1 && black_pieces == 1 && white_knight && black_knight // only kings and knights
-        || white_pieces == 1 && black_pieces == 1 && white_bishop && black_bishop // only kings and bishops
-        || white_pieces == 2 && black_pieces == 1 && white_bishop // only kings and bishops
-        || white_pieces == 1 && black_pieces == 2 && black_bishop // only kings and bishops
+        let white_minors = white_bishops + white_knights;
+        let black_minors = black_bishops + black_knights;
+        other_pieces == 0
+            && (white_minors + black_minors <= 1 // only kings, or one minor piece
+                || white_knights == 1 && black_knights == 1 && white_minors + black_minors == 2 // only kings and knights
+                || white_bishops == 1 && black_bishops == 1 && white_minors + black_minors == 2)
+        // only kings and bishops
     }
 
     /// This function doesn't check if move is valid, it just moves the piece
@@ -270,7 +418,11 @@ impl Board {
                     // when pawn moves one square forward
                     16 => self.board[to].is_empty(),
                     // when pawn moves two squares forward it should be in the second rank
-                    32 => self.board[to].is_empty() && (16..24).contains(&from),
+                    32 => {
+                        self.board[to].is_empty()
+                            && self.board[from + 16].is_empty()
+                            && (16..24).contains(&from)
+                    }
                     // when pawn captures there should be a piece in the destination square
                     15 | 17 => {
                         self.board[to].is_black()
@@ -285,7 +437,11 @@ impl Board {
                 }
                 match from - to {
                     16 => self.board[to].is_empty(),
-                    32 => self.board[to].is_empty() && (96..104).contains(&from),
+                    32 => {
+                        self.board[to].is_empty()
+                            && self.board[from - 16].is_empty()
+                            && (96..104).contains(&from)
+                    }
                     15 | 17 => {
                         self.board[to].is_white()
                             || (en_passant.is_some() && en_passant.unwrap() as usize == to)
@@ -304,7 +460,11 @@ impl Board {
                 return !self.is_attacked(to, !self.board[from].is_white());
             }
 
-            if diff == 2 && self.board[to].is_empty() {
+            // castling is not allowed out of check
+            if diff == 2
+                && self.board[to].is_empty()
+                && !self.is_attacked(from, !self.board[from].is_white())
+            {
                 if self.board[from].is_white() && from == 4 {
                     if self.board[5].is_empty() && to == 6 && castling.can_white_king_castle() {
                         return !self.is_attacked(5, false) && !self.is_attacked(6, false);
@@ -335,6 +495,42 @@ impl Board {
         false
     }
 
+    /// Returns the first occupied square on the line between `from` and `to`
+    /// when the sliding piece on `from` moves along a line that reaches `to`.
+    pub fn blocking_square(&self, from: usize, to: usize) -> Option<usize> {
+        let piece = self.board[from];
+        let offsets: &[usize] = if piece.is_bishop() {
+            &BISHOP_OFFSET
+        } else if piece.is_rook() {
+            &ROOK_OFFSET
+        } else if piece.is_queen() {
+            &[15, 17, 16, 1]
+        } else {
+            &[]
+        };
+        for &offset in offsets {
+            if !abs(from, to).is_multiple_of(offset) {
+                continue;
+            }
+            let path: Vec<usize> = (1..abs(from, to) / offset)
+                .map(|step| {
+                    if to > from {
+                        from + step * offset
+                    } else {
+                        from - step * offset
+                    }
+                })
+                .collect();
+            if path.iter().any(|&square| is_offboard(square)) {
+                continue;
+            }
+            if let Some(&square) = path.iter().find(|&&square| !self[square].is_empty()) {
+                return Some(square);
+            }
+        }
+        None
+    }
+
     pub fn is_legal_queen_move(&self, from: usize, to: usize) -> bool {
         if self.board[from].is_queen() {
             return BISHOP_OFFSET
@@ -352,7 +548,7 @@ impl Board {
     }
 
     fn check_if_not_blocked(&self, from: usize, to: usize, offset: usize) -> bool {
-        if abs(from, to) % offset != 0 {
+        if !abs(from, to).is_multiple_of(offset) {
             return false;
         }
         if from > to {
@@ -372,18 +568,25 @@ impl Board {
     }
 
     fn is_attacked(&self, pos: usize, attacked_by: bool) -> bool {
+        self.attacker(pos, attacked_by).is_some()
+    }
+
+    /// Returns the square of a piece of colour `attacked_by` that attacks `pos`, if any.
+    pub fn attacker(&self, pos: usize, attacked_by: bool) -> Option<usize> {
         if attacked_by {
-            let is_attacked = [15, 17].into_iter().any(|offset| {
-                pos.checked_sub(offset).is_some()
-                    && self.is_piece_at(pos - offset, Piece::WhitePawn)
+            let attacker = [15, 17].into_iter().find_map(|offset| {
+                pos.checked_sub(offset)
+                    .filter(|&from| self.is_piece_at(from, Piece::WhitePawn))
             });
-            if is_attacked {
-                return true;
+            if attacker.is_some() {
+                return attacker;
+            }
+        } else {
+            for offset in [15, 17] {
+                if self.is_piece_at(pos + offset, Piece::BlackPawn) {
+                    return Some(pos + offset);
+                }
             }
-        } else if self.is_piece_at(pos + 15, Piece::BlackPawn)
-            || self.is_piece_at(pos + 17, Piece::BlackPawn)
-        {
-            return true;
         }
 
         let enemy_knight = if attacked_by {
@@ -393,11 +596,11 @@ impl Board {
         };
 
         for &offset in KNIGHT_OFFSET.iter() {
-            if self.is_piece_at(pos + offset, enemy_knight)
-                || (pos.checked_sub(offset).is_some()
-                    && self.is_piece_at(pos - offset, enemy_knight))
-            {
-                return true;
+            if self.is_piece_at(pos + offset, enemy_knight) {
+                return Some(pos + offset);
+            }
+            if pos.checked_sub(offset).is_some() && self.is_piece_at(pos - offset, enemy_knight) {
+                return Some(pos - offset);
             }
         }
 
@@ -416,7 +619,7 @@ impl Board {
             let mut t_pos = pos + offset;
             while !utils::is_offboard(t_pos) {
                 if self.is_piece_at(t_pos, enemy_bishop) || self.is_piece_at(t_pos, enemy_queen) {
-                    return true;
+                    return Some(t_pos);
                 }
                 if !self.is_piece_at(t_pos, Piece::Empty) {
                     break;
@@ -430,7 +633,7 @@ impl Board {
                     break;
                 }
                 if self.is_piece_at(t_pos, enemy_bishop) || self.is_piece_at(t_pos, enemy_queen) {
-                    return true;
+                    return Some(t_pos);
                 }
                 if !self.is_piece_at(t_pos, Piece::Empty) {
                     break;
@@ -445,10 +648,11 @@ impl Board {
         };
 
         for &offset in KING_OFFSET.iter() {
-            if self.is_piece_at(pos + offset, enemy_king)
-                || (pos.checked_sub(offset).is_some() && self.is_piece_at(pos - offset, enemy_king))
-            {
-                return true;
+            if self.is_piece_at(pos + offset, enemy_king) {
+                return Some(pos + offset);
+            }
+            if pos.checked_sub(offset).is_some() && self.is_piece_at(pos - offset, enemy_king) {
+                return Some(pos - offset);
             }
         }
 
@@ -462,7 +666,7 @@ impl Board {
             let mut t_pos = pos + offset;
             while !utils::is_offboard(t_pos) {
                 if self.is_piece_at(t_pos, enemy_rook) || self.is_piece_at(t_pos, enemy_queen) {
-                    return true;
+                    return Some(t_pos);
                 }
                 if !self.is_piece_at(t_pos, Piece::Empty) {
                     break;
@@ -476,14 +680,14 @@ impl Board {
                     break;
                 }
                 if self.is_piece_at(t_pos, enemy_rook) || self.is_piece_at(t_pos, enemy_queen) {
-                    return true;
+                    return Some(t_pos);
                 }
                 if !self.is_piece_at(t_pos, Piece::Empty) {
                     break;
                 }
             }
         }
-        false
+        None
     }
 }
 
@@ -523,7 +727,7 @@ mod tests {
         let mut board = Board::default();
         for fen in check_fens {
             board.parse_fen(fen).unwrap();
-            assert_eq!(board.is_check(true), true, "FEN: {}", fen);
+            assert!(board.is_check(true), "FEN: {}", fen);
         }
     }
 
@@ -533,7 +737,119 @@ mod tests {
         let mut board = Board::default();
         for fen in check_fens {
             board.parse_fen(fen).unwrap();
-            assert_eq!(board.is_check(true), false);
+            assert!(!board.is_check(true));
+        }
+    }
+
+    #[test]
+    fn test_pawn_double_push() {
+        let mut board = Board::default();
+        let arr: Vec<(&str, usize, usize, bool)> = vec![
+            (
+                "rnbqkbnr/pppppppp/8/8/8/4n3/PPPPPPPP/RNBQKBNR",
+                20,
+                52,
+                false,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/4n3/PPPPPPPP/RNBQKBNR",
+                19,
+                51,
+                true,
+            ),
+            (
+                "rnbqkbnr/pppppppp/4N3/8/8/8/PPPPPPPP/RNBQKBNR",
+                100,
+                68,
+                false,
+            ),
+            (
+                "rnbqkbnr/pppppppp/4N3/8/8/8/PPPPPPPP/RNBQKBNR",
+                99,
+                67,
+                true,
+            ),
+        ];
+        for (fen, from, to, is_legal) in arr {
+            board.parse_fen(fen).unwrap();
+            assert_eq!(
+                board.is_legal_pawn_move(from, to, None),
+                is_legal,
+                "{}->{}| {}",
+                from,
+                to,
+                fen
+            );
         }
     }
+
+    #[test]
+    fn test_insufficient_material() {
+        let mut board = Board::default();
+        let arr: Vec<(&str, bool)> = vec![
+            ("4k3/8/8/8/8/8/8/4K3", true),
+            ("4k3/8/8/8/8/8/8/2B1K3", true),
+            ("4k3/8/8/8/8/8/8/4K1n1", true),
+            ("4kn2/8/8/8/8/8/8/4K1N1", true),
+            ("2b1k3/8/8/8/8/8/8/4KB2", true),
+            ("4k3/8/8/8/8/8/8/2B1KN2", false),
+            ("4k3/8/8/8/8/8/8/2B1KB2", false),
+            ("4k3/8/8/8/8/8/8/4KNN1", false),
+            ("4kb2/8/8/8/8/8/8/4K1N1", false),
+            ("4k3/8/8/8/8/8/7P/4K3", false),
+            ("4k3/8/8/8/8/8/8/R3K3", false),
+        ];
+        for (fen, is_insufficient) in arr {
+            board.parse_fen(fen).unwrap();
+            assert_eq!(board.is_insufficient_material(), is_insufficient, "{fen}");
+        }
+
+        // a captured rook is kept off the board
+        board.parse_fen("4k3/8/8/8/8/8/8/r1B1K3").unwrap();
+        board.r#move(2, 0);
+        assert!(board.is_insufficient_material());
+    }
+
+    #[test]
+    fn test_put_and_remove_king() {
+        let mut board = Board::default();
+        board.clear();
+        assert!(!board.is_check(true));
+        board.put_piece(36, Piece::WhiteKing);
+        board.put_piece(38, Piece::BlackRook);
+        assert!(board.is_check(true));
+        board.put_piece(36, Piece::WhiteQueen);
+        assert!(!board.is_check(true));
+        board.put_piece(6, Piece::WhiteKing);
+        assert!(board.is_check(true));
+        assert_eq!(board.remove_piece(38), Piece::BlackRook);
+        assert!(!board.is_check(true));
+    }
+
+    #[test]
+    fn test_attackers() {
+        let mut board = Board::default();
+        board.parse_fen("4k3/8/8/3p4/4N3/8/1B6/R3K3").unwrap();
+        let arr: Vec<(usize, bool, Vec<usize>)> = vec![
+            (85, true, vec![17, 52]),
+            (52, false, vec![67]),
+            (0, true, vec![17]),
+            (116, true, vec![]),
+        ];
+        for (pos, attacked_by, attackers) in arr {
+            assert_eq!(board.attackers(pos, attacked_by), attackers, "{pos}");
+        }
+        assert_eq!(board.attacks(67), vec![52, 50]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut board = Board::default();
+        assert!(board.validate().is_empty());
+        board.parse_fen("P3k3/8/8/8/8/8/8/8").unwrap();
+        let problems = board.validate();
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("Expected 1 white king, Found 0"));
+        assert!(problems[1].contains("Pawn on first or last rank at \"a8\""));
+    }
 }
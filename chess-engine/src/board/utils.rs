@@ -4,11 +4,7 @@ pub fn is_offboard(pos: usize) -> bool {
 }
 
 pub fn abs(a: usize, b: usize) -> usize {
-    if a > b {
-        a - b
-    } else {
-        b - a
-    }
+    a.abs_diff(b)
 }
 
 #[cfg(test)]
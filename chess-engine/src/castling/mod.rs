@@ -8,7 +8,7 @@ const WHITE_QUEEN_CASTLING: u8 = 1 << 1;
 const BLACK_KING_CASTLING: u8 = 1 << 2;
 const BLACK_QUEEN_CASTLING: u8 = 1 << 3;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Castling(pub u8);
 
 impl CastlingTrait for Castling {
@@ -126,83 +126,83 @@ mod tests {
     fn test_no_castling() {
         let mut castling = Castling::default();
         castling.parse_fen("-").unwrap();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), false);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(!castling.can_black_queen_castle());
     }
 
     #[test]
     fn test_white_king_castling() {
         let mut castling = Castling::default();
         castling.parse_fen("K").unwrap();
-        assert_eq!(castling.can_white_king_castle(), true);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), false);
+        assert!(castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(!castling.can_black_queen_castle());
     }
 
     #[test]
     fn test_white_queen_castling() {
         let mut castling = Castling::default();
         castling.parse_fen("Q").unwrap();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), true);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), false);
+        assert!(!castling.can_white_king_castle());
+        assert!(castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(!castling.can_black_queen_castle());
     }
 
     #[test]
     fn test_black_king_castling() {
         let mut castling = Castling::default();
         castling.parse_fen("k").unwrap();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), true);
-        assert_eq!(castling.can_black_queen_castle(), false);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(castling.can_black_king_castle());
+        assert!(!castling.can_black_queen_castle());
     }
 
     #[test]
     fn test_black_queen_castling() {
         let mut castling = Castling::default();
         castling.parse_fen("q").unwrap();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), true);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(castling.can_black_queen_castle());
     }
 
     #[test]
     fn test_all_castling() {
         let mut castling = Castling::default();
-        assert_eq!(castling.can_white_king_castle(), true);
-        assert_eq!(castling.can_white_queen_castle(), true);
-        assert_eq!(castling.can_black_king_castle(), true);
-        assert_eq!(castling.can_black_queen_castle(), true);
+        assert!(castling.can_white_king_castle());
+        assert!(castling.can_white_queen_castle());
+        assert!(castling.can_black_king_castle());
+        assert!(castling.can_black_queen_castle());
 
         castling.reset_white_king_castling();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), true);
-        assert_eq!(castling.can_black_king_castle(), true);
-        assert_eq!(castling.can_black_queen_castle(), true);
+        assert!(!castling.can_white_king_castle());
+        assert!(castling.can_white_queen_castle());
+        assert!(castling.can_black_king_castle());
+        assert!(castling.can_black_queen_castle());
 
         castling.reset_white_queen_castling();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), true);
-        assert_eq!(castling.can_black_queen_castle(), true);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(castling.can_black_king_castle());
+        assert!(castling.can_black_queen_castle());
 
         castling.reset_black_king_castling();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), true);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(castling.can_black_queen_castle());
 
         castling.reset_black_queen_castling();
-        assert_eq!(castling.can_white_king_castle(), false);
-        assert_eq!(castling.can_white_queen_castle(), false);
-        assert_eq!(castling.can_black_king_castle(), false);
-        assert_eq!(castling.can_black_queen_castle(), false);
+        assert!(!castling.can_white_king_castle());
+        assert!(!castling.can_white_queen_castle());
+        assert!(!castling.can_black_king_castle());
+        assert!(!castling.can_black_queen_castle());
     }
 
     #[test]
@@ -0,0 +1,34 @@
+use crate::algebric_notation;
+use crate::piece::Piece;
+
+/// A move between two 0x88 squares, with the piece a pawn promotes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub from: usize,
+    pub to: usize,
+    pub promotion: Option<Piece>,
+}
+
+impl Move {
+    pub fn new(from: usize, to: usize, promotion: Option<Piece>) -> Self {
+        Self {
+            from,
+            to,
+            promotion,
+        }
+    }
+
+    pub fn to_uci(self) -> String {
+        algebric_notation::to_uci(
+            self.from as u8,
+            self.to as u8,
+            self.promotion.map(char::from),
+        )
+    }
+}
+
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_uci())
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::Serialize;
+
+use crate::pgn;
+use crate::ChessEngine;
+
+/// Tab separated ECO code, opening name, variation and moves, most general lines first.
+const ECO_TABLE: &str = include_str!("eco.tsv");
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+    pub variation: Option<String>,
+    pub moves: String,
+}
+
+impl std::fmt::Display for Opening {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.variation {
+            Some(variation) => write!(f, "{}: {}", self.name, variation),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Openings indexed by the Polyglot key of the position their moves reach,
+/// so a line is recognised however the game transposed into it.
+#[derive(Debug, Default)]
+pub struct EcoTable {
+    openings: Vec<Opening>,
+    positions: HashMap<u64, usize>,
+}
+
+impl EcoTable {
+    /// The table embedded in the crate.
+    pub fn embedded() -> &'static EcoTable {
+        static TABLE: OnceLock<EcoTable> = OnceLock::new();
+        TABLE.get_or_init(|| EcoTable::parse(ECO_TABLE).expect("Invalid embedded ECO table"))
+    }
+
+    pub fn parse(tsv: &str) -> Result<Self, String> {
+        let mut table = EcoTable::default();
+        for line in tsv.lines().filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [eco, name, variation, moves] = fields[..] else {
+                return Err(format!("Invalid ECO Line \"{line}\""));
+            };
+            let mut engine = ChessEngine::new();
+            for game in pgn::parse_pgn(moves)? {
+                for san in &game.moves {
+                    let r#move = engine
+                        .parse_san(san)
+                        .map_err(|err| format!("{err} in ECO line \"{line}\""))?;
+                    engine.make(r#move);
+                }
+            }
+            // a later line reaching the same position is only another name for it
+            table
+                .positions
+                .entry(engine.polyglot_key())
+                .or_insert(table.openings.len());
+            table.openings.push(Opening {
+                eco: eco.to_string(),
+                name: name.to_string(),
+                variation: (!variation.is_empty()).then(|| variation.to_string()),
+                moves: moves.to_string(),
+            });
+        }
+        Ok(table)
+    }
+
+    pub fn lookup(&self, key: u64) -> Option<&Opening> {
+        self.positions.get(&key).map(|&index| &self.openings[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_table() {
+        let table = EcoTable::embedded();
+        assert!(table.openings.len() > 100);
+        let najdorf = table
+            .openings
+            .iter()
+            .find(|opening| opening.eco == "B90")
+            .unwrap();
+        assert_eq!(najdorf.to_string(), "Sicilian Defense: Najdorf Variation");
+        assert_eq!(
+            table.lookup(0x463b96181691fc9c),
+            None,
+            "the starting position has no name"
+        );
+    }
+
+    #[test]
+    fn test_invalid_table() {
+        assert!(EcoTable::parse("A00\tPolish Opening\t1. b4").is_err());
+        assert!(EcoTable::parse("A00\tPolish Opening\t\t1. b5").is_err());
+    }
+}
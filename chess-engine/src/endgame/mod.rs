@@ -0,0 +1,748 @@
+use std::sync::{Mutex, OnceLock};
+
+use crate::board::Board;
+use crate::castling::CastlingTrait;
+use crate::chess_move::Move;
+use crate::piece::{Piece, PieceTrait};
+use crate::utils;
+use crate::ChessEngine;
+
+/// Marks positions that are neither won nor lost, and entries that are never used.
+const DRAW: u8 = u8::MAX;
+/// A count of remaining moves meaning the side to move has a drawing way out.
+const ESCAPE: u8 = u8::MAX;
+/// The a1-d1-d4 triangle the strong king is kept in for endings without pawns.
+const TRIANGLE: [usize; 10] = [0, 1, 2, 3, 9, 10, 11, 18, 19, 27];
+
+/// The tables built so far, see [`Endgame::build`].
+static TABLES: [OnceLock<EndgameTable>; 4] = [const { OnceLock::new() }; 4];
+/// The tables being built.
+static BUILDERS: [Mutex<Option<TableBuilder>>; 4] = [const { Mutex::new(None) }; 4];
+
+/// A basic ending of a lone king against a king with these pieces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Endgame {
+    Kqk,
+    Krk,
+    Kpk,
+    Kbnk,
+}
+
+impl Endgame {
+    const ALL: [Endgame; 4] = [Endgame::Kqk, Endgame::Krk, Endgame::Kpk, Endgame::Kbnk];
+
+    /// The pieces of the strong side besides its king, as white pieces.
+    fn pieces(self) -> &'static [Piece] {
+        match self {
+            Endgame::Kqk => &[Piece::WhiteQueen],
+            Endgame::Krk => &[Piece::WhiteRook],
+            Endgame::Kpk => &[Piece::WhitePawn],
+            Endgame::Kbnk => &[Piece::WhiteBishop, Piece::WhiteKnight],
+        }
+    }
+
+    fn has_pawn(self) -> bool {
+        self == Endgame::Kpk
+    }
+
+    /// The tables whose mates a promotion leads to.
+    fn dependencies(self) -> &'static [Endgame] {
+        match self {
+            Endgame::Kpk => &[Endgame::Kqk, Endgame::Krk],
+            _ => &[],
+        }
+    }
+
+    /// The table, once built.
+    pub fn table(self) -> Option<&'static EndgameTable> {
+        TABLES[self as usize].get()
+    }
+
+    /// Builds more positions of the table, after those of the tables it needs, taking them
+    /// out of `budget`, and returns whether there is still more to build.
+    pub fn build(self, budget: &mut u64) -> bool {
+        for dependency in self.dependencies() {
+            if dependency.build(budget) {
+                return true;
+            }
+        }
+        if self.table().is_some() {
+            return false;
+        }
+        let mut builder = BUILDERS[self as usize].lock().unwrap();
+        let done = builder
+            .get_or_insert_with(|| TableBuilder::new(self))
+            .step(budget);
+        if done {
+            let table = builder.take().unwrap().finish();
+            TABLES[self as usize].get_or_init(|| table);
+        }
+        !done
+    }
+}
+
+/// A position of a table: the side to move, then the white king, the black king and
+/// the other white pieces, on squares numbered a1 = 0 to h8 = 63.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    white_to_move: bool,
+    squares: [usize; 4],
+    len: usize,
+}
+
+impl Placement {
+    fn board(&self, endgame: Endgame) -> Board {
+        let mut board = Board::default();
+        board.clear();
+        board.put_piece(to_0x88(self.squares[0]), Piece::WhiteKing);
+        board.put_piece(to_0x88(self.squares[1]), Piece::BlackKing);
+        for (i, &piece) in endgame.pieces().iter().enumerate() {
+            board.put_piece(to_0x88(self.squares[2 + i]), piece);
+        }
+        board
+    }
+
+    fn with(&self, index: usize, square: usize, white_to_move: bool) -> Self {
+        let mut placement = *self;
+        placement.squares[index] = square;
+        placement.white_to_move = white_to_move;
+        placement
+    }
+}
+
+fn to_0x88(square: usize) -> usize {
+    utils::convert_postion_to_0x88(square)
+}
+
+fn from_0x88(pos: usize) -> usize {
+    (pos >> 4) * 8 + (pos & 7)
+}
+
+fn king_squares(square: usize) -> impl Iterator<Item = usize> {
+    let (rank, file) = ((square / 8) as i32, (square % 8) as i32);
+    (-1..=1)
+        .flat_map(move |dr| (-1..=1).map(move |df| (rank + dr, file + df)))
+        .filter(move |&(r, f)| (r, f) != (rank, file) && (0..8).contains(&r) && (0..8).contains(&f))
+        .map(|(r, f)| (r * 8 + f) as usize)
+}
+
+/// Exact distances to mate for every position of a basic ending, in plies,
+/// worked backwards from the mates with the engine's own attack and move rules.
+#[derive(Debug)]
+pub struct EndgameTable {
+    endgame: Endgame,
+    dtm: Vec<u8>,
+}
+
+impl EndgameTable {
+    fn size(endgame: Endgame) -> usize {
+        let kings = if endgame.has_pawn() {
+            64
+        } else {
+            TRIANGLE.len()
+        };
+        2 * kings * 64usize.pow(1 + endgame.pieces().len() as u32)
+    }
+
+    /// The entry of the placement, or None if its white king is outside the stored area.
+    fn raw_index(endgame: Endgame, placement: &Placement) -> Option<usize> {
+        let king = if endgame.has_pawn() {
+            placement.squares[0]
+        } else {
+            TRIANGLE
+                .iter()
+                .position(|&square| square == placement.squares[0])?
+        };
+        let mut index = king;
+        for &square in &placement.squares[1..placement.len] {
+            index = index * 64 + square;
+        }
+        Some(index * 2 + placement.white_to_move as usize)
+    }
+
+    fn decode(endgame: Endgame, mut index: usize) -> Placement {
+        let len = 2 + endgame.pieces().len();
+        let mut placement = Placement {
+            white_to_move: index % 2 == 1,
+            squares: [0; 4],
+            len,
+        };
+        index /= 2;
+        for i in (1..len).rev() {
+            placement.squares[i] = index % 64;
+            index /= 64;
+        }
+        placement.squares[0] = if endgame.has_pawn() {
+            index
+        } else {
+            TRIANGLE[index]
+        };
+        placement
+    }
+
+    /// The smallest entry among the mirror images of the placement, so that
+    /// symmetric positions share one entry.
+    fn index(endgame: Endgame, placement: &Placement) -> usize {
+        // (flip files, flip ranks, swap ranks and files), pawns only allow the first
+        let transforms = if endgame.has_pawn() { 2 } else { 8 };
+        (0..transforms)
+            .filter_map(|transform| {
+                let mut image = *placement;
+                for square in image.squares[..image.len].iter_mut() {
+                    let (mut rank, mut file) = (*square / 8, *square % 8);
+                    if transform & 1 != 0 {
+                        file = 7 - file;
+                    }
+                    if transform & 2 != 0 {
+                        rank = 7 - rank;
+                    }
+                    if transform & 4 != 0 {
+                        (rank, file) = (file, rank);
+                    }
+                    *square = rank * 8 + file;
+                }
+                Self::raw_index(endgame, &image)
+            })
+            .min()
+            .unwrap()
+    }
+
+    /// Whether the placement is a legal position: no shared squares, no pawn on the
+    /// first or last rank and the side that just moved not in check.
+    fn is_legal(endgame: Endgame, placement: &Placement, board: &Board) -> bool {
+        let squares = &placement.squares[..placement.len];
+        if squares
+            .iter()
+            .enumerate()
+            .any(|(i, square)| squares[i + 1..].contains(square))
+        {
+            return false;
+        }
+        if endgame.has_pawn() && matches!(squares[2] / 8, 0 | 7) {
+            return false;
+        }
+        if placement.white_to_move {
+            board.attacker(to_0x88(squares[1]), true).is_none()
+        } else {
+            board.attacker(to_0x88(squares[0]), false).is_none()
+        }
+    }
+
+    /// The distinct entries black can move to, or None if black can take a piece and draw.
+    fn black_successors(
+        endgame: Endgame,
+        placement: &Placement,
+        board: &Board,
+    ) -> Option<Vec<usize>> {
+        let mut without_king = *board;
+        without_king.remove_piece(to_0x88(placement.squares[1]));
+        let mut successors = Vec::new();
+        for target in king_squares(placement.squares[1]) {
+            let pos = to_0x88(target);
+            if without_king.attacker(pos, true).is_some() {
+                continue;
+            }
+            if without_king[pos].is_white() {
+                return None;
+            }
+            successors.push(Self::index(endgame, &placement.with(1, target, true)));
+        }
+        successors.sort_unstable();
+        successors.dedup();
+        Some(successors)
+    }
+
+    /// The distinct white to move entries that lead to this black to move placement.
+    fn white_predecessors(endgame: Endgame, placement: &Placement, board: &Board) -> Vec<usize> {
+        let mut predecessors = Vec::new();
+        for index in (0..placement.len).filter(|&index| index != 1) {
+            let square = placement.squares[index];
+            let pos = to_0x88(square);
+            let piece = board[pos];
+            let origins: Vec<usize> = if piece.is_pawn() {
+                let mut origins = Vec::new();
+                if square / 8 >= 2 && board[pos - 16].is_empty() {
+                    origins.push(square - 8);
+                    if square / 8 == 3 && board[pos - 32].is_empty() {
+                        origins.push(square - 16);
+                    }
+                }
+                origins
+            } else {
+                // pieces move back the way they came, kings never castled here
+                board
+                    .candidate_targets(pos)
+                    .into_iter()
+                    .filter(|&target| board[target].is_empty())
+                    .filter(|&target| !piece.is_king() || target.abs_diff(pos) != 2)
+                    .map(from_0x88)
+                    .collect()
+            };
+            for origin in origins {
+                let predecessor = placement.with(index, origin, true);
+                let mut before = *board;
+                before.remove_piece(pos);
+                before.put_piece(to_0x88(origin), piece);
+                if Self::is_legal(endgame, &predecessor, &before) {
+                    predecessors.push(Self::index(endgame, &predecessor));
+                }
+            }
+        }
+        predecessors.sort_unstable();
+        predecessors.dedup();
+        predecessors
+    }
+
+    /// The distinct black to move entries that lead to this white to move placement.
+    fn black_predecessors(endgame: Endgame, placement: &Placement) -> Vec<usize> {
+        let mut predecessors: Vec<usize> = king_squares(placement.squares[1])
+            .filter(|origin| !placement.squares[..placement.len].contains(origin))
+            .filter(|&origin| king_squares(origin).all(|square| square != placement.squares[0]))
+            .map(|origin| Self::index(endgame, &placement.with(1, origin, false)))
+            .collect();
+        predecessors.sort_unstable();
+        predecessors.dedup();
+        predecessors
+    }
+
+    /// The fastest mate a promotion leads to, in plies from before the promotion.
+    fn promotion_dtm(placement: &Placement) -> Option<u8> {
+        let pawn = placement.squares[2];
+        if pawn / 8 != 6 || placement.squares[..2].contains(&(pawn + 8)) {
+            return None;
+        }
+        // a rook promotion avoids some stalemates, minor pieces cannot mate
+        [Endgame::Kqk, Endgame::Krk]
+            .iter()
+            .filter_map(|&endgame| {
+                let promoted = placement.with(2, pawn + 8, false);
+                let table = endgame
+                    .table()
+                    .expect("the tables promotions lead to built first");
+                let dtm = table.dtm[Self::index(endgame, &promoted)];
+                (dtm != DRAW).then(|| dtm + 1)
+            })
+            .min()
+    }
+
+    /// Plies to mate for the side to move, positive when it mates and negative (or 0 when
+    /// already mated) when it gets mated. None for a draw.
+    fn probe(&self, placement: &Placement) -> Option<i32> {
+        match self.dtm[Self::index(self.endgame, placement)] {
+            DRAW => None,
+            plies if placement.white_to_move => Some(plies as i32),
+            plies => Some(-(plies as i32)),
+        }
+    }
+}
+
+/// An [`EndgameTable`] being worked out a few positions at a time, so that building the
+/// larger tables can share a thread with a UI.
+#[derive(Debug)]
+struct TableBuilder {
+    endgame: Endgame,
+    dtm: Vec<u8>,
+    /// The moves left to black before every one of them is known to lose.
+    counts: Vec<u8>,
+    /// The next entry to set up, then the size of the table once they all are.
+    index: usize,
+    /// The white to move entries a promotion wins from, by plies to mate.
+    promotions: Vec<Vec<usize>>,
+    /// The plies to mate of the entries being found.
+    ply: usize,
+    /// The entries found at the ply before, whose predecessors are looked at.
+    frontier: Vec<usize>,
+    /// The position in `frontier` of the next entry to look at.
+    cursor: usize,
+    /// The entries found so far at `ply`.
+    next: Vec<usize>,
+}
+
+impl TableBuilder {
+    fn new(endgame: Endgame) -> Self {
+        let size = EndgameTable::size(endgame);
+        Self {
+            endgame,
+            dtm: vec![DRAW; size],
+            counts: vec![ESCAPE; size],
+            index: 0,
+            promotions: Vec::new(),
+            ply: 1,
+            frontier: Vec::new(),
+            cursor: 0,
+            next: Vec::new(),
+        }
+    }
+
+    /// Looks at entries until `budget` runs out and returns whether the table is done.
+    fn step(&mut self, budget: &mut u64) -> bool {
+        while self.index < self.dtm.len() {
+            if *budget == 0 {
+                return false;
+            }
+            *budget -= 1;
+            self.set_up(self.index);
+            self.index += 1;
+        }
+        // wins are found one ply after the losses they lead to, losses once every move is a known win
+        while !self.frontier.is_empty() || self.ply < self.promotions.len() {
+            while let Some(&index) = self.frontier.get(self.cursor) {
+                if *budget == 0 {
+                    return false;
+                }
+                *budget -= 1;
+                self.expand(index);
+                self.cursor += 1;
+            }
+            for &index in self.promotions.get(self.ply).into_iter().flatten() {
+                if self.dtm[index] == DRAW {
+                    self.dtm[index] = self.ply as u8;
+                    self.next.push(index);
+                }
+            }
+            self.frontier = std::mem::take(&mut self.next);
+            self.cursor = 0;
+            self.ply += 1;
+        }
+        true
+    }
+
+    /// Marks the entry as mated, or counts black's moves, or notes the promotion it wins by.
+    fn set_up(&mut self, index: usize) {
+        let endgame = self.endgame;
+        let placement = EndgameTable::decode(endgame, index);
+        if EndgameTable::index(endgame, &placement) != index {
+            return;
+        }
+        let board = placement.board(endgame);
+        if !EndgameTable::is_legal(endgame, &placement, &board) {
+            return;
+        }
+        if placement.white_to_move {
+            if let Some(plies) = endgame
+                .has_pawn()
+                .then(|| EndgameTable::promotion_dtm(&placement))
+                .flatten()
+            {
+                let plies = plies as usize;
+                if self.promotions.len() <= plies {
+                    self.promotions.resize(plies + 1, Vec::new());
+                }
+                self.promotions[plies].push(index);
+            }
+            return;
+        }
+        match EndgameTable::black_successors(endgame, &placement, &board) {
+            Some(successors) if successors.is_empty() && board.is_check(false) => {
+                self.dtm[index] = 0;
+                self.frontier.push(index);
+            }
+            // stalemates keep no moves to wait for and stay drawn
+            Some(successors) => self.counts[index] = successors.len() as u8,
+            None => {}
+        }
+    }
+
+    /// Finds the entries that reach the entry, found the ply before, with one move.
+    fn expand(&mut self, index: usize) {
+        let endgame = self.endgame;
+        let ply = self.ply;
+        let placement = EndgameTable::decode(endgame, index);
+        if ply % 2 == 1 {
+            let board = placement.board(endgame);
+            for predecessor in EndgameTable::white_predecessors(endgame, &placement, &board) {
+                if self.dtm[predecessor] == DRAW {
+                    self.dtm[predecessor] = ply as u8;
+                    self.next.push(predecessor);
+                }
+            }
+        } else {
+            for predecessor in EndgameTable::black_predecessors(endgame, &placement) {
+                if self.dtm[predecessor] == DRAW && self.counts[predecessor] != ESCAPE {
+                    self.counts[predecessor] -= 1;
+                    if self.counts[predecessor] == 0 {
+                        self.dtm[predecessor] = ply as u8;
+                        self.next.push(predecessor);
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> EndgameTable {
+        EndgameTable {
+            endgame: self.endgame,
+            dtm: self.dtm,
+        }
+    }
+}
+
+impl ChessEngine {
+    /// The ending and the position seen with the strong side as white, if it is one of the basic endings.
+    fn endgame_placement(&self) -> Option<(Endgame, Placement)> {
+        if self.castling.can_white_king_castle()
+            || self.castling.can_white_queen_castle()
+            || self.castling.can_black_king_castle()
+            || self.castling.can_black_queen_castle()
+        {
+            return None;
+        }
+        let mut pieces: Vec<(usize, Piece)> = (0..64)
+            .map(|square| (square, self.board[to_0x88(square)]))
+            .filter(|(_, piece)| !piece.is_empty())
+            .collect();
+        let strong_white = pieces
+            .iter()
+            .filter(|(_, piece)| !piece.is_king())
+            .all(|(_, piece)| piece.is_white());
+        if !strong_white {
+            // mirror the board so the side with the pieces plays white
+            pieces = pieces
+                .into_iter()
+                .map(|(square, piece)| {
+                    let letter = char::from(piece);
+                    let swapped = if letter.is_ascii_uppercase() {
+                        letter.to_ascii_lowercase()
+                    } else {
+                        letter.to_ascii_uppercase()
+                    };
+                    (square ^ 56, Piece::from(swapped))
+                })
+                .collect();
+        }
+        let others: Vec<Piece> = pieces
+            .iter()
+            .map(|&(_, piece)| piece)
+            .filter(|piece| !piece.is_king())
+            .collect();
+        let endgame = Endgame::ALL.into_iter().find(|endgame| {
+            others.len() == endgame.pieces().len()
+                && endgame.pieces().iter().all(|piece| others.contains(piece))
+        })?;
+        let square_of = |wanted: Piece| {
+            pieces
+                .iter()
+                .find(|&&(_, piece)| piece == wanted)
+                .map(|&(square, _)| square)
+        };
+        let mut placement = Placement {
+            white_to_move: self.is_white_turn == strong_white,
+            squares: [
+                square_of(Piece::WhiteKing)?,
+                square_of(Piece::BlackKing)?,
+                0,
+                0,
+            ],
+            len: 2 + endgame.pieces().len(),
+        };
+        for (i, &piece) in endgame.pieces().iter().enumerate() {
+            placement.squares[2 + i] = square_of(piece)?;
+        }
+        let board = placement.board(endgame);
+        EndgameTable::is_legal(endgame, &placement, &board).then_some((endgame, placement))
+    }
+
+    /// Builds about `budget` more positions of the distance to mate tables, smallest first,
+    /// and returns whether there is still more to build.
+    pub(crate) fn build_endgame_tables(budget: u64) -> bool {
+        let mut budget = budget;
+        Endgame::ALL
+            .into_iter()
+            .any(|endgame| endgame.build(&mut budget))
+    }
+
+    /// The exact distance to mate in plies for KQK, KRK, KPK and KBNK: the outer None when
+    /// the position is not one of them or its table is not built yet, the inner one for a draw,
+    /// else signed as in [`EndgameTable::probe`].
+    pub(crate) fn distance_to_mate(&self) -> Option<Option<i32>> {
+        let (endgame, placement) = self.endgame_placement()?;
+        Some(endgame.table()?.probe(&placement))
+    }
+
+    /// The move that mates fastest, keeps the draw or resists longest in one of the basic
+    /// endings whose table is built.
+    pub(crate) fn endgame_move(&mut self) -> Option<Move> {
+        self.endgame_placement()?.0.table()?;
+        let mut best: Option<(i32, Move)> = None;
+        for r#move in self.generate_moves() {
+            let undo = self.make(r#move);
+            // leaving the tables means taking the last piece or promoting to a minor one
+            let after = self.distance_to_mate().flatten();
+            self.unmake(undo);
+            let rank = match after {
+                Some(plies) if plies <= 0 => 1000 + plies,
+                Some(plies) => -1000 + plies,
+                None => 0,
+            };
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, r#move));
+            }
+        }
+        best.map(|(_, r#move)| r#move)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+    use crate::game_status::GameStatus;
+
+    #[test]
+    fn test_distance_to_mate() {
+        while Endgame::Kpk.build(&mut 1_000_000) {}
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, Option<Option<i32>>)> = vec![
+            ("6k1/8/6K1/8/8/8/8/Q7 w - - 0 1", Some(Some(1))),
+            ("Q5k1/8/6K1/8/8/8/8/8 b - - 0 1", Some(Some(0))),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", Some(None)),
+            ("6k1/8/6K1/8/8/8/8/Q7 b - - 0 1", Some(Some(-4))),
+            ("7q/8/8/8/8/6k1/8/6K1 b - - 0 1", Some(Some(1))),
+            ("k7/8/1K6/8/8/8/8/7R w - - 0 1", Some(Some(1))),
+            ("k7/8/1K6/P7/8/8/8/8 w - - 0 1", Some(None)),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", None),
+            ("4k3/8/8/8/8/8/8/3QK2R w - - 0 1", None),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                None,
+            ),
+        ];
+        for (fen, dtm) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(chess_position.distance_to_mate(), dtm, "{fen}");
+        }
+        chess_position
+            .parse_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1")
+            .unwrap();
+        assert!(chess_position.distance_to_mate().unwrap().unwrap() > 0);
+        chess_position
+            .parse_fen("8/4k3/8/4K3/4P3/8/8/8 w - - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.distance_to_mate(), Some(None));
+    }
+
+    #[test]
+    fn test_longest_mates() {
+        let longest = |endgame: Endgame| {
+            while endgame.build(&mut 1_000_000) {}
+            let table = endgame.table().unwrap();
+            (0..table.dtm.len())
+                .filter(|&index| index % 2 == 1)
+                .map(|index| table.dtm[index])
+                .filter(|&dtm| dtm != DRAW)
+                .max()
+        };
+        assert_eq!(longest(Endgame::Kqk), Some(19));
+        assert_eq!(longest(Endgame::Krk), Some(31));
+    }
+
+    #[test]
+    fn test_build_in_steps() {
+        while Endgame::Kqk.build(&mut 1_000_000) {}
+        let mut builder = TableBuilder::new(Endgame::Kqk);
+        let mut steps = 0;
+        loop {
+            let mut budget = 5000;
+            if builder.step(&mut budget) {
+                break;
+            }
+            assert_eq!(budget, 0);
+            steps += 1;
+        }
+        assert!(steps > 10);
+        assert_eq!(builder.finish().dtm, Endgame::Kqk.table().unwrap().dtm);
+
+        let mut builder = TableBuilder::new(Endgame::Kbnk);
+        let mut budget = 1000;
+        assert!(!builder.step(&mut budget));
+        assert_eq!(builder.index, 1000);
+        assert!(Endgame::Kbnk.build(&mut 0));
+    }
+
+    #[test]
+    fn test_unbuilt_table() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("8/8/8/3k4/8/8/8/2B1KN2 w - - 0 1")
+            .unwrap();
+        // KBNK is left to the search until its table is built
+        let built = Endgame::Kbnk.table().is_some();
+        assert_eq!(chess_position.distance_to_mate().is_some(), built);
+        assert_eq!(chess_position.endgame_move().is_some(), built);
+        assert!(chess_position.engine_move().is_some());
+    }
+
+    #[test]
+    #[ignore = "building KBNK takes long in debug builds"]
+    fn test_bishop_and_knight() {
+        while Endgame::Kbnk.build(&mut 1_000_000) {}
+        let table = Endgame::Kbnk.table().unwrap();
+        let longest = (0..table.dtm.len())
+            .filter(|&index| index % 2 == 1)
+            .map(|index| table.dtm[index])
+            .filter(|&dtm| dtm != DRAW)
+            .max();
+        assert_eq!(longest, Some(65));
+    }
+
+    #[test]
+    fn test_bishop_and_knight_can_win() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("4k3/8/8/8/8/8/8/2B1KN2 w - - 0 1")
+            .unwrap();
+        chess_position.play(4, 20, None).unwrap();
+        assert_eq!(chess_position.game_status, GameStatus::InProgress);
+        assert!(chess_position.analyse(3).score < -300);
+    }
+
+    #[test]
+    fn test_endgame_move() {
+        while Endgame::Kpk.build(&mut 1_000_000) {}
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<&str> = vec![
+            "6k1/8/6K1/8/8/8/8/Q7 w - - 0 1",
+            "6k1/8/6K1/8/8/8/8/Q7 b - - 0 1",
+            "8/8/8/8/8/2k5/8/K6R w - - 0 1",
+            "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+            "k7/8/1K6/P7/8/8/8/8 b - - 0 1",
+        ];
+        for fen in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let before = chess_position.distance_to_mate().unwrap();
+            let r#move = chess_position.endgame_move().unwrap();
+            chess_position.make(r#move);
+            let after = chess_position.distance_to_mate().unwrap();
+            let expected = before.map(|plies| if plies > 0 { 1 - plies } else { -plies - 1 });
+            assert_eq!(after, expected, "{fen} {move}");
+        }
+        chess_position
+            .parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.endgame_move(), None);
+    }
+
+    #[test]
+    fn test_engine_move() {
+        while Endgame::Kpk.build(&mut 1_000_000) {}
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<&str> = vec![
+            "8/8/8/8/8/2k5/8/K6R w - - 0 1",
+            "8/8/2k5/8/8/8/8/K2Q4 w - - 0 1",
+            "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        ];
+        for fen in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let mut dtm = chess_position.distance_to_mate().flatten().unwrap();
+            // both sides play from the tables, so every ply brings the mate one closer
+            while dtm != 0 {
+                let r#move = chess_position.engine_move().unwrap();
+                chess_position.make(r#move);
+                let after = chess_position.distance_to_mate().flatten().unwrap();
+                assert_eq!(after.abs(), dtm.abs() - 1, "{fen} {move}");
+                dtm = after;
+            }
+            assert_eq!(chess_position.engine_move(), None, "{fen}");
+        }
+    }
+}
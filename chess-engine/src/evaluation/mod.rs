@@ -0,0 +1,156 @@
+mod weights;
+
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::piece::{Piece, PieceTrait};
+use crate::utils;
+use crate::ChessEngine;
+pub use weights::{Weights, KING_END_GAME_TABLE, KING_MIDDLE_GAME_TABLE, TABLES};
+
+/// The game phase with all pieces on the board, knights and bishops counting 1, rooks 2 and queens 4.
+const OPENING_PHASE: i32 = 24;
+
+fn phase(piece: Piece) -> i32 {
+    if piece.is_queen() {
+        4
+    } else if piece.is_rook() {
+        2
+    } else if piece.is_bishop() || piece.is_knight() {
+        1
+    } else {
+        0
+    }
+}
+
+/// The index of the piece-square bonus of a piece on a square from 0 to 63, in tables
+/// from white's side with rank 8 first.
+fn table_index(piece: Piece, square: usize) -> usize {
+    let (rank, file) = (square / 8, square % 8);
+    if piece.is_white() {
+        (7 - rank) * 8 + file
+    } else {
+        rank * 8 + file
+    }
+}
+
+/// Scores the position in centipawns for the side to move from material and piece placement,
+/// the king moving from shelter to the centre as pieces come off.
+pub fn evaluate(weights: &Weights, board: &Board, white_to_move: bool) -> i32 {
+    let mut score = 0;
+    let mut middle_game_king = 0;
+    let mut end_game_king = 0;
+    let mut game_phase = 0;
+    for square in 0..64 {
+        let piece = board[utils::convert_postion_to_0x88(square)];
+        if piece.is_empty() {
+            continue;
+        }
+        let index = table_index(piece, square);
+        let sign = if piece.is_white() { 1 } else { -1 };
+        game_phase += phase(piece);
+        if piece.is_king() {
+            middle_game_king += sign * weights.king_middle_game[index];
+            end_game_king += sign * weights.king_end_game[index];
+            continue;
+        }
+        let table = &weights.tables[piece as usize % 6];
+        score += sign * (weights.value(piece) + table[index]);
+    }
+    let game_phase = game_phase.min(OPENING_PHASE);
+    score += (middle_game_king * game_phase + end_game_king * (OPENING_PHASE - game_phase))
+        / OPENING_PHASE;
+    if white_to_move {
+        score
+    } else {
+        -score
+    }
+}
+
+/// The evaluation from white's side as a sum of weights, each given with how many times
+/// it counts, in the order of [`Weights::to_parameters`]. Kings count in part towards the
+/// middle game and in part towards the end game, so the sum is the evaluation before
+/// rounding.
+pub fn features(board: &Board) -> Vec<(usize, f64)> {
+    let mut features = Vec::new();
+    let mut kings = Vec::new();
+    let mut game_phase = 0;
+    for square in 0..64 {
+        let piece = board[utils::convert_postion_to_0x88(square)];
+        if piece.is_empty() {
+            continue;
+        }
+        let index = table_index(piece, square);
+        let sign = if piece.is_white() { 1.0 } else { -1.0 };
+        game_phase += phase(piece);
+        if piece.is_king() {
+            kings.push((index, sign));
+            continue;
+        }
+        let kind = piece as usize % 6;
+        features.push((kind, sign));
+        features.push((TABLES + kind * 64 + index, sign));
+    }
+    let middle_game = game_phase.min(OPENING_PHASE) as f64 / OPENING_PHASE as f64;
+    for (index, sign) in kings {
+        features.push((KING_MIDDLE_GAME_TABLE + index, sign * middle_game));
+        features.push((KING_END_GAME_TABLE + index, sign * (1.0 - middle_game)));
+    }
+    features
+}
+
+impl ChessEngine {
+    /// Loads the weights of the handcrafted evaluation from a local file, see [`Weights`].
+    pub fn load_weights_file(&mut self, path: &str) -> Result<(), String> {
+        self.weights = Arc::new(Weights::from_file(path)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_evaluate() {
+        let mut board = Board::default();
+        let arr: Vec<(&str, bool, i32)> = vec![
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", true, 0),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR", false, 0),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR", true, 40),
+            ("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR", false, -40),
+            ("4k3/8/8/8/8/8/8/3QK3", true, 895),
+        ];
+        for (fen, white_to_move, score) in arr {
+            board.parse_fen(fen).unwrap();
+            assert_eq!(
+                evaluate(&Weights::default(), &board, white_to_move),
+                score,
+                "{fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_features() {
+        let mut board = Board::default();
+        let weights = Weights::default();
+        let parameters = weights.to_parameters();
+        let arr: Vec<&str> = vec![
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR",
+            "r3k2r/pppq1ppp/2n2n2/3pp3/1b1PP3/2N2N2/PPPQ1PPP/R3K2R",
+            "8/5k2/8/3p4/8/2B5/5K2/8",
+            "4k3/8/8/8/8/8/8/3QK3",
+        ];
+        for fen in arr {
+            board.parse_fen(fen).unwrap();
+            let sum: f64 = features(&board)
+                .iter()
+                .map(|&(index, count)| parameters[index] as f64 * count)
+                .sum();
+            let score = evaluate(&weights, &board, true) as f64;
+            assert!((sum - score).abs() < 1.0, "{fen}");
+        }
+    }
+}
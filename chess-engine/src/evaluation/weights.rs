@@ -0,0 +1,260 @@
+use crate::piece::Piece;
+
+/// The sections of a weights file in order, with the number of weights of each.
+const SECTIONS: [(&str, usize); 8] = [
+    ("material", 5),
+    ("pawn", 64),
+    ("knight", 64),
+    ("bishop", 64),
+    ("rook", 64),
+    ("queen", 64),
+    ("king_middle_game", 64),
+    ("king_end_game", 64),
+];
+/// The number of weights, in the order of the sections.
+pub const WEIGHT_COUNT: usize = 5 + 7 * 64;
+/// Where the piece-square bonuses of the pawns start among the weights, those of the knights,
+/// bishops, rooks and queens following.
+pub const TABLES: usize = 5;
+pub const KING_MIDDLE_GAME_TABLE: usize = TABLES + 5 * 64;
+pub const KING_END_GAME_TABLE: usize = KING_MIDDLE_GAME_TABLE + 64;
+
+/// Piece-square bonuses in centipawns from white's side, rank 8 first.
+#[rustfmt::skip]
+const PAWN: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+    50,  50,  50,  50,  50,  50,  50,  50,
+    10,  10,  20,  30,  30,  20,  10,  10,
+     5,   5,  10,  25,  25,  10,   5,   5,
+     0,   0,   0,  20,  20,   0,   0,   0,
+     5,  -5, -10,   0,   0, -10,  -5,   5,
+     5,  10,  10, -20, -20,  10,  10,   5,
+     0,   0,   0,   0,   0,   0,   0,   0,
+];
+#[rustfmt::skip]
+const KNIGHT: [i32; 64] = [
+   -50, -40, -30, -30, -30, -30, -40, -50,
+   -40, -20,   0,   0,   0,   0, -20, -40,
+   -30,   0,  10,  15,  15,  10,   0, -30,
+   -30,   5,  15,  20,  20,  15,   5, -30,
+   -30,   0,  15,  20,  20,  15,   0, -30,
+   -30,   5,  10,  15,  15,  10,   5, -30,
+   -40, -20,   0,   5,   5,   0, -20, -40,
+   -50, -40, -30, -30, -30, -30, -40, -50,
+];
+#[rustfmt::skip]
+const BISHOP: [i32; 64] = [
+   -20, -10, -10, -10, -10, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,  10,  10,   5,   0, -10,
+   -10,   5,   5,  10,  10,   5,   5, -10,
+   -10,   0,  10,  10,  10,  10,   0, -10,
+   -10,  10,  10,  10,  10,  10,  10, -10,
+   -10,   5,   0,   0,   0,   0,   5, -10,
+   -20, -10, -10, -10, -10, -10, -10, -20,
+];
+#[rustfmt::skip]
+const ROOK: [i32; 64] = [
+     0,   0,   0,   0,   0,   0,   0,   0,
+     5,  10,  10,  10,  10,  10,  10,   5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+    -5,   0,   0,   0,   0,   0,   0,  -5,
+     0,   0,   0,   5,   5,   0,   0,   0,
+];
+#[rustfmt::skip]
+const QUEEN: [i32; 64] = [
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+   -10,   0,   0,   0,   0,   0,   0, -10,
+   -10,   0,   5,   5,   5,   5,   0, -10,
+    -5,   0,   5,   5,   5,   5,   0,  -5,
+     0,   0,   5,   5,   5,   5,   0,  -5,
+   -10,   5,   5,   5,   5,   5,   0, -10,
+   -10,   0,   5,   0,   0,   0,   0, -10,
+   -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+#[rustfmt::skip]
+const KING_MIDDLE_GAME: [i32; 64] = [
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -30, -40, -40, -50, -50, -40, -40, -30,
+   -20, -30, -30, -40, -40, -30, -30, -20,
+   -10, -20, -20, -20, -20, -20, -20, -10,
+    20,  20,   0,   0,   0,   0,  20,  20,
+    20,  30,  10,   0,   0,  10,  30,  20,
+];
+#[rustfmt::skip]
+const KING_END_GAME: [i32; 64] = [
+   -50, -40, -30, -20, -20, -30, -40, -50,
+   -30, -20, -10,   0,   0, -10, -20, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  30,  40,  40,  30, -10, -30,
+   -30, -10,  20,  30,  30,  20, -10, -30,
+   -30, -30,   0,   0,   0,   0, -30, -30,
+   -50, -30, -30, -30, -30, -30, -30, -50,
+];
+/// The numbers the handcrafted evaluation adds up, all in centipawns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Weights {
+    /// Pawn, knight, bishop, rook and queen.
+    pub material: [i32; 5],
+    /// Piece-square bonuses from white's side, rank 8 first, pawns to queens.
+    pub tables: [[i32; 64]; 5],
+    pub king_middle_game: [i32; 64],
+    pub king_end_game: [i32; 64],
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            material: [100, 300, 300, 500, 900],
+            tables: [PAWN, KNIGHT, BISHOP, ROOK, QUEEN],
+            king_middle_game: KING_MIDDLE_GAME,
+            king_end_game: KING_END_GAME,
+        }
+    }
+}
+
+impl Weights {
+    /// The weight of the material of a piece other than a king.
+    pub fn value(&self, piece: Piece) -> i32 {
+        self.material[piece as usize % 6]
+    }
+
+    /// All the weights in the order of the sections of a file.
+    pub fn to_parameters(&self) -> Vec<i32> {
+        let mut parameters = self.material.to_vec();
+        for table in &self.tables {
+            parameters.extend(table);
+        }
+        parameters.extend(self.king_middle_game);
+        parameters.extend(self.king_end_game);
+        parameters
+    }
+
+    /// The weights from all of them in the order of [`Weights::to_parameters`].
+    pub fn from_parameters(parameters: &[i32]) -> Result<Self, String> {
+        if parameters.len() != WEIGHT_COUNT {
+            return Err(format!("Invalid Weights Count \"{}\"", parameters.len()));
+        }
+        let table =
+            |start: usize| -> [i32; 64] { parameters[start..start + 64].try_into().unwrap() };
+        Ok(Self {
+            material: parameters[..TABLES].try_into().unwrap(),
+            tables: std::array::from_fn(|kind| table(TABLES + kind * 64)),
+            king_middle_game: table(KING_MIDDLE_GAME_TABLE),
+            king_end_game: table(KING_END_GAME_TABLE),
+        })
+    }
+
+    /// Reads a weights file: each section's name ("material", "pawn" to "queen",
+    /// "king_middle_game" and "king_end_game") followed by its numbers, in any layout.
+    /// Lines starting with "#" are comments.
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut sections: Vec<(&str, Vec<i32>)> = Vec::new();
+        let words = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .flat_map(str::split_whitespace);
+        for word in words {
+            if let Ok(weight) = word.parse::<i32>() {
+                match sections.last_mut() {
+                    Some((_, weights)) => weights.push(weight),
+                    None => return Err(format!("Invalid Weight \"{word}\"")),
+                }
+            } else if SECTIONS.iter().any(|(name, _)| *name == word) {
+                if sections.iter().any(|(name, _)| *name == word) {
+                    return Err(format!("Duplicate Weights Section \"{word}\""));
+                }
+                sections.push((word, Vec::new()));
+            } else {
+                return Err(format!("Invalid Weights Section \"{word}\""));
+            }
+        }
+        let mut parameters = Vec::with_capacity(WEIGHT_COUNT);
+        for (name, count) in SECTIONS {
+            let Some((_, weights)) = sections.iter().find(|(section, _)| *section == name) else {
+                return Err(format!("Missing Weights Section \"{name}\""));
+            };
+            if weights.len() != count {
+                return Err(format!(
+                    "Invalid Weights Count \"{}\" for Section \"{name}\"",
+                    weights.len()
+                ));
+            }
+            parameters.extend(weights);
+        }
+        Self::from_parameters(&parameters)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Cannot Read Weights \"{path}\": {err}"))?;
+        Self::from_text(&text)
+    }
+
+    /// Writes the weights as read by [`Weights::from_text`], the tables a rank to a line.
+    pub fn to_text(&self) -> String {
+        let parameters = self.to_parameters();
+        let mut text = String::new();
+        let mut start = 0;
+        for (name, count) in SECTIONS {
+            text.push_str(name);
+            text.push('\n');
+            for row in parameters[start..start + count].chunks(8) {
+                let row: Vec<String> = row.iter().map(|weight| format!("{weight:4}")).collect();
+                text.push_str(&row.join(" "));
+                text.push('\n');
+            }
+            start += count;
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weights() {
+        let weights = Weights::default();
+        assert_eq!(weights.to_parameters().len(), WEIGHT_COUNT);
+        assert_eq!(Weights::from_text(&weights.to_text()), Ok(weights.clone()));
+        assert_eq!(weights.value(Piece::BlackRook), 500);
+
+        let mut tuned = weights.clone();
+        tuned.material[1] = 320;
+        tuned.king_end_game[63] = -45;
+        let text = format!("# tuned\n{}", tuned.to_text());
+        assert_eq!(Weights::from_text(&text), Ok(tuned));
+
+        let text = weights.to_text();
+        let arr: Vec<(String, &str)> = vec![
+            (
+                text.replace("material\n 100", "material\n"),
+                "Invalid Weights Count \"4\" for Section \"material\"",
+            ),
+            (
+                text.replace("queen", "king"),
+                "Invalid Weights Section \"king\"",
+            ),
+            (
+                text.replace("rook", "pawn"),
+                "Duplicate Weights Section \"pawn\"",
+            ),
+            (format!("5 {text}"), "Invalid Weight \"5\""),
+            (
+                text.replace("king_end_game", "# king_end_game"),
+                "Invalid Weights Count \"128\" for Section \"king_middle_game\"",
+            ),
+        ];
+        for (text, err) in arr {
+            assert_eq!(Weights::from_text(&text), Err(err.to_string()));
+        }
+    }
+}
@@ -4,8 +4,8 @@ pub trait FenParser {
     fn to_fen(&self) -> String;
 }
 
-impl ToString for dyn FenParser {
-    fn to_string(&self) -> String {
-        self.to_fen()
+impl std::fmt::Display for dyn FenParser {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_fen())
     }
 }
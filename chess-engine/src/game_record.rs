@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the layout of [`GameRecord`] changes in a way older readers cannot load.
+pub const GAME_RECORD_VERSION: u32 = 1;
+
+/// The whole game as saved to JSON, enough to rebuild the engine by replaying the moves.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameRecord {
+    pub version: u32,
+    /// The FEN the game started from.
+    pub start_position: String,
+    pub moves: Vec<RecordedMove>,
+    /// "1-0", "0-1", "1/2-1/2" or "*" while the game is in progress.
+    pub result: String,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct RecordedMove {
+    /// The move in UCI notation, e.g. "e2e4" or "e7e8q".
+    pub uci: String,
+    /// Milliseconds since the Unix epoch when the move was played.
+    pub timestamp: f64,
+}
+
+impl GameRecord {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let record: GameRecord =
+            serde_json::from_str(json).map_err(|err| format!("Invalid Game \"{err}\""))?;
+        if record.version > GAME_RECORD_VERSION {
+            return Err(format!("Unsupported Game Version \"{}\"", record.version));
+        }
+        Ok(record)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_record_json() {
+        let record = GameRecord {
+            version: GAME_RECORD_VERSION,
+            start_position: "8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
+            moves: vec![RecordedMove {
+                uci: "e2e4".to_string(),
+                timestamp: 1.0,
+            }],
+            result: "*".to_string(),
+            tags: BTreeMap::from([("White".to_string(), "Magnus".to_string())]),
+        };
+        assert_eq!(GameRecord::from_json(&record.to_json()).unwrap(), record);
+        assert!(GameRecord::from_json("{}").is_err());
+
+        let json = record.to_json().replace("\"version\":1", "\"version\":2");
+        assert!(GameRecord::from_json(&json)
+            .unwrap_err()
+            .contains("Unsupported Game Version \"2\""));
+    }
+}
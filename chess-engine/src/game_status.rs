@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GameStatus {
     InProgress,
     Draw,
@@ -6,13 +6,36 @@ pub enum GameStatus {
     BlackWon,
 }
 
-impl ToString for GameStatus {
-    fn to_string(&self) -> String {
+impl GameStatus {
+    /// The result in PGN notation.
+    pub fn result(&self) -> &'static str {
         match self {
-            GameStatus::InProgress => "In Progress".to_string(),
-            GameStatus::Draw => "Game Over (Draw)".to_string(),
-            GameStatus::WhiteWon => "Game Over (White Won)".to_string(),
-            GameStatus::BlackWon => "Game Over (Black Won)".to_string(),
+            GameStatus::InProgress => "*",
+            GameStatus::Draw => "1/2-1/2",
+            GameStatus::WhiteWon => "1-0",
+            GameStatus::BlackWon => "0-1",
+        }
+    }
+
+    /// The status for a result in PGN notation.
+    pub fn from_result(result: &str) -> Result<GameStatus, String> {
+        match result {
+            "*" => Ok(GameStatus::InProgress),
+            "1/2-1/2" => Ok(GameStatus::Draw),
+            "1-0" => Ok(GameStatus::WhiteWon),
+            "0-1" => Ok(GameStatus::BlackWon),
+            _ => Err(format!("Invalid Result \"{result}\"")),
+        }
+    }
+}
+
+impl std::fmt::Display for GameStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameStatus::InProgress => write!(f, "In Progress"),
+            GameStatus::Draw => write!(f, "Game Over (Draw)"),
+            GameStatus::WhiteWon => write!(f, "Game Over (White Won)"),
+            GameStatus::BlackWon => write!(f, "Game Over (Black Won)"),
         }
     }
 }
@@ -0,0 +1,120 @@
+use serde::Serialize;
+
+use crate::algebric_notation;
+
+/// The reason a move was rejected, as seen by the player trying to make it.
+/// Squares are stored in 0x88 representation.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IllegalMove {
+    NotYourTurn,
+    NoPiece,
+    OwnPieceOnTarget,
+    WrongMovementPattern,
+    /// The piece on the square stands between the start and the target.
+    PathBlocked(usize),
+    /// The piece is pinned to its king by the piece on the square.
+    Pinned(usize),
+    KingInCheck,
+    MovesIntoCheck,
+    /// The king would pass over the attacked square.
+    CastlingThroughCheck(usize),
+    CastlingRightLost,
+    EnPassantNotAvailable,
+}
+
+/// The serialised form of [`IllegalMove`] handed to the UI.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct IllegalMoveReport {
+    pub reason: &'static str,
+    pub message: String,
+    pub square: Option<String>,
+}
+
+impl IllegalMove {
+    pub fn code(&self) -> &'static str {
+        match self {
+            IllegalMove::NotYourTurn => "notYourTurn",
+            IllegalMove::NoPiece => "noPiece",
+            IllegalMove::OwnPieceOnTarget => "ownPieceOnTarget",
+            IllegalMove::WrongMovementPattern => "wrongMovementPattern",
+            IllegalMove::PathBlocked(_) => "pathBlocked",
+            IllegalMove::Pinned(_) => "pinned",
+            IllegalMove::KingInCheck => "kingInCheck",
+            IllegalMove::MovesIntoCheck => "movesIntoCheck",
+            IllegalMove::CastlingThroughCheck(_) => "castlingThroughCheck",
+            IllegalMove::CastlingRightLost => "castlingRightLost",
+            IllegalMove::EnPassantNotAvailable => "enPassantNotAvailable",
+        }
+    }
+
+    pub fn square(&self) -> Option<usize> {
+        match *self {
+            IllegalMove::PathBlocked(square)
+            | IllegalMove::Pinned(square)
+            | IllegalMove::CastlingThroughCheck(square) => Some(square),
+            _ => None,
+        }
+    }
+
+    pub fn to_report(self) -> IllegalMoveReport {
+        IllegalMoveReport {
+            reason: self.code(),
+            message: self.to_string(),
+            square: self
+                .square()
+                .map(|square| algebric_notation::to_string(square as u8)),
+        }
+    }
+}
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let square = |square: usize| algebric_notation::to_string(square as u8);
+        match *self {
+            IllegalMove::NotYourTurn => write!(f, "It is not your turn to move this piece"),
+            IllegalMove::NoPiece => write!(f, "There is no piece on this square"),
+            IllegalMove::OwnPieceOnTarget => {
+                write!(f, "You cannot capture your own piece")
+            }
+            IllegalMove::WrongMovementPattern => write!(f, "This piece does not move that way"),
+            IllegalMove::PathBlocked(pos) => {
+                write!(f, "The path is blocked by the piece on {}", square(pos))
+            }
+            IllegalMove::Pinned(pos) => write!(
+                f,
+                "This piece is pinned to your king by the piece on {}",
+                square(pos)
+            ),
+            IllegalMove::KingInCheck => {
+                write!(f, "Your king is in check and this move does not stop it")
+            }
+            IllegalMove::MovesIntoCheck => write!(f, "Your king cannot move into check"),
+            IllegalMove::CastlingThroughCheck(pos) => write!(
+                f,
+                "Your king cannot castle through check, {} is attacked",
+                square(pos)
+            ),
+            IllegalMove::CastlingRightLost => {
+                write!(f, "You have lost the right to castle on this side")
+            }
+            IllegalMove::EnPassantNotAvailable => write!(
+                f,
+                "En passant is only possible right after the pawn moved two squares"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let report = IllegalMove::PathBlocked(20).to_report();
+        assert_eq!(report.reason, "pathBlocked");
+        assert_eq!(report.square, Some("e2".to_string()));
+        assert!(report.message.contains("e2"));
+        assert_eq!(IllegalMove::NoPiece.to_report().square, None);
+    }
+}
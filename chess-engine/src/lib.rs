@@ -1,18 +1,67 @@
 mod algebric_notation;
+mod analysis;
 mod board;
 mod castling;
+mod chess_move;
+mod eco;
+mod endgame;
+mod evaluation;
 mod fen_trait;
+mod game_record;
 mod game_status;
+mod illegal_move;
+mod mcts;
+mod move_delta;
+mod nnue;
+mod opening_book;
+mod options;
+mod pgn;
 mod piece;
+mod problem;
+mod puzzle;
+mod random;
+mod review;
+mod san;
+mod search;
+mod see;
+mod selfplay;
+mod snapshot;
+mod strength;
+mod tablebase;
+mod tactics;
+mod tuner;
 mod utils;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use analysis::AnalysisSession;
 use board::Board;
 
 use castling::{Castling, CastlingTrait};
+use chess_move::Move;
+use eco::{EcoTable, Opening};
 use fen_trait::FenParser;
+use game_record::{GameRecord, RecordedMove, GAME_RECORD_VERSION};
 use game_status::GameStatus;
+use illegal_move::IllegalMove;
+use mcts::{MctsConfig, Player};
+use move_delta::{MoveDelta, SquareChange};
+use nnue::Nnue;
+use opening_book::{BookBuilder, BookMove, OpeningBook};
+use pgn::PgnGame;
 use piece::{Piece, PieceTrait};
+use puzzle::{Puzzle, PuzzleState};
+use random::Random;
+use search::{SearchLimits, SearchSession, TranspositionTable};
+use snapshot::{CastlingRights, MoveSnapshot, Snapshot};
+use strength::Strength;
+use tablebase::Tablebase;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 
+pub use evaluation::Weights;
+pub use selfplay::{SelfPlayConfig, TrainingPosition, RECORD_SIZE};
+pub use tuner::Tuner;
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
@@ -21,8 +70,10 @@ extern "C" {
     fn alert(s: &str);
 }
 
+const FEN_STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
 #[wasm_bindgen]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ChessEngine {
     /// The piece placement (from white's perspective). Each rank is described, starting with rank 8 and ending with rank 1;
     /// within each rank, the contents of each square are described from file "a" through file "h".
@@ -39,6 +90,57 @@ pub struct ChessEngine {
     /// The number of full moves, it starts at 1 and is incremented after black's move.
     full_move_number: u16,
     game_status: GameStatus,
+    /// The squares of the last move played, if any.
+    last_move: Option<(usize, usize)>,
+    /// The FEN the move history starts from.
+    start_fen: String,
+    history: Vec<HistoryEntry>,
+    /// PGN style tags such as "Event", "White" and "Black".
+    tags: BTreeMap<String, String>,
+    book: OpeningBook,
+    random: Random,
+    /// Syzygy endgame tables, shared by the copies made while searching.
+    tablebase: Arc<Tablebase>,
+    /// The puzzles loaded with `loadPuzzles`.
+    puzzles: Vec<Puzzle>,
+    puzzle: Option<PuzzleState>,
+    /// The threads a search runs on, 1 in wasm builds whatever the "Threads" option says.
+    threads: usize,
+    /// What plays `engineMove`, chosen with `withPlayer`.
+    player: Player,
+    mcts: MctsConfig,
+    /// How well `engineMove` plays.
+    strength: Strength,
+    /// The weights of the handcrafted evaluation, set with `loadWeights`.
+    weights: Arc<Weights>,
+    /// The network loaded with `loadNetwork`, evaluating instead of the handcrafted evaluation.
+    nnue: Option<Nnue>,
+    /// The transposition table, shared by the copies made while searching.
+    hash: Arc<TranspositionTable>,
+    /// The search started with `startSearch`, run through `step`.
+    search: Option<Box<SearchSession>>,
+}
+
+/// The state needed to take back a move made with [`ChessEngine::make`].
+#[derive(Debug, Clone, Copy)]
+struct Undo {
+    board: Board,
+    castling: Castling,
+    is_white_turn: bool,
+    en_passant: Option<u8>,
+    half_move_clock: u8,
+    full_move_number: u16,
+    last_move: Option<(usize, usize)>,
+}
+
+/// A move played through [`ChessEngine::play`], kept so the game can be saved and replayed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HistoryEntry {
+    from: usize,
+    to: usize,
+    promotion: Option<Piece>,
+    /// Milliseconds since the Unix epoch.
+    timestamp: f64,
 }
 
 #[wasm_bindgen]
@@ -61,6 +163,11 @@ impl ChessEngine {
 
     #[wasm_bindgen(js_name = "gameStatus")]
     pub fn game_status(&mut self) -> String {
+        if self.game_status == GameStatus::InProgress {
+            if let Some(tablebase) = self.tablebase_status() {
+                return format!("{} ({tablebase})", self.game_status);
+            }
+        }
         self.game_status.to_string().clone()
     }
 
@@ -69,6 +176,81 @@ impl ChessEngine {
         JsValue::from(self.parse_fen(fen_string).err())
     }
 
+    #[wasm_bindgen(js_name = "putPiece")]
+    pub fn put_piece(&mut self, square: &str, piece: &str) -> JsValue {
+        JsValue::from(self.edit_piece(square, piece).err())
+    }
+
+    #[wasm_bindgen(js_name = "removePiece")]
+    pub fn remove_piece(&mut self, square: &str) -> JsValue {
+        match algebric_notation::from_string(square) {
+            Ok(pos) => {
+                self.board.remove_piece(pos.into());
+                self.reset_history();
+                JsValue::UNDEFINED
+            }
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Removes every piece and all castling and en passant rights.
+    #[wasm_bindgen(js_name = "clearBoard")]
+    pub fn clear_board(&mut self) {
+        self.board.clear();
+        self.castling = Castling(0);
+        self.en_passant = None;
+        self.reset_history();
+    }
+
+    #[wasm_bindgen(js_name = "setTurn")]
+    pub fn set_turn(&mut self, active_color: &str) -> JsValue {
+        JsValue::from(self.parse_active_color(active_color).err())
+    }
+
+    #[wasm_bindgen(js_name = "setCastlingRights")]
+    pub fn set_castling_rights(
+        &mut self,
+        white_king: bool,
+        white_queen: bool,
+        black_king: bool,
+        black_queen: bool,
+    ) {
+        self.castling = Castling(0);
+        if white_king {
+            self.castling.set_white_king_castling();
+        }
+        if white_queen {
+            self.castling.set_white_queen_castling();
+        }
+        if black_king {
+            self.castling.set_black_king_castling();
+        }
+        if black_queen {
+            self.castling.set_black_queen_castling();
+        }
+        self.reset_history();
+    }
+
+    #[wasm_bindgen(js_name = "setEnPassant")]
+    pub fn set_en_passant(&mut self, square: Option<String>) -> JsValue {
+        let en_passant = match square {
+            Some(square) => match algebric_notation::from_string(&square) {
+                Ok(en_passant) => Some(en_passant),
+                Err(err) => return JsValue::from(err),
+            },
+            None => None,
+        };
+        self.en_passant = en_passant;
+        self.reset_history();
+        JsValue::UNDEFINED
+    }
+
+    /// Lists the problems that make the current position unplayable, empty when it is valid.
+    #[wasm_bindgen(js_name = "validatePosition")]
+    pub fn validate_position(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.validate()).unwrap()
+    }
+
     #[wasm_bindgen(js_name = "canDrag")]
     pub fn can_drag(&self, from: &str) -> bool {
         let from = algebric_notation::from_string(from).unwrap();
@@ -82,19 +264,408 @@ impl ChessEngine {
         self.test_move(from, to)
     }
 
+    /// Explains why moving from `from` to `to` is rejected, or returns null if the move is legal.
+    #[wasm_bindgen(js_name = "whyIllegal")]
+    pub fn why_illegal(&mut self, from: &str, to: &str) -> JsValue {
+        let from = algebric_notation::from_string(from).unwrap().into();
+        let to = algebric_notation::from_string(to).unwrap().into();
+        match self.why_illegal_move(from, to) {
+            Some(reason) => serde_wasm_bindgen::to_value(&reason.to_report()).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Plays the move and returns the squares it changed, or null if the move is illegal.
+    /// Pawns reaching the last rank become `promotion` ("q", "r", "b" or "n"), a queen by default.
     #[wasm_bindgen(js_name = "move")]
-    pub fn r#move(&mut self, from: &str, to: &str) -> bool {
+    pub fn r#move(&mut self, from: &str, to: &str, promotion: Option<String>) -> JsValue {
         let from = algebric_notation::from_string(from).unwrap().into();
         let to = algebric_notation::from_string(to).unwrap().into();
-        let castling = self.castling.0;
-        self.make_move(from, to);
-        if self.board.is_check(self.is_white_turn) {
-            self.castling.0 = castling;
-            return false;
+        let promotion = match promotion {
+            Some(promotion) => match self.parse_promotion(&promotion) {
+                Ok(piece) => Some(piece),
+                Err(_) => return JsValue::NULL,
+            },
+            None => None,
+        };
+        match self.play(from, to, promotion) {
+            Some(delta) => serde_wasm_bindgen::to_value(&delta).unwrap(),
+            None => JsValue::NULL,
         }
-        self.is_white_turn = !self.is_white_turn;
-        self.game_status = self.get_status();
-        true
+    }
+
+    /// Returns everything the UI needs to render the position in a single object.
+    #[wasm_bindgen(js_name = "snapshot")]
+    pub fn snapshot(&mut self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.build_snapshot()).unwrap()
+    }
+
+    /// Serialises the whole game, including the move history and tags, to JSON.
+    #[wasm_bindgen(js_name = "saveGame")]
+    pub fn save_game(&self) -> String {
+        self.to_record().to_json()
+    }
+
+    /// Restores a game saved with `saveGame`, returning an error message if it cannot be loaded.
+    #[wasm_bindgen(js_name = "loadGame")]
+    pub fn load_game(&mut self, json: &str) -> JsValue {
+        JsValue::from(self.load_record(json).err())
+    }
+
+    #[wasm_bindgen(js_name = "setTag")]
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        self.tags.insert(name.to_string(), value.to_string());
+    }
+
+    /// Loads a Polyglot `.bin` opening book, returning an error message if it is malformed.
+    #[wasm_bindgen(js_name = "loadBook")]
+    pub fn load_book(&mut self, bytes: &[u8]) -> JsValue {
+        match OpeningBook::from_bytes(bytes) {
+            Ok(book) => {
+                self.book = book;
+                JsValue::UNDEFINED
+            }
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Builds a Polyglot `.bin` book from the games of a PGN collection, counting the first `max_ply`
+    /// moves of each game and keeping the moves played at least `min_count` times.
+    #[wasm_bindgen(js_name = "buildBook")]
+    pub fn build_book(pgn: &str, max_ply: usize, min_count: u32) -> Result<Vec<u8>, JsValue> {
+        Self::build_book_bytes(pgn, max_ply, min_count).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(js_name = "polyglotKey")]
+    pub fn polyglot_key(&self) -> u64 {
+        opening_book::polyglot_key(
+            &self.board,
+            self.is_white_turn,
+            &self.castling,
+            self.en_passant,
+        )
+    }
+
+    /// Lists the legal book moves of the position with their weights.
+    #[wasm_bindgen(js_name = "bookMoves")]
+    pub fn book_moves(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.legal_book_moves()).unwrap()
+    }
+
+    /// Picks a book move in UCI notation, by weight or the best one only, if the position is in the book.
+    #[wasm_bindgen(js_name = "bookMove")]
+    pub fn book_move(&mut self, best_only: bool) -> Option<String> {
+        let moves = self.legal_book_moves();
+        OpeningBook::pick(&moves, best_only, &mut self.random).map(|book_move| book_move.to_uci())
+    }
+
+    /// Adds a Syzygy table from its bytes, `file_name` being e.g. "KQvK.rtbw" or "KQvK.rtbz".
+    #[wasm_bindgen(js_name = "addTablebase")]
+    pub fn add_tablebase(&mut self, file_name: &str, bytes: &[u8]) -> JsValue {
+        match self.tablebase.add_table(file_name, bytes.to_vec()) {
+            Ok(()) => JsValue::UNDEFINED,
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// The tablebase result for the side to move, from -2 (loss) to 2 (win), if the tables cover the position.
+    #[wasm_bindgen(js_name = "probeWdl")]
+    pub fn probe_wdl_js(&mut self) -> Option<i32> {
+        self.probe_wdl().map(|wdl| wdl as i32)
+    }
+
+    /// The plies to the next capture or pawn move on the best path, signed like the result.
+    #[wasm_bindgen(js_name = "probeDtz")]
+    pub fn probe_dtz_js(&mut self) -> Option<i32> {
+        self.probe_dtz()
+    }
+
+    /// The best tablebase move in UCI notation, if the tables cover the position.
+    #[wasm_bindgen(js_name = "tablebaseMove")]
+    pub fn tablebase_move_js(&mut self) -> Option<String> {
+        self.tablebase_move().map(|r#move| r#move.to_uci())
+    }
+
+    /// The exact distance to mate in plies for KQK, KRK, KPK and KBNK, positive when the side to
+    /// move mates, negative when it gets mated and 0 for a draw or a finished game. Null for other
+    /// positions and until `buildEndgameTables` has built the table.
+    #[wasm_bindgen(js_name = "dtm")]
+    pub fn dtm(&self) -> Option<i32> {
+        self.distance_to_mate().map(|dtm| dtm.unwrap_or(0))
+    }
+
+    /// The best move of one of the basic endings in UCI notation, or null.
+    #[wasm_bindgen(js_name = "endgameMove")]
+    pub fn endgame_move_js(&mut self) -> Option<String> {
+        self.endgame_move().map(|r#move| r#move.to_uci())
+    }
+
+    /// Builds about `budget` more positions of the distance to mate tables and returns whether
+    /// there is still more to build, to call between frames until it returns false. KBNK, the
+    /// largest, takes about 5 million positions.
+    #[wasm_bindgen(js_name = "buildEndgameTables")]
+    pub fn build_endgame_tables_js(budget: u32) -> bool {
+        Self::build_endgame_tables(budget.into())
+    }
+
+    /// Loads puzzles in the Lichess CSV format, returning how many were read.
+    #[wasm_bindgen(js_name = "loadPuzzles")]
+    pub fn load_puzzles(&mut self, csv: &str) -> Result<usize, JsValue> {
+        self.puzzles = puzzle::parse_puzzles(csv)?;
+        Ok(self.puzzles.len())
+    }
+
+    /// Sets up the loaded puzzle at `index` and plays the opponent's first move.
+    #[wasm_bindgen(js_name = "startPuzzle")]
+    pub fn start_puzzle_js(&mut self, index: usize) -> JsValue {
+        JsValue::from(self.start_puzzle(index).err())
+    }
+
+    /// The puzzle being solved, or null.
+    #[wasm_bindgen(js_name = "puzzle")]
+    pub fn puzzle_js(&self) -> JsValue {
+        match &self.puzzle {
+            Some(state) => serde_wasm_bindgen::to_value(&state.puzzle).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Plays the solver's move in the puzzle, then the opponent's reply, and reports whether
+    /// the puzzle is solved, failed or still going on.
+    #[wasm_bindgen(js_name = "puzzleMove")]
+    pub fn puzzle_move(&mut self, from: &str, to: &str, promotion: Option<String>) -> JsValue {
+        let result = self
+            .parse_move(from, to, promotion)
+            .and_then(|r#move| self.play_puzzle_move(r#move));
+        match result {
+            Ok(report) => serde_wasm_bindgen::to_value(&report).unwrap(),
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// The material in centipawns the move wins or loses once every capture on its target square
+    /// that pays has been made, e.g. negative for a capture that loses material.
+    #[wasm_bindgen(js_name = "see")]
+    pub fn see_js(&self, from: &str, to: &str, promotion: Option<String>) -> Result<i32, JsValue> {
+        let r#move = self.parse_move(from, to, promotion)?;
+        if self.board[r#move.from].is_empty() || !self.is_turn(r#move.from) {
+            return Err(JsValue::from(format!(
+                "Illegal Move \"{}\"",
+                r#move.to_uci()
+            )));
+        }
+        Ok(self.see(r#move))
+    }
+
+    /// The material in centipawns the side to move wins by starting the exchange on `square`.
+    #[wasm_bindgen(js_name = "exchangeValue")]
+    pub fn exchange_value_js(&self, square: &str) -> Result<i32, JsValue> {
+        let pos = algebric_notation::from_string(square)? as usize;
+        Ok(self.exchange_value(pos))
+    }
+
+    /// Lists the tactical motifs of the position, such as forks, pins and hanging pieces, for both sides.
+    #[wasm_bindgen(js_name = "motifs")]
+    pub fn motifs_js(&self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.motifs()).unwrap()
+    }
+
+    /// Lists the tactical motifs a legal move creates, e.g. "Knight on e5 forks King on g6 and Rook on c6".
+    #[wasm_bindgen(js_name = "moveMotifs")]
+    pub fn move_motifs_js(&mut self, from: &str, to: &str, promotion: Option<String>) -> JsValue {
+        let r#move = match self.parse_move(from, to, promotion) {
+            Ok(r#move) => r#move,
+            Err(err) => return JsValue::from(err),
+        };
+        match self.generate_moves().into_iter().find(|legal| {
+            legal.from == r#move.from
+                && legal.to == r#move.to
+                && (r#move.promotion.is_none() || legal.promotion == r#move.promotion)
+        }) {
+            Some(legal) => serde_wasm_bindgen::to_value(&self.move_motifs(legal)).unwrap(),
+            None => JsValue::from(format!("Illegal Move \"{}\"", r#move.to_uci())),
+        }
+    }
+
+    /// Proves or refutes a forced mate in `moves` moves, listing every key and the solution tree.
+    #[wasm_bindgen(js_name = "solveMate")]
+    pub fn solve_mate_js(&mut self, moves: usize) -> JsValue {
+        match self.solve_mate(moves) {
+            Ok(solution) => serde_wasm_bindgen::to_value(&solution).unwrap(),
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Finds every helpmate in `moves` moves, the side to move being mated, and flags cooks.
+    #[wasm_bindgen(js_name = "solveHelpmate")]
+    pub fn solve_helpmate_js(&mut self, moves: usize) -> JsValue {
+        match self.solve_helpmate(moves) {
+            Ok(solution) => serde_wasm_bindgen::to_value(&solution).unwrap(),
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Proves or refutes a selfmate in `moves` moves, the side to move forcing its own mate.
+    #[wasm_bindgen(js_name = "solveSelfmate")]
+    pub fn solve_selfmate_js(&mut self, moves: usize) -> JsValue {
+        match self.solve_selfmate(moves) {
+            Ok(solution) => serde_wasm_bindgen::to_value(&solution).unwrap(),
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Names the opening of the game, e.g. "Sicilian Defense: Najdorf Variation", if it is a known line.
+    #[wasm_bindgen(js_name = "openingName")]
+    pub fn opening_name(&self) -> Option<String> {
+        self.opening().map(|opening| opening.to_string())
+    }
+
+    /// The ECO code, name, variation and moves of the opening of the game, or null.
+    #[wasm_bindgen(js_name = "opening")]
+    pub fn opening_js(&self) -> JsValue {
+        match self.opening() {
+            Some(opening) => serde_wasm_bindgen::to_value(opening).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Writes the game as PGN, filling the ECO and Opening tags when they are not set.
+    #[wasm_bindgen(js_name = "exportPgn")]
+    pub fn export_pgn(&self) -> String {
+        self.to_pgn().to_string()
+    }
+
+    /// Searches every position of the game to `depth` plies and reports each move's centipawn
+    /// loss and classification, each player's accuracy and the PGN annotated with NAGs and evals.
+    #[wasm_bindgen(js_name = "reviewGame")]
+    pub fn review_game(&self, depth: u32) -> JsValue {
+        serde_wasm_bindgen::to_value(&self.review(depth)).unwrap()
+    }
+
+    /// Starts analysing the current position, keeping the best `multi_pv` lines and going no
+    /// deeper than `max_depth` plies. The session works on a copy, so later moves do not change it.
+    #[wasm_bindgen(js_name = "analysisSession")]
+    pub fn analysis_session(&self, multi_pv: usize, max_depth: u32) -> AnalysisSession {
+        AnalysisSession::new(self, multi_pv, max_depth)
+    }
+
+    /// Starts searching the current position without blocking, within limits such as
+    /// `{ depth: 8, nodes: 1000000, movetime: 5000, multiPv: 1 }`, all optional.
+    /// The search then runs through `step` until it is over or stopped.
+    #[wasm_bindgen(js_name = "startSearch")]
+    pub fn start_search_js(&mut self, limits: JsValue) -> Result<(), JsValue> {
+        let limits = if limits.is_undefined() || limits.is_null() {
+            SearchLimits::default()
+        } else {
+            serde_wasm_bindgen::from_value(limits)?
+        };
+        self.start_search(limits);
+        Ok(())
+    }
+
+    /// Searches about `node_budget` more positions and returns the search's depth, nodes,
+    /// score and best line, or null when there is no search or a move was played since it started.
+    #[wasm_bindgen(js_name = "step")]
+    pub fn step_js(&mut self, node_budget: u32) -> JsValue {
+        match self.step_search(node_budget.into()) {
+            Some(info) => serde_wasm_bindgen::to_value(&info).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// Stops the search and returns its last progress, or null when there is no search.
+    #[wasm_bindgen(js_name = "stop")]
+    pub fn stop_js(&mut self) -> JsValue {
+        match self.stop_search() {
+            Some(info) => serde_wasm_bindgen::to_value(&info).unwrap(),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// The best move found by the search so far in UCI notation, or null.
+    #[wasm_bindgen(js_name = "bestSoFar")]
+    pub fn best_so_far_js(&self) -> Option<String> {
+        self.best_so_far()
+    }
+
+    /// Sets an engine option such as "Hash", the transposition table size in megabytes.
+    #[wasm_bindgen(js_name = "setOption")]
+    pub fn set_option_js(&mut self, name: &str, value: &str) -> Result<(), JsValue> {
+        Ok(self.set_option(name, value)?)
+    }
+
+    #[wasm_bindgen(js_name = "getOption")]
+    pub fn get_option_js(&self, name: &str) -> Result<String, JsValue> {
+        Ok(self.get_option(name)?)
+    }
+
+    /// Empties the transposition table, so the next search starts from nothing.
+    #[wasm_bindgen(js_name = "clearHash")]
+    pub fn clear_hash(&self) {
+        self.hash.clear();
+    }
+
+    /// Seeds the random choices of the engine, so its moves can be replayed.
+    #[wasm_bindgen(js_name = "setSeed")]
+    pub fn set_seed(&mut self, seed: u32) {
+        self.random = Random::new(seed.into());
+    }
+
+    /// The move the engine plays in UCI notation, from the endgame tables or the tablebases
+    /// when they cover the position, else weakened by the "Skill Level" or "UCI_Elo" options or found by the MCTS
+    /// player, or null when the game is over. Random choices follow `setSeed`.
+    #[wasm_bindgen(js_name = "engineMove")]
+    pub fn engine_move_js(&mut self) -> Option<String> {
+        self.engine_move().map(|r#move| r#move.to_uci())
+    }
+
+    /// Builds an engine whose `engineMove` plays with "alphabeta" or "mcts".
+    #[wasm_bindgen(js_name = "withPlayer")]
+    pub fn with_player(player: &str) -> Result<ChessEngine, JsValue> {
+        Ok(ChessEngine {
+            player: Player::parse(player)?,
+            ..Default::default()
+        })
+    }
+
+    /// Loads an NNUE network the search evaluates with instead of the handcrafted
+    /// evaluation, returning an error message if it is malformed.
+    #[wasm_bindgen(js_name = "loadNetwork")]
+    pub fn load_network(&mut self, bytes: &[u8]) -> JsValue {
+        match self.load_network_bytes(bytes) {
+            Ok(()) => JsValue::UNDEFINED,
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// Goes back to the handcrafted evaluation.
+    #[wasm_bindgen(js_name = "unloadNetwork")]
+    pub fn unload_network(&mut self) {
+        self.nnue = None;
+    }
+
+    #[wasm_bindgen(js_name = "hasNetwork")]
+    pub fn has_network(&self) -> bool {
+        self.nnue.is_some()
+    }
+
+    /// Loads the weights of the handcrafted evaluation from the text of a weights file, such
+    /// as one written by the tuner, returning an error message if it is malformed.
+    #[wasm_bindgen(js_name = "loadWeights")]
+    pub fn load_weights(&mut self, text: &str) -> JsValue {
+        match Weights::from_text(text) {
+            Ok(weights) => {
+                self.weights = Arc::new(weights);
+                JsValue::UNDEFINED
+            }
+            Err(err) => JsValue::from(err),
+        }
+    }
+
+    /// The weights of the handcrafted evaluation in the text of a weights file.
+    #[wasm_bindgen(js_name = "getWeights")]
+    pub fn get_weights(&self) -> String {
+        self.weights.to_text()
     }
 
     #[wasm_bindgen(js_name = "getFen")]
@@ -103,19 +674,35 @@ impl ChessEngine {
     }
 }
 
+impl ChessEngine {
+    /// Loads a Polyglot `.bin` opening book from a local file.
+    pub fn load_book_file(&mut self, path: &str) -> Result<(), String> {
+        self.book = OpeningBook::from_file(path)?;
+        Ok(())
+    }
+
+    /// Builds a Polyglot `.bin` book from a local PGN file, see [`ChessEngine::build_book`].
+    pub fn build_book_file(
+        pgn_path: &str,
+        book_path: &str,
+        max_ply: usize,
+        min_count: u32,
+    ) -> Result<(), String> {
+        let pgn = std::fs::read_to_string(pgn_path)
+            .map_err(|err| format!("Cannot Read PGN \"{pgn_path}\": {err}"))?;
+        let bytes = Self::build_book_bytes(&pgn, max_ply, min_count)?;
+        std::fs::write(book_path, bytes)
+            .map_err(|err| format!("Cannot Write Book \"{book_path}\": {err}"))
+    }
+}
+
 impl FenParser for ChessEngine {
     fn parse_fen(&mut self, fen_string: &str) -> Result<(), String> {
         let mut parts = fen_string.split_whitespace();
         self.board
             .parse_fen(parts.next().expect("Board String not found"))?;
 
-        let active_color = parts.next().unwrap_or("w");
-        if active_color != "w" && active_color != "b" {
-            return Err(format!(
-                "Invalid Character \"{active_color}\" in active color"
-            ));
-        }
-        self.is_white_turn = active_color == "w";
+        self.parse_active_color(parts.next().unwrap_or("w"))?;
         self.castling.parse_fen(parts.next().unwrap_or("-"))?;
         self.en_passant = match parts.next().unwrap_or("-") {
             "-" => None,
@@ -132,6 +719,7 @@ impl FenParser for ChessEngine {
             Ok(fullmove_number) => fullmove_number,
             Err(_) => return Err(format!("Invalid Fullmove \"{fullmove}\"")),
         };
+        self.reset_history();
         Ok(())
     }
 
@@ -164,17 +752,130 @@ impl Default for ChessEngine {
             half_move_clock: 0,
             full_move_number: 1,
             game_status: GameStatus::InProgress,
+            last_move: None,
+            start_fen: FEN_STARTING_POSITION.to_string(),
+            history: Vec::new(),
+            tags: BTreeMap::new(),
+            book: OpeningBook::default(),
+            random: Random::default(),
+            tablebase: Arc::default(),
+            puzzles: Vec::new(),
+            puzzle: None,
+            threads: 1,
+            player: Player::default(),
+            mcts: MctsConfig::default(),
+            strength: Strength::default(),
+            weights: Arc::default(),
+            nnue: None,
+            hash: Arc::default(),
+            search: None,
         }
     }
 }
 
-impl ToString for ChessEngine {
-    fn to_string(&self) -> String {
-        self.to_fen()
+impl std::fmt::Display for ChessEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.to_fen())
     }
 }
 
 impl ChessEngine {
+    fn parse_active_color(&mut self, active_color: &str) -> Result<(), String> {
+        if active_color != "w" && active_color != "b" {
+            return Err(format!(
+                "Invalid Character \"{active_color}\" in active color"
+            ));
+        }
+        self.is_white_turn = active_color == "w";
+        self.reset_history();
+        Ok(())
+    }
+
+    fn edit_piece(&mut self, square: &str, piece: &str) -> Result<(), String> {
+        let pos = algebric_notation::from_string(square)? as usize;
+        let mut chars = piece.chars();
+        let piece = match (chars.next(), chars.next()) {
+            (Some(ch), None) => Piece::from_char(ch)?,
+            _ => return Err(format!("Invalid Chess Piece \"{piece}\"")),
+        };
+        self.board.put_piece(pos, piece);
+        self.reset_history();
+        Ok(())
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = self.board.validate();
+        if self.board.is_check(!self.is_white_turn) {
+            problems.push("The side not to move is in check".to_string());
+        }
+        let castling_rights = [
+            (
+                self.castling.can_white_king_castle(),
+                'K',
+                Piece::WhiteKing,
+                4,
+                7,
+            ),
+            (
+                self.castling.can_white_queen_castle(),
+                'Q',
+                Piece::WhiteKing,
+                4,
+                0,
+            ),
+            (
+                self.castling.can_black_king_castle(),
+                'k',
+                Piece::BlackKing,
+                116,
+                119,
+            ),
+            (
+                self.castling.can_black_queen_castle(),
+                'q',
+                Piece::BlackKing,
+                116,
+                112,
+            ),
+        ];
+        for (can_castle, ch, king, king_pos, rook_pos) in castling_rights {
+            let rook = if king.is_white() {
+                Piece::WhiteRook
+            } else {
+                Piece::BlackRook
+            };
+            if can_castle && (self.board[king_pos] != king || self.board[rook_pos] != rook) {
+                problems.push(format!(
+                    "Castling \"{ch}\" requires the king and rook on their starting squares"
+                ));
+            }
+        }
+        if let Some(en_passant) = self.en_passant {
+            let pos = en_passant as usize;
+            let (rank, enemy_pawn) = if self.is_white_turn {
+                (5, Piece::BlackPawn)
+            } else {
+                (2, Piece::WhitePawn)
+            };
+            // the rank is checked first, the square of the pawn being off the board otherwise
+            let is_valid = pos >> 4 == rank && self.board[pos].is_empty() && {
+                let pawn_pos = if self.is_white_turn {
+                    pos - 16
+                } else {
+                    pos + 16
+                };
+                self.board[pawn_pos] == enemy_pawn
+            };
+            if !is_valid {
+                problems.push(format!(
+                    "Invalid en passant square \"{}\"",
+                    algebric_notation::to_string(en_passant)
+                ));
+            }
+        }
+        problems
+    }
+
     fn is_turn(&self, pos: usize) -> bool {
         self.board[pos].is_white() == self.is_white_turn
     }
@@ -202,32 +903,158 @@ impl ChessEngine {
         if self.board.is_insufficient_material() {
             return true;
         }
+        self.legal_moves().is_empty()
+    }
+
+    /// Lists every legal move of the side to move as `(from, to)` pairs.
+    fn legal_moves(&self) -> Vec<(usize, usize)> {
+        let mut moves = Vec::new();
         for i in 0..64 {
             let from = utils::convert_postion_to_0x88(i);
-            if !self.is_turn(from) {
+            if self.board[from].is_empty() || !self.is_turn(from) {
                 continue;
             }
-            for j in 0..64 {
-                let to = utils::convert_postion_to_0x88(j);
+            for to in self.board.candidate_targets(from) {
                 if self.test_move(from, to) {
-                    return false;
+                    moves.push((from, to));
                 }
             }
         }
-        true
+        moves
     }
 
-    fn test_move(&mut self, from: usize, to: usize) -> bool {
+    /// Lists every legal move of the side to move, with one move per promotion piece.
+    fn generate_moves(&self) -> Vec<Move> {
+        let promotions = if self.is_white_turn {
+            [
+                Piece::WhiteQueen,
+                Piece::WhiteRook,
+                Piece::WhiteBishop,
+                Piece::WhiteKnight,
+            ]
+        } else {
+            [
+                Piece::BlackQueen,
+                Piece::BlackRook,
+                Piece::BlackBishop,
+                Piece::BlackKnight,
+            ]
+        };
+        let mut moves = Vec::new();
+        for (from, to) in self.legal_moves() {
+            if self.board[from].is_pawn() && matches!(to >> 4, 0 | 7) {
+                moves.extend(
+                    promotions
+                        .iter()
+                        .map(|&promotion| Move::new(from, to, Some(promotion))),
+                );
+            } else {
+                moves.push(Move::new(from, to, None));
+            }
+        }
+        moves
+    }
+
+    /// Makes a move known to be legal without recording it in the history,
+    /// returning what [`ChessEngine::unmake`] needs to take it back.
+    fn make(&mut self, r#move: Move) -> Undo {
+        let undo = Undo {
+            board: self.board,
+            castling: self.castling,
+            is_white_turn: self.is_white_turn,
+            en_passant: self.en_passant,
+            half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
+            last_move: self.last_move,
+        };
+        let changes = self.make_move(r#move.from, r#move.to, r#move.promotion);
+        if let Some(nnue) = &mut self.nnue {
+            nnue.push(&undo.board, &self.board, &changes);
+        }
+        if !self.is_white_turn {
+            self.full_move_number += 1;
+        }
+        self.is_white_turn = !self.is_white_turn;
+        self.last_move = Some((r#move.from, r#move.to));
+        undo
+    }
+
+    fn unmake(&mut self, undo: Undo) {
+        if let Some(nnue) = &mut self.nnue {
+            nnue.pop();
+        }
+        self.board = undo.board;
+        self.castling = undo.castling;
+        self.is_white_turn = undo.is_white_turn;
+        self.en_passant = undo.en_passant;
+        self.half_move_clock = undo.half_move_clock;
+        self.full_move_number = undo.full_move_number;
+        self.last_move = undo.last_move;
+    }
+
+    fn build_snapshot(&mut self) -> Snapshot {
+        let square = |pos: usize| algebric_notation::to_string(pos as u8);
+        let mut pieces = BTreeMap::new();
+        for i in 0..64 {
+            let pos = utils::convert_postion_to_0x88(i);
+            if !self.board[pos].is_empty() {
+                pieces.insert(square(pos), char::from(self.board[pos]));
+            }
+        }
+        let check = if self.board.is_check(self.is_white_turn) {
+            self.board.king(self.is_white_turn).map(square)
+        } else {
+            None
+        };
+        Snapshot {
+            pieces,
+            turn: if self.is_white_turn { 'w' } else { 'b' },
+            castling: CastlingRights {
+                white_king_side: self.castling.can_white_king_castle(),
+                white_queen_side: self.castling.can_white_queen_castle(),
+                black_king_side: self.castling.can_black_king_castle(),
+                black_queen_side: self.castling.can_black_queen_castle(),
+            },
+            en_passant: self.en_passant.map(algebric_notation::to_string),
+            half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
+            last_move: self.last_move.map(|(from, to)| MoveSnapshot {
+                from: square(from),
+                to: square(to),
+            }),
+            check,
+            status: self.game_status.to_string(),
+            legal_moves: self
+                .legal_moves()
+                .into_iter()
+                .map(|(from, to)| MoveSnapshot {
+                    from: square(from),
+                    to: square(to),
+                })
+                .collect(),
+        }
+    }
+
+    fn test_move(&self, from: usize, to: usize) -> bool {
         if !self.is_legal_move(from, to) {
             return false;
         }
-        let to_elem = self.board[to];
-        self.board[to] = self.board[from];
-        self.board[from] = Piece::Empty;
-        let is_check = self.board.is_check(self.is_white_turn);
-        self.board[from] = self.board[to];
-        self.board[to] = to_elem;
-        !is_check
+        !self.board_after(from, to).is_check(self.is_white_turn)
+    }
+
+    /// The piece placement after the move, without touching castling, en passant or the clocks.
+    fn board_after(&self, from: usize, to: usize) -> Board {
+        let mut board = self.board;
+        if board[from].is_pawn() && board[to].is_empty() && !from.abs_diff(to).is_multiple_of(16) {
+            let captured = if board[from].is_white() {
+                to - 16
+            } else {
+                to + 16
+            };
+            board[captured] = Piece::Empty;
+        }
+        board.r#move(from, to);
+        board
     }
 
     fn is_legal_move(&self, from: usize, to: usize) -> bool {
@@ -243,103 +1070,417 @@ impl ChessEngine {
                 || self.board.is_legal_pawn_move(from, to, self.en_passant))
     }
 
-    fn make_move(&mut self, from: usize, to: usize) {
+    fn why_illegal_move(&mut self, from: usize, to: usize) -> Option<IllegalMove> {
         let piece = self.board[from];
-        let diff = (from as i8 - to as i8).abs();
+        if piece.is_empty() {
+            return Some(IllegalMove::NoPiece);
+        }
+        if !self.is_turn(from) {
+            return Some(IllegalMove::NotYourTurn);
+        }
+        if from == to {
+            return Some(IllegalMove::WrongMovementPattern);
+        }
+        if !self.board[to].is_empty() && self.board[to].is_white() == piece.is_white() {
+            return Some(IllegalMove::OwnPieceOnTarget);
+        }
+        if !self.is_legal_move(from, to) {
+            return Some(self.why_wrong_pattern(from, to));
+        }
+        if self.test_move(from, to) {
+            return None;
+        }
+        if piece.is_king() {
+            return Some(IllegalMove::MovesIntoCheck);
+        }
+        if self.board.is_check(self.is_white_turn) {
+            return Some(IllegalMove::KingInCheck);
+        }
+        self.board_after(from, to)
+            .checker(self.is_white_turn)
+            .map(IllegalMove::Pinned)
+    }
+
+    /// Classifies a move rejected by the `is_legal_*_move` checks.
+    fn why_wrong_pattern(&self, from: usize, to: usize) -> IllegalMove {
+        let piece = self.board[from];
+        let white = piece.is_white();
+        if piece.is_king() {
+            let (home, king_side) = if white { (4, 6) } else { (116, 118) };
+            if from != home || (to != king_side && to != king_side - 4) {
+                if matches!(from.abs_diff(to), 1 | 15 | 16 | 17) {
+                    return IllegalMove::MovesIntoCheck;
+                }
+                return IllegalMove::WrongMovementPattern;
+            }
+            let (has_right, path) = match (white, to == king_side) {
+                (true, true) => (self.castling.can_white_king_castle(), vec![5, 6]),
+                (true, false) => (self.castling.can_white_queen_castle(), vec![3, 2, 1]),
+                (false, true) => (self.castling.can_black_king_castle(), vec![117, 118]),
+                (false, false) => (self.castling.can_black_queen_castle(), vec![115, 114, 113]),
+            };
+            if !has_right {
+                return IllegalMove::CastlingRightLost;
+            }
+            if let Some(&square) = path.iter().find(|&&square| !self.board[square].is_empty()) {
+                return IllegalMove::PathBlocked(square);
+            }
+            if self.board.is_check(white) {
+                return IllegalMove::KingInCheck;
+            }
+            if self.board.attacker(path[0], !white).is_some() {
+                return IllegalMove::CastlingThroughCheck(path[0]);
+            }
+            return IllegalMove::MovesIntoCheck;
+        }
+        if piece.is_pawn() {
+            let (step, start_rank, en_passant_rank, enemy_pawn) = if white {
+                (16, 1, 4, Piece::BlackPawn)
+            } else {
+                (-16, 6, 3, Piece::WhitePawn)
+            };
+            let delta = to as isize - from as isize;
+            if delta == step {
+                return IllegalMove::PathBlocked(to);
+            }
+            if delta == 2 * step && from / 16 == start_rank {
+                let middle = (from as isize + step) as usize;
+                if self.board[middle].is_empty() {
+                    return IllegalMove::PathBlocked(to);
+                }
+                return IllegalMove::PathBlocked(middle);
+            }
+            if (delta == step + 1 || delta == step - 1)
+                && from / 16 == en_passant_rank
+                && self.board[(to as isize - step) as usize] == enemy_pawn
+            {
+                return IllegalMove::EnPassantNotAvailable;
+            }
+            return IllegalMove::WrongMovementPattern;
+        }
+        match self.board.blocking_square(from, to) {
+            Some(square) => IllegalMove::PathBlocked(square),
+            None => IllegalMove::WrongMovementPattern,
+        }
+    }
+
+    fn parse_promotion(&self, promotion: &str) -> Result<Piece, String> {
+        let piece = match promotion.chars().next() {
+            Some(ch) if promotion.len() == 1 && self.is_white_turn => {
+                Piece::from_char(ch.to_ascii_uppercase())?
+            }
+            Some(ch) if promotion.len() == 1 => Piece::from_char(ch.to_ascii_lowercase())?,
+            _ => return Err(format!("Invalid Promotion \"{promotion}\"")),
+        };
+        if piece.is_pawn() || piece.is_king() {
+            return Err(format!("Invalid Promotion \"{promotion}\""));
+        }
+        Ok(piece)
+    }
+
+    /// Reads a move given as squares in algebraic notation and an optional promotion letter.
+    fn parse_move(&self, from: &str, to: &str, promotion: Option<String>) -> Result<Move, String> {
+        let from = algebric_notation::from_string(from)? as usize;
+        let to = algebric_notation::from_string(to)? as usize;
+        let promotion = promotion
+            .map(|promotion| self.parse_promotion(&promotion))
+            .transpose()?;
+        Ok(Move::new(from, to, promotion))
+    }
+
+    /// Replays the game from its start position, listing the moves with the position before each one.
+    fn replay(&self) -> Vec<(ChessEngine, Move)> {
+        let mut engine = ChessEngine::new();
+        engine
+            .parse_fen(&self.start_fen)
+            .expect("The start position is always valid");
+        self.history
+            .iter()
+            .map(|entry| {
+                let r#move = Move::new(entry.from, entry.to, entry.promotion);
+                let before = engine.clone();
+                engine.make(r#move);
+                (before, r#move)
+            })
+            .collect()
+    }
+
+    /// Finds the deepest position of the game that is in the ECO table.
+    fn opening(&self) -> Option<&'static Opening> {
+        let table = EcoTable::embedded();
+        std::iter::once(self.polyglot_key())
+            .chain(
+                self.replay()
+                    .iter()
+                    .rev()
+                    .map(|(engine, _)| engine.polyglot_key()),
+            )
+            .find_map(|key| table.lookup(key))
+    }
+
+    fn to_pgn(&self) -> PgnGame {
+        let mut game = PgnGame {
+            tags: self.tags.clone(),
+            moves: self
+                .replay()
+                .into_iter()
+                .map(|(mut engine, r#move)| engine.move_to_san(r#move))
+                .collect(),
+            annotations: Vec::new(),
+            result: self.game_status.result().to_string(),
+        };
+        if self.start_fen != FEN_STARTING_POSITION {
+            game.tags.insert("SetUp".to_string(), "1".to_string());
+            game.tags.insert("FEN".to_string(), self.start_fen.clone());
+        }
+        if let Some(opening) = self.opening() {
+            game.tags
+                .entry("ECO".to_string())
+                .or_insert_with(|| opening.eco.clone());
+            game.tags
+                .entry("Opening".to_string())
+                .or_insert_with(|| opening.name.clone());
+            if let Some(variation) = &opening.variation {
+                game.tags
+                    .entry("Variation".to_string())
+                    .or_insert_with(|| variation.clone());
+            }
+        }
+        game
+    }
+
+    fn build_book_bytes(pgn: &str, max_ply: usize, min_count: u32) -> Result<Vec<u8>, String> {
+        let mut builder = BookBuilder::new(max_ply, min_count);
+        for (index, game) in pgn::parse_pgn(pgn)?.iter().enumerate() {
+            builder
+                .add_game(game)
+                .map_err(|err| format!("{err} in game {}", index + 1))?;
+        }
+        Ok(builder.to_bytes())
+    }
+
+    fn legal_book_moves(&self) -> Vec<BookMove> {
+        self.book
+            .moves(self.polyglot_key(), &self.board)
+            .into_iter()
+            .filter(|book_move| self.test_move(book_move.from, book_move.to))
+            .collect()
+    }
+
+    /// Starts a new move history from the current position.
+    fn reset_history(&mut self) {
+        self.start_fen = self.to_fen();
+        self.history.clear();
+        self.last_move = None;
+    }
+
+    fn to_record(&self) -> GameRecord {
+        GameRecord {
+            version: GAME_RECORD_VERSION,
+            start_position: self.start_fen.clone(),
+            moves: self
+                .history
+                .iter()
+                .map(|entry| RecordedMove {
+                    uci: algebric_notation::to_uci(
+                        entry.from as u8,
+                        entry.to as u8,
+                        entry.promotion.map(char::from),
+                    ),
+                    timestamp: entry.timestamp,
+                })
+                .collect(),
+            result: self.game_status.result().to_string(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    /// Replaces the game with the one in `json`, replaying its moves from the start position.
+    fn load_record(&mut self, json: &str) -> Result<(), String> {
+        let record = GameRecord::from_json(json)?;
+        let result = GameStatus::from_result(&record.result)?;
+        let mut engine = ChessEngine::new();
+        engine.parse_fen(&record.start_position)?;
+        engine.game_status = engine.get_status();
+        for (ply, recorded) in record.moves.iter().enumerate() {
+            let (from, to, promotion) = algebric_notation::from_uci(&recorded.uci)?;
+            let promotion = promotion
+                .map(|promotion| engine.parse_promotion(&promotion.to_string()))
+                .transpose()?;
+            if engine.play(from.into(), to.into(), promotion).is_none() {
+                return Err(format!(
+                    "Illegal Move \"{}\" at ply {}",
+                    recorded.uci,
+                    ply + 1
+                ));
+            }
+            engine.history[ply].timestamp = recorded.timestamp;
+        }
+        engine.tags = record.tags;
+        // a resigned or agreed game ends without the moves deciding it
+        if engine.game_status == GameStatus::InProgress && result != GameStatus::InProgress {
+            engine.game_status = result;
+            engine
+                .tags
+                .insert("Result".to_string(), record.result.clone());
+        }
+        engine.book = std::mem::take(&mut self.book);
+        engine.random = self.random.clone();
+        engine.tablebase = self.tablebase.clone();
+        engine.hash = self.hash.clone();
+        engine.strength = self.strength;
+        engine.player = self.player;
+        engine.mcts = self.mcts;
+        engine.threads = self.threads;
+        engine.weights = self.weights.clone();
+        engine.nnue = self.nnue.take();
+        engine.puzzles = std::mem::take(&mut self.puzzles);
+        *self = engine;
+        Ok(())
+    }
+
+    /// Plays a move for the side to move if it is legal, and reports what changed.
+    fn play(&mut self, from: usize, to: usize, promotion: Option<Piece>) -> Option<MoveDelta> {
+        if !self.test_move(from, to) {
+            return None;
+        }
+        let piece = self.board[from];
+        let changes = self.make_move(from, to, promotion);
+        if !self.is_white_turn {
+            self.full_move_number += 1;
+        }
+        self.is_white_turn = !self.is_white_turn;
+        self.last_move = Some((from, to));
+        self.game_status = self.get_status();
+        let check = self.board.is_check(self.is_white_turn);
+        self.history.push(HistoryEntry {
+            from,
+            to,
+            promotion: changes.iter().find_map(|change| match change {
+                SquareChange::Placed { piece, .. } => Some(*piece),
+                _ => None,
+            }),
+            timestamp: utils::now(),
+        });
+        Some(MoveDelta {
+            capture: changes.iter().any(|change| {
+                matches!(change, SquareChange::Removed { piece: removed, .. } if removed.is_white() != piece.is_white())
+            }),
+            check,
+            castle: piece.is_king() && from.abs_diff(to) == 2,
+            promotion: changes
+                .iter()
+                .any(|change| matches!(change, SquareChange::Placed { .. })),
+            checkmate: check && self.generate_moves().is_empty(),
+            changes,
+        })
+    }
+
+    /// Moves the piece without checking legality, updating castling, en passant and the clocks,
+    /// and returns the squares that changed.
+    fn make_move(&mut self, from: usize, to: usize, promotion: Option<Piece>) -> Vec<SquareChange> {
+        let piece = self.board[from];
+        let mut changes = Vec::new();
         /* If no capture or pawn move, increment half move clock */
         if self.board[to].is_empty() {
             self.half_move_clock += 1;
         } else {
             self.half_move_clock = 0;
+            changes.push(SquareChange::Removed {
+                piece: self.board[to],
+                square: to,
+            });
         }
-        /* Update king position, castling and en passant */
+        changes.push(SquareChange::Moved { piece, from, to });
+        let en_passant = self.en_passant.take();
+        /* Update castling, en passant and move the rook when castling */
         match piece {
-            Piece::WhiteKing => {
-                if diff == 2 {
-                    if to == 2 {
-                        self.board[0] = Piece::Empty;
-                        self.board[3] = Piece::WhiteRook;
-                    } else if to == 6 {
-                        self.board[7] = Piece::Empty;
-                        self.board[5] = Piece::WhiteRook;
-                    }
+            Piece::WhiteKing | Piece::BlackKing => {
+                if from.abs_diff(to) == 2 {
+                    let (rook_from, rook_to) = if to > from {
+                        (from + 3, from + 1)
+                    } else {
+                        (from - 4, from - 1)
+                    };
+                    changes.push(SquareChange::Moved {
+                        piece: self.board[rook_from],
+                        from: rook_from,
+                        to: rook_to,
+                    });
+                    self.board.r#move(rook_from, rook_to);
                 }
-                self.castling.reset_white_king_castling();
-                self.castling.reset_white_queen_castling();
-            }
-            Piece::BlackKing => {
-                if diff == 2 {
-                    if to == 118 {
-                        self.board[119] = Piece::Empty;
-                        self.board[117] = Piece::BlackRook;
-                    } else if to == 112 {
-                        self.board[112] = Piece::Empty;
-                        self.board[115] = Piece::BlackRook;
-                    }
-                }
-                self.castling.reset_black_king_castling();
-                self.castling.reset_black_queen_castling();
-            }
-            Piece::BlackRook => {
-                if from == 112 {
-                    self.castling.reset_white_queen_castling()
-                } else if from == 7 {
-                    self.castling.reset_white_king_castling()
-                }
-            }
-            Piece::WhiteRook => {
-                if from == 0 {
-                    self.castling.reset_black_queen_castling()
-                } else if from == 7 {
-                    self.castling.reset_black_king_castling()
-                }
-            }
-            Piece::WhitePawn => {
-                self.half_move_clock = 0;
-                if let Some(pos) = self.en_passant {
-                    if to == pos as usize && self.board[to - 16].is_black() {
-                        self.board[to - 16] = Piece::Empty;
-                    }
-                    self.en_passant = None;
-                } else if to - from == 32 {
-                    self.en_passant = Some((from + 16) as u8);
+                if piece.is_white() {
+                    self.castling.reset_white_king_castling();
+                    self.castling.reset_white_queen_castling();
                 } else {
-                    self.en_passant = None;
+                    self.castling.reset_black_king_castling();
+                    self.castling.reset_black_queen_castling();
                 }
             }
-            Piece::BlackPawn => {
+            Piece::WhitePawn | Piece::BlackPawn => {
                 self.half_move_clock = 0;
-                if let Some(pos) = self.en_passant {
-                    if to == pos as usize && self.board[to + 16].is_white() {
-                        self.board[to + 16] = Piece::Empty;
-                    }
-                    self.en_passant = None;
-                } else if from - to == 32 {
-                    self.en_passant = Some((from - 16) as u8);
+                let captured = if piece.is_white() {
+                    to.wrapping_sub(16)
                 } else {
-                    self.en_passant = None;
+                    to + 16
+                };
+                if en_passant == Some(to as u8) && from.abs_diff(to) != 16 {
+                    changes.push(SquareChange::Removed {
+                        piece: self.board[captured],
+                        square: captured,
+                    });
+                    self.board[captured] = Piece::Empty;
+                } else if from.abs_diff(to) == 32 {
+                    self.en_passant = Some(((from + to) / 2) as u8);
                 }
             }
-            _ => {
-                self.en_passant = None;
+            _ => {}
+        }
+        /* A rook leaving or captured on its starting square loses its castling right */
+        for square in [from, to] {
+            match square {
+                0 => self.castling.reset_white_queen_castling(),
+                7 => self.castling.reset_white_king_castling(),
+                112 => self.castling.reset_black_queen_castling(),
+                119 => self.castling.reset_black_king_castling(),
+                _ => {}
             }
         }
 
         self.board.r#move(from, to);
+        if piece.is_pawn() && matches!(to >> 4, 0 | 7) {
+            let promoted = promotion.unwrap_or(if piece.is_white() {
+                Piece::WhiteQueen
+            } else {
+                Piece::BlackQueen
+            });
+            self.board[to] = promoted;
+            changes.push(SquareChange::Removed { piece, square: to });
+            changes.push(SquareChange::Placed {
+                piece: promoted,
+                square: to,
+            });
+        }
+        changes
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    const FEN_STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn play_squares(chess_position: &mut ChessEngine, from: &str, to: &str) -> Option<MoveDelta> {
+        let from = algebric_notation::from_string(from).unwrap().into();
+        let to = algebric_notation::from_string(to).unwrap().into();
+        chess_position.play(from, to, None)
+    }
 
     #[test]
     fn test_parse_fen_string() {
         let input = FEN_STARTING_POSITION;
         let mut chess_position = ChessEngine::new();
         chess_position.parse_fen(input).unwrap();
-        assert_eq!(chess_position.is_white_turn, true);
+        assert!(chess_position.is_white_turn);
         assert_eq!(chess_position.to_string(), input);
     }
 
@@ -348,7 +1489,7 @@ mod tests {
         let input = "Jnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
         let mut chess_position = ChessEngine::new();
         let result: Result<(), String> = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result.unwrap_err().contains("Invalid Chess Piece \"J\""));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8";
@@ -357,38 +1498,38 @@ mod tests {
 
         let input = "rnbqkbnr/pppppppp/8/0/8/8/PPPPPPPP/RNBQKBNR";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result
             .unwrap_err()
             .contains("Invalid Character \"0\" in Board"));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR J KQkq - 0 1";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result
             .unwrap_err()
             .contains("Invalid Character \"J\" in active color"));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w JQkq - 0 1";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result
             .unwrap_err()
             .contains("Invalid Character in Castling \"J\""));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq JJ 0 1";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result.unwrap_err().contains("Invalid File \"J\""));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - JJ 1";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result.unwrap_err().contains("Invalid Halfmove \"JJ\""));
 
         let input = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 JJ";
         let result = chess_position.parse_fen(input);
-        assert_eq!(result.is_err(), true);
+        assert!(result.is_err());
         assert!(result.unwrap_err().contains("Invalid Fullmove \"JJ\""));
     }
 
@@ -409,9 +1550,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
+            assert!(
                 chess_position.can_drop(from, to),
-                true,
                 "{}->{}| {}",
                 from,
                 to,
@@ -437,9 +1577,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
-                chess_position.can_drop(from, to),
-                false,
+            assert!(
+                !chess_position.can_drop(from, to),
                 "{}->{}| {}",
                 from,
                 to,
@@ -510,9 +1649,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
+            assert!(
                 chess_position.can_drop(from, to),
-                true,
                 "{}->{}| {}",
                 from,
                 to,
@@ -530,9 +1668,8 @@ mod tests {
         )];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
-                chess_position.can_drop(from, to),
-                false,
+            assert!(
+                !chess_position.can_drop(from, to),
                 "{}->{}| {}",
                 from,
                 to,
@@ -582,9 +1719,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
-                chess_position.can_drop(from, to),
-                false,
+            assert!(
+                !chess_position.can_drop(from, to),
                 "{}->{}| {}",
                 from,
                 to,
@@ -621,9 +1757,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
-            assert_eq!(
+            assert!(
                 chess_position.can_drop(from, to),
-                true,
                 "{}->{}| {}",
                 from,
                 to,
@@ -637,12 +1772,12 @@ mod tests {
         let mut chess_position = ChessEngine::new();
         let fen = "r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1";
         chess_position.set_board(fen);
-        assert_eq!(chess_position.can_drop("e1", "g1"), true);
-        assert_eq!(chess_position.can_drop("e1", "c1"), true);
-        chess_position.r#move("e1", "g1");
-        assert_eq!(chess_position.can_drop("e8", "g8"), true);
-        assert_eq!(chess_position.can_drop("e8", "c8"), true);
-        chess_position.r#move("e8", "c8");
+        assert!(chess_position.can_drop("e1", "g1"));
+        assert!(chess_position.can_drop("e1", "c1"));
+        play_squares(&mut chess_position, "e1", "g1");
+        assert!(chess_position.can_drop("e8", "g8"));
+        assert!(chess_position.can_drop("e8", "c8"));
+        play_squares(&mut chess_position, "e8", "c8");
     }
 
     #[test]
@@ -664,9 +1799,8 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to), status)| {
             chess_position.set_board(fen);
-            assert_eq!(
-                chess_position.can_drop(from, to),
-                false,
+            assert!(
+                !chess_position.can_drop(from, to),
                 "{}->{}| {}",
                 from,
                 to,
@@ -703,9 +1837,121 @@ mod tests {
         ];
         arr.into_iter().for_each(|(fen, (from, to))| {
             chess_position.set_board(fen);
+            assert!(
+                !chess_position.can_drop(from, to),
+                "{}->{}| {}",
+                from,
+                to,
+                fen
+            );
+        });
+    }
+
+    #[test]
+    fn test_why_illegal() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, (&str, &str), Option<IllegalMove>)> = vec![
+            (FEN_STARTING_POSITION, ("e2", "e4"), None),
+            (
+                FEN_STARTING_POSITION,
+                ("e4", "e5"),
+                Some(IllegalMove::NoPiece),
+            ),
+            (
+                FEN_STARTING_POSITION,
+                ("e7", "e5"),
+                Some(IllegalMove::NotYourTurn),
+            ),
+            (
+                FEN_STARTING_POSITION,
+                ("d1", "d2"),
+                Some(IllegalMove::OwnPieceOnTarget),
+            ),
+            (
+                FEN_STARTING_POSITION,
+                ("g1", "g3"),
+                Some(IllegalMove::WrongMovementPattern),
+            ),
+            (
+                FEN_STARTING_POSITION,
+                ("f1", "c4"),
+                Some(IllegalMove::PathBlocked(20)),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/4n3/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                ("e2", "e4"),
+                Some(IllegalMove::PathBlocked(36)),
+            ),
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/7q/3P4/PPP1PPPP/RNBQKBNR w KQkq - 0 1",
+                ("f2", "f3"),
+                Some(IllegalMove::Pinned(55)),
+            ),
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/7q/5P2/PPPPP1PP/RNBQKBNR w KQkq - 0 1",
+                ("a2", "a3"),
+                Some(IllegalMove::KingInCheck),
+            ),
+            (
+                "4k3/8/8/8/8/8/P7/4K2r w - - 0 1",
+                ("a2", "a3"),
+                Some(IllegalMove::KingInCheck),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2r w - - 0 1",
+                ("e1", "d1"),
+                Some(IllegalMove::MovesIntoCheck),
+            ),
+            ("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1", ("e1", "f1"), None),
+            ("R3k3/8/8/8/8/8/8/4K3 b - - 0 1", ("e8", "d7"), None),
+            (
+                "4k3/8/8/8/8/8/8/r3K2R w K - 0 1",
+                ("e1", "g1"),
+                Some(IllegalMove::KingInCheck),
+            ),
+            (
+                "8/8/8/K1pP3r/8/8/8/4k3 w - c6 0 1",
+                ("d5", "c6"),
+                Some(IllegalMove::Pinned(71)),
+            ),
+            (
+                "4k3/8/8/8/8/8/r7/4K3 w - - 0 1",
+                ("e1", "e2"),
+                Some(IllegalMove::MovesIntoCheck),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K2R w - - 0 1",
+                ("e1", "g1"),
+                Some(IllegalMove::CastlingRightLost),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4KN1R w K - 0 1",
+                ("e1", "g1"),
+                Some(IllegalMove::PathBlocked(5)),
+            ),
+            (
+                "4kr2/8/8/8/8/8/8/4K2R w K - 0 1",
+                ("e1", "g1"),
+                Some(IllegalMove::CastlingThroughCheck(5)),
+            ),
+            (
+                "4k1r1/8/8/8/8/8/8/4K2R w K - 0 1",
+                ("e1", "g1"),
+                Some(IllegalMove::MovesIntoCheck),
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 2",
+                ("d5", "e6"),
+                Some(IllegalMove::EnPassantNotAvailable),
+            ),
+        ];
+        arr.into_iter().for_each(|(fen, (from, to), reason)| {
+            chess_position.parse_fen(fen).unwrap();
+            let from = algebric_notation::from_string(from).unwrap().into();
+            let to = algebric_notation::from_string(to).unwrap().into();
             assert_eq!(
-                chess_position.can_drop(from, to),
-                false,
+                chess_position.why_illegal_move(from, to),
+                reason,
                 "{}->{}| {}",
                 from,
                 to,
@@ -713,4 +1959,381 @@ mod tests {
             );
         });
     }
+
+    #[test]
+    fn test_edit_position() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.clear_board();
+        assert_eq!(chess_position.get_fen(), "8/8/8/8/8/8/8/8 w - - 0 1");
+        assert_eq!(chess_position.validate().len(), 2);
+
+        chess_position.edit_piece("e1", "K").unwrap();
+        chess_position.edit_piece("e8", "k").unwrap();
+        chess_position.edit_piece("a1", "R").unwrap();
+        chess_position.edit_piece("d8", "q").unwrap();
+        assert!(chess_position.edit_piece("z9", "K").is_err());
+        assert!(chess_position.edit_piece("a2", "X").is_err());
+        assert!(chess_position.edit_piece("a2", "PP").is_err());
+        assert!(chess_position.validate().is_empty());
+
+        chess_position.edit_piece("d8", " ").unwrap();
+        chess_position.edit_piece("e7", "Q").unwrap();
+        assert_eq!(
+            chess_position.validate(),
+            vec!["The side not to move is in check".to_string()]
+        );
+        chess_position.parse_active_color("b").unwrap();
+        assert!(chess_position.validate().is_empty());
+        assert!(chess_position.parse_active_color("x").is_err());
+
+        chess_position.set_castling_rights(false, true, true, false);
+        assert_eq!(
+            chess_position.validate(),
+            vec!["Castling \"k\" requires the king and rook on their starting squares".to_string()]
+        );
+        chess_position.set_castling_rights(false, true, false, false);
+        chess_position.en_passant = Some(algebric_notation::from_string("e3").unwrap());
+        assert_eq!(
+            chess_position.validate(),
+            vec!["Invalid en passant square \"e3\"".to_string()]
+        );
+        chess_position.en_passant = Some(algebric_notation::from_string("h8").unwrap());
+        assert_eq!(
+            chess_position.validate(),
+            vec!["Invalid en passant square \"h8\"".to_string()]
+        );
+        chess_position.parse_active_color("w").unwrap();
+        chess_position.en_passant = Some(algebric_notation::from_string("a1").unwrap());
+        assert_eq!(
+            chess_position.validate(),
+            vec![
+                "The side not to move is in check".to_string(),
+                "Invalid en passant square \"a1\"".to_string()
+            ]
+        );
+        chess_position.parse_active_color("b").unwrap();
+        chess_position.en_passant = None;
+        assert_eq!(chess_position.get_fen(), "4k3/4Q3/8/8/8/8/8/R3K3 b Q - 0 1");
+        assert!(chess_position.can_drop("e8", "e7"));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut chess_position = ChessEngine::new();
+        let snapshot = chess_position.build_snapshot();
+        assert_eq!(snapshot.pieces.len(), 32);
+        assert_eq!(snapshot.pieces["e1"], 'K');
+        assert_eq!(snapshot.turn, 'w');
+        assert!(snapshot.castling.black_queen_side);
+        assert_eq!(snapshot.legal_moves.len(), 20);
+        assert_eq!(snapshot.last_move, None);
+        assert_eq!(snapshot.check, None);
+
+        play_squares(&mut chess_position, "e2", "e4");
+        let snapshot = chess_position.build_snapshot();
+        assert_eq!(snapshot.turn, 'b');
+        assert_eq!(snapshot.en_passant, Some("e3".to_string()));
+        assert_eq!(
+            snapshot.last_move,
+            Some(MoveSnapshot {
+                from: "e2".to_string(),
+                to: "e4".to_string()
+            })
+        );
+
+        chess_position
+            .parse_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+            .unwrap();
+        let snapshot = chess_position.build_snapshot();
+        assert_eq!(snapshot.check, Some("e1".to_string()));
+        assert!(snapshot.legal_moves.is_empty());
+    }
+
+    #[test]
+    fn test_move_delta() {
+        let mut chess_position = ChessEngine::new();
+        assert_eq!(play_squares(&mut chess_position, "e2", "e5"), None);
+        let delta = play_squares(&mut chess_position, "e2", "e4").unwrap();
+        assert_eq!(
+            delta.changes,
+            vec![SquareChange::Moved {
+                piece: Piece::WhitePawn,
+                from: 20,
+                to: 52
+            }]
+        );
+        assert!(!delta.capture && !delta.check && !delta.castle);
+
+        chess_position
+            .parse_fen("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R b KQkq - 0 1")
+            .unwrap();
+        let delta = play_squares(&mut chess_position, "e8", "c8").unwrap();
+        assert!(delta.castle);
+        assert_eq!(
+            delta.changes[1],
+            SquareChange::Moved {
+                piece: Piece::BlackRook,
+                from: 112,
+                to: 115
+            }
+        );
+        assert_eq!(
+            chess_position.get_fen(),
+            "2kr3r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQ - 1 2"
+        );
+
+        chess_position
+            .parse_fen("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1")
+            .unwrap();
+        let delta = play_squares(&mut chess_position, "d5", "e6").unwrap();
+        assert!(delta.capture);
+        assert!(delta.changes.contains(&SquareChange::Removed {
+            piece: Piece::BlackPawn,
+            square: 68
+        }));
+        assert_eq!(chess_position.get_fen(), "4k3/8/4P3/8/8/8/8/4K3 b - - 0 1");
+
+        chess_position
+            .parse_fen("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1")
+            .unwrap();
+        let delta = chess_position
+            .play(98, 115, Some(Piece::WhiteKnight))
+            .unwrap();
+        assert!(delta.capture && delta.promotion && !delta.check);
+        assert_eq!(
+            delta.changes[3],
+            SquareChange::Placed {
+                piece: Piece::WhiteKnight,
+                square: 115
+            }
+        );
+        assert_eq!(chess_position.get_fen(), "3Nk3/8/8/8/8/8/8/4K3 b - - 0 1");
+
+        chess_position
+            .parse_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2")
+            .unwrap();
+        let delta = play_squares(&mut chess_position, "d8", "h4").unwrap();
+        assert!(delta.check && delta.checkmate);
+
+        // a lone bishop cannot mate, but its check is no mate either
+        chess_position
+            .parse_fen("4k3/8/8/8/8/8/8/4KB2 w - - 0 1")
+            .unwrap();
+        let delta = play_squares(&mut chess_position, "f1", "b5").unwrap();
+        assert!(delta.check && !delta.checkmate);
+    }
+
+    #[test]
+    fn test_en_passant_after_double_push() {
+        let mut chess_position = ChessEngine::new();
+        play_squares(&mut chess_position, "e2", "e4");
+        play_squares(&mut chess_position, "d7", "d5");
+        assert_eq!(
+            chess_position.get_fen(),
+            "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2"
+        );
+    }
+
+    #[test]
+    fn test_save_and_load_game() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1")
+            .unwrap();
+        chess_position.set_tag("White", "Alice");
+        chess_position.play(96, 112, Some(Piece::WhiteRook));
+        play_squares(&mut chess_position, "e8", "d7");
+        let json = chess_position.save_game();
+
+        let mut restored = ChessEngine::new();
+        restored.load_record(&json).unwrap();
+        assert_eq!(restored.get_fen(), "R7/3k4/8/8/8/8/8/4K3 w - - 1 2");
+        assert_eq!(restored.history, chess_position.history);
+        assert_eq!(restored.tags["White"], "Alice");
+        assert_eq!(restored.last_move, Some((116, 99)));
+        assert_eq!(restored.save_game(), json);
+
+        let record = restored.to_record();
+        assert_eq!(record.moves[0].uci, "a7a8r");
+        assert_eq!(record.result, "*");
+
+        chess_position.game_status = GameStatus::BlackWon;
+        let resigned = chess_position.save_game();
+        restored.load_record(&resigned).unwrap();
+        assert_eq!(restored.game_status, GameStatus::BlackWon);
+        assert_eq!(restored.tags["Result"], "0-1");
+        assert!(restored.export_pgn().contains("0-1"));
+        assert_eq!(restored.to_record().result, "0-1");
+        let unknown = resigned.replace("\"0-1\"", "\"2-0\"");
+        assert!(restored
+            .load_record(&unknown)
+            .unwrap_err()
+            .contains("Invalid Result \"2-0\""));
+        restored.load_record(&json).unwrap();
+
+        let illegal = json.replace("e8d7", "e8e6");
+        assert!(restored
+            .load_record(&illegal)
+            .unwrap_err()
+            .contains("Illegal Move \"e8e6\" at ply 2"));
+        assert_eq!(restored.get_fen(), "R7/3k4/8/8/8/8/8/4K3 w - - 1 2");
+    }
+
+    #[test]
+    fn test_polyglot_key() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, u64)> = vec![
+            ("e2e4", 0x823c9b50fd114196),
+            ("d7d5", 0x0756b94461c50fb0),
+            ("e4e5", 0x662fafb965db29d4),
+            ("f7f5", 0x22a48b5a8e47ff78),
+            ("e1e2", 0x652a607ca3f242c1),
+            ("e8f7", 0x00fdd303c946bdd9),
+        ];
+        assert_eq!(chess_position.polyglot_key(), 0x463b96181691fc9c);
+        for (uci, key) in arr {
+            play_squares(&mut chess_position, &uci[0..2], &uci[2..4]).unwrap();
+            assert_eq!(chess_position.polyglot_key(), key, "{uci}");
+        }
+
+        chess_position
+            .parse_fen("rnbqkbnr/p1pppppp/8/8/PpP4P/8/1P1PPPP1/RNBQKBNR b KQkq c3 0 3")
+            .unwrap();
+        assert_eq!(chess_position.polyglot_key(), 0x3c8123ea7b067637);
+        play_squares(&mut chess_position, "b4", "c3").unwrap();
+        play_squares(&mut chess_position, "a1", "a3").unwrap();
+        assert_eq!(chess_position.polyglot_key(), 0x5c3f9b829b279560);
+    }
+
+    #[test]
+    fn test_book_move() {
+        let mut chess_position = ChessEngine::new();
+        let mut bytes = Vec::new();
+        // e2e4 with weight 5 and the illegal e2e5 with weight 100
+        for (r#move, weight) in [(0x031c_u16, 5_u16), (0x0324, 100)] {
+            bytes.extend(0x463b96181691fc9c_u64.to_be_bytes());
+            bytes.extend(r#move.to_be_bytes());
+            bytes.extend(weight.to_be_bytes());
+            bytes.extend(0_u32.to_be_bytes());
+        }
+        chess_position.book = OpeningBook::from_bytes(&bytes).unwrap();
+        chess_position.random = Random::new(1);
+        assert_eq!(chess_position.book_move(true), Some("e2e4".to_string()));
+        assert_eq!(chess_position.book_move(false), Some("e2e4".to_string()));
+        play_squares(&mut chess_position, "e2", "e4");
+        assert_eq!(chess_position.book_move(false), None);
+    }
+
+    #[test]
+    fn test_build_book() {
+        let bytes = ChessEngine::build_book_bytes("1. e4 e5 1-0\n\n1. d4 d5 0-1", 1, 1).unwrap();
+        let mut chess_position = ChessEngine::new();
+        chess_position.book = OpeningBook::from_bytes(&bytes).unwrap();
+        assert_eq!(chess_position.book_move(true), Some("e2e4".to_string()));
+        assert_eq!(
+            ChessEngine::build_book_bytes("1. e4 e5 1-0\n\n1. e4 e4 0-1", 4, 1),
+            Err("Illegal Move \"e4\" in game 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_opening_name() {
+        let arr: Vec<(&str, Option<&str>)> = vec![
+            ("", None),
+            (
+                "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+                Some("Sicilian Defense: Najdorf Variation"),
+            ),
+            // the Najdorf reached through the Open Sicilian move order
+            (
+                "e4 c5 Nf3 d6 d4 cxd4 Nxd4 a6 Nc3 Nf6",
+                Some("Sicilian Defense: Najdorf Variation"),
+            ),
+            // out of book keeps the last known name
+            (
+                "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 h3 h6",
+                Some("Sicilian Defense: Najdorf Variation"),
+            ),
+            ("Nf3 d5 d4", Some("Queen's Pawn Game: Zukertort Variation")),
+            ("d4 Nf6 c4 e6 Nc3 Bb4", Some("Nimzo-Indian Defense")),
+            ("a3", None),
+        ];
+        for (moves, name) in arr {
+            let mut chess_position = ChessEngine::new();
+            for san in moves.split_whitespace() {
+                let r#move = chess_position.parse_san(san).unwrap();
+                chess_position.play(r#move.from, r#move.to, r#move.promotion);
+            }
+            assert_eq!(chess_position.opening_name().as_deref(), name, "{moves}");
+        }
+    }
+
+    #[test]
+    fn test_export_pgn() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.set_tag("White", "Alice");
+        for (from, to) in [
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("g1", "f3"),
+            ("b8", "c6"),
+            ("f1", "b5"),
+        ] {
+            play_squares(&mut chess_position, from, to);
+        }
+        let pgn = chess_position.export_pgn();
+        assert!(pgn.contains("[White \"Alice\"]"));
+        assert!(pgn.contains("[ECO \"C60\"]\n[Opening \"Ruy Lopez\"]\n"));
+        assert!(pgn.ends_with("\n1. e4 e5 2. Nf3 Nc6 3. Bb5 *\n"));
+
+        chess_position.set_tag("ECO", "C99");
+        assert!(chess_position.export_pgn().contains("[ECO \"C99\"]"));
+
+        chess_position
+            .parse_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 0 30")
+            .unwrap();
+        play_squares(&mut chess_position, "e8", "d7");
+        play_squares(&mut chess_position, "e1", "c1");
+        let pgn = chess_position.export_pgn();
+        assert!(pgn.contains("[SetUp \"1\"]\n"));
+        assert!(pgn.contains("[FEN \"4k3/8/8/8/8/8/8/R3K3 b Q - 0 30\"]"));
+        assert!(pgn.ends_with("\n30... Kd7 31. O-O-O+ *\n"));
+    }
+
+    fn perft(chess_position: &mut ChessEngine, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for r#move in chess_position.generate_moves() {
+            let undo = chess_position.make(r#move);
+            nodes += perft(chess_position, depth - 1);
+            chess_position.unmake(undo);
+        }
+        nodes
+    }
+
+    #[test]
+    fn test_perft() {
+        let arr: Vec<(&str, u32, u64)> = vec![
+            (FEN_STARTING_POSITION, 3, 8902),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                2,
+                2039,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3, 2812),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                2,
+                1486,
+            ),
+        ];
+        for (fen, depth, nodes) in arr {
+            let mut chess_position = ChessEngine::new();
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(perft(&mut chess_position, depth), nodes, "{fen}");
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+    }
 }
@@ -0,0 +1,344 @@
+use crate::chess_move::Move;
+use crate::random::Random;
+use crate::utils;
+use crate::ChessEngine;
+
+/// The exploration constant of UCT, the square root of 2.
+const EXPLORATION: f64 = std::f64::consts::SQRT_2;
+/// The plies a playout goes before it is scored as it stands.
+const PLAYOUT_PLIES: usize = 40;
+/// The most iterations a search can be given.
+pub const MAX_ITERATIONS: u32 = 1_000_000;
+
+/// How `engineMove` chooses its moves, set when the engine is built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Player {
+    /// The alpha-beta search, weakened by the skill options.
+    #[default]
+    AlphaBeta,
+    /// A Monte Carlo tree search, which needs no evaluation with random playouts.
+    Mcts,
+}
+
+impl Player {
+    pub fn parse(player: &str) -> Result<Self, String> {
+        match player.to_ascii_lowercase().as_str() {
+            "alphabeta" | "alpha-beta" => Ok(Player::AlphaBeta),
+            "mcts" => Ok(Player::Mcts),
+            _ => Err(format!("Invalid Player \"{player}\"")),
+        }
+    }
+}
+
+/// How the moves of a playout are chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Playout {
+    /// Any legal move alike. Playouts cut short count as draws, so no evaluation is used.
+    Random,
+    /// Captures and promotions more often than quiet moves, the more so the more they win.
+    /// Playouts cut short are scored by the evaluation.
+    Heuristic,
+}
+
+impl Playout {
+    pub fn parse(playout: &str) -> Result<Self, String> {
+        match playout.to_ascii_lowercase().as_str() {
+            "random" => Ok(Playout::Random),
+            "heuristic" => Ok(Playout::Heuristic),
+            _ => Err(format!("Invalid Playout \"{playout}\"")),
+        }
+    }
+}
+
+impl std::fmt::Display for Playout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Playout::Random => write!(f, "random"),
+            Playout::Heuristic => write!(f, "heuristic"),
+        }
+    }
+}
+
+/// How long the tree search runs and how it plays out positions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    /// Milliseconds, no limit when 0.
+    pub movetime: u32,
+    pub playout: Playout,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 2000,
+            movetime: 0,
+            playout: Playout::Heuristic,
+        }
+    }
+}
+
+/// A position of the tree, reached by `move` from its parent.
+#[derive(Debug)]
+struct Node {
+    r#move: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// The legal moves not expanded yet, listed on the first visit.
+    untried: Option<Vec<Move>>,
+    visits: u32,
+    /// The sum of the results for the side that played `move`, 1 for a win and 0.5 for a draw.
+    wins: f64,
+}
+
+/// The outcome of a tree search.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsResult {
+    /// The most visited move of the root.
+    pub best_move: Option<Move>,
+    pub visits: u32,
+    /// The share of the best move's playouts won by the side to move.
+    pub win_rate: f64,
+    pub iterations: u32,
+}
+
+/// A Monte Carlo tree search: each iteration follows the tree by UCT to a position not
+/// expanded yet, adds one of its moves, plays the game out from there and scores the path.
+#[derive(Debug)]
+pub struct Mcts {
+    nodes: Vec<Node>,
+}
+
+impl Mcts {
+    pub fn search(engine: &mut ChessEngine, config: MctsConfig, random: &mut Random) -> MctsResult {
+        let mut mcts = Mcts {
+            nodes: vec![Node {
+                r#move: None,
+                parent: None,
+                children: Vec::new(),
+                untried: None,
+                visits: 0,
+                wins: 0.0,
+            }],
+        };
+        let started = utils::now();
+        let mut iterations = 0;
+        while iterations < config.iterations.max(1) {
+            mcts.iterate(engine, config.playout, random);
+            iterations += 1;
+            if config.movetime > 0 && utils::now() - started >= config.movetime as f64 {
+                break;
+            }
+        }
+        let best = mcts.nodes[0]
+            .children
+            .iter()
+            .map(|&child| &mcts.nodes[child])
+            .max_by_key(|node| node.visits);
+        MctsResult {
+            best_move: best.and_then(|node| node.r#move),
+            visits: best.map_or(0, |node| node.visits),
+            win_rate: best.map_or(0.0, |node| node.wins / node.visits.max(1) as f64),
+            iterations,
+        }
+    }
+
+    fn iterate(&mut self, engine: &mut ChessEngine, playout: Playout, random: &mut Random) {
+        let mut undos = Vec::new();
+        let mut node = 0;
+        loop {
+            let untried = self.nodes[node]
+                .untried
+                .get_or_insert_with(|| engine.generate_moves());
+            if !untried.is_empty() {
+                let r#move = untried.swap_remove(random.below(untried.len() as u64) as usize);
+                undos.push(engine.make(r#move));
+                let child = self.nodes.len();
+                self.nodes.push(Node {
+                    r#move: Some(r#move),
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: None,
+                    visits: 0,
+                    wins: 0.0,
+                });
+                self.nodes[node].children.push(child);
+                node = child;
+                break;
+            }
+            let Some(child) = self.select(node) else {
+                break;
+            };
+            undos.push(engine.make(self.nodes[child].r#move.expect("a child move")));
+            node = child;
+        }
+
+        // the result for the side that played into the node, then alternating up the tree
+        let mut result = 1.0 - play_out(engine, playout, random);
+        let mut current = Some(node);
+        while let Some(index) = current {
+            self.nodes[index].visits += 1;
+            self.nodes[index].wins += result;
+            result = 1.0 - result;
+            current = self.nodes[index].parent;
+        }
+        for undo in undos.into_iter().rev() {
+            engine.unmake(undo);
+        }
+    }
+
+    /// The child with the best upper confidence bound, if any.
+    fn select(&self, node: usize) -> Option<usize> {
+        let log_visits = (self.nodes[node].visits.max(1) as f64).ln();
+        self.nodes[node].children.iter().copied().max_by(|&a, &b| {
+            let bound = |index: usize| {
+                let child = &self.nodes[index];
+                let visits = child.visits.max(1) as f64;
+                child.wins / visits + EXPLORATION * (log_visits / visits).sqrt()
+            };
+            bound(a).total_cmp(&bound(b))
+        })
+    }
+}
+
+/// Plays the game out from the position and returns the result for the side to move in it:
+/// 1 for a win, 0 for a loss and 0.5 for a draw or, for heuristic playouts cut short, the
+/// winning chances of the evaluation.
+fn play_out(engine: &mut ChessEngine, playout: Playout, random: &mut Random) -> f64 {
+    let mut undos = Vec::new();
+    let mut result = None;
+    for _ in 0..PLAYOUT_PLIES {
+        if engine.half_move_clock >= 100 || engine.board.is_insufficient_material() {
+            result = Some(0.5);
+            break;
+        }
+        let moves = engine.generate_moves();
+        if moves.is_empty() {
+            let mated = engine.board.is_check(engine.is_white_turn);
+            result = Some(if mated { 0.0 } else { 0.5 });
+            break;
+        }
+        let r#move = match playout {
+            Playout::Random => moves[random.below(moves.len() as u64) as usize],
+            Playout::Heuristic => heuristic_move(engine, &moves, random),
+        };
+        undos.push(engine.make(r#move));
+    }
+    let result = result.unwrap_or_else(|| match playout {
+        Playout::Random => 0.5,
+        Playout::Heuristic => {
+            let score = engine.evaluate();
+            1.0 / (1.0 + 10f64.powf(-score as f64 / 400.0))
+        }
+    });
+    // the result is for the side to move at the end, which changes with every ply played
+    let result = if undos.len() % 2 == 0 {
+        result
+    } else {
+        1.0 - result
+    };
+    for undo in undos.into_iter().rev() {
+        engine.unmake(undo);
+    }
+    result
+}
+
+/// Picks a move at random, captures and promotions weighing more the more they take.
+fn heuristic_move(engine: &ChessEngine, moves: &[Move], random: &mut Random) -> Move {
+    let weights: Vec<u64> = moves
+        .iter()
+        .map(|r#move| {
+            let captured = engine.board[r#move.to].value().max(0) as u64;
+            let promotion = r#move.promotion.map_or(0, |piece| piece.value()) as u64;
+            1 + (captured + promotion) / 50
+        })
+        .collect();
+    let mut choice = random.below(weights.iter().sum());
+    for (r#move, weight) in moves.iter().zip(weights) {
+        if choice < weight {
+            return *r#move;
+        }
+        choice -= weight;
+    }
+    moves[moves.len() - 1]
+}
+
+impl ChessEngine {
+    /// The move of the Monte Carlo tree search within the MCTS options.
+    pub(crate) fn mcts_move(&mut self) -> Option<Move> {
+        let mut random = self.random.clone();
+        let result = Mcts::search(self, self.mcts, &mut random);
+        self.random = random;
+        result.best_move
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_mcts() {
+        let mut chess_position = ChessEngine::with_player("MCTS").unwrap();
+        let arr: Vec<(&str, Playout, &str)> = vec![
+            (
+                "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+                Playout::Random,
+                "a1a8",
+            ),
+            (
+                "6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1",
+                Playout::Heuristic,
+                "a1a8",
+            ),
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                Playout::Heuristic,
+                "d1d5",
+            ),
+        ];
+        for (fen, playout, best_move) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            chess_position.mcts = MctsConfig {
+                iterations: 300,
+                playout,
+                ..Default::default()
+            };
+            chess_position.random = Random::new(5);
+            let r#move = chess_position.engine_move().unwrap();
+            assert_eq!(r#move.to_uci(), best_move, "{fen}");
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+
+        chess_position
+            .parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.engine_move(), None);
+    }
+
+    #[test]
+    fn test_mcts_is_reproducible() {
+        let mut chess_position = ChessEngine::new();
+        let config = MctsConfig {
+            iterations: 100,
+            ..Default::default()
+        };
+        let first = Mcts::search(&mut chess_position, config, &mut Random::new(9));
+        let second = Mcts::search(&mut chess_position, config, &mut Random::new(9));
+        assert_eq!(first, second);
+        assert_eq!(first.iterations, 100);
+        assert!(first.visits > 100 / 20);
+        assert!((0.0..=1.0).contains(&first.win_rate));
+    }
+
+    #[test]
+    fn test_player() {
+        assert_eq!(Player::parse("alphabeta"), Ok(Player::AlphaBeta));
+        assert_eq!(Player::parse("Mcts"), Ok(Player::Mcts));
+        assert!(Player::parse("minimax").is_err());
+        assert_eq!(ChessEngine::new().player, Player::AlphaBeta);
+        assert_eq!(Playout::parse("Random"), Ok(Playout::Random));
+        assert!(Playout::parse("greedy").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+use crate::algebric_notation::serialize_square;
+use crate::piece::Piece;
+
+/// A single square-level change caused by a move, so the UI can animate every piece involved.
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SquareChange {
+    Moved {
+        piece: Piece,
+        #[serde(serialize_with = "serialize_square")]
+        from: usize,
+        #[serde(serialize_with = "serialize_square")]
+        to: usize,
+    },
+    Removed {
+        piece: Piece,
+        #[serde(serialize_with = "serialize_square")]
+        square: usize,
+    },
+    Placed {
+        piece: Piece,
+        #[serde(serialize_with = "serialize_square")]
+        square: usize,
+    },
+}
+
+/// Everything that happened when a move was applied.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MoveDelta {
+    pub changes: Vec<SquareChange>,
+    pub capture: bool,
+    pub check: bool,
+    pub castle: bool,
+    pub promotion: bool,
+    pub checkmate: bool,
+}
@@ -0,0 +1,480 @@
+use std::sync::Arc;
+
+use crate::board::Board;
+use crate::evaluation;
+use crate::move_delta::SquareChange;
+use crate::piece::{Piece, PieceTrait};
+use crate::ChessEngine;
+
+/// The first bytes of a network file.
+const MAGIC: &[u8; 4] = b"CENN";
+const VERSION: u32 = 1;
+/// The ceiling of the clipped ReLU, the scale the first layer is quantised to.
+const QA: i64 = 255;
+/// The scale the output weights are quantised to.
+const QB: i64 = 64;
+/// Centipawns per unit of the network's output.
+const SCALE: i64 = 400;
+/// The largest hidden layer a file can have.
+const MAX_HIDDEN: usize = 4096;
+
+/// The inputs of a network, seen from each side with its own pieces first and the board
+/// flipped for black, so the same weights serve both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Architecture {
+    /// The king's square with each other piece on its square, 64 × 640 inputs.
+    HalfKp,
+    /// As HalfKP with the kings among the pieces, 64 × 768 inputs.
+    HalfKa,
+}
+
+impl Architecture {
+    fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Architecture::HalfKp),
+            1 => Ok(Architecture::HalfKa),
+            _ => Err(format!("Invalid Network Architecture \"{id}\"")),
+        }
+    }
+
+    /// The kinds of piece an input can be, each side's counted apart.
+    fn pieces(self) -> usize {
+        match self {
+            Architecture::HalfKp => 10,
+            Architecture::HalfKa => 12,
+        }
+    }
+
+    pub fn inputs(self) -> usize {
+        64 * self.pieces() * 64
+    }
+
+    /// The input of `piece` on `square` for the side seen from, its king being on `king`,
+    /// none for kings in HalfKP.
+    fn feature(self, white: bool, king: usize, piece: Piece, square: usize) -> Option<usize> {
+        if self == Architecture::HalfKp && piece.is_king() {
+            return None;
+        }
+        let theirs = piece.is_white() != white;
+        let kind = piece as usize % 6 + self.pieces() / 2 * theirs as usize;
+        Some(((orient(white, king) * self.pieces() + kind) << 6) + orient(white, square))
+    }
+}
+
+/// The 0..64 index of a 0x88 square as seen by one side, rank 1 being the side's own.
+fn orient(white: bool, square: usize) -> usize {
+    let index = (square >> 4) * 8 + (square & 7);
+    if white {
+        index
+    } else {
+        index ^ 56
+    }
+}
+
+/// A quantised network: inputs to a hidden layer per side, whose clipped halves, the side
+/// to move's first, make a single output.
+///
+/// Files are little-endian: "CENN", the version (1), the architecture (0 for HalfKP, 1 for
+/// HalfKA) and the hidden size as u32s, then as i16s the hidden weights of each input in turn
+/// and the hidden biases, the output weights for both halves and last the output bias as
+/// an i32.
+#[derive(Debug, PartialEq)]
+pub struct Network {
+    architecture: Architecture,
+    hidden: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+/// Reads the fields of a network file one after the other.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> Result<&[u8], String> {
+        if self.bytes.len() < count {
+            return Err("Truncated Network".to_string());
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i16s(&mut self, count: usize) -> Result<Vec<i16>, String> {
+        Ok(self
+            .take(count * 2)?
+            .chunks_exact(2)
+            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect())
+    }
+}
+
+impl Network {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes };
+        if reader.take(4).ok() != Some(MAGIC.as_slice()) {
+            return Err("Invalid Network Header".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("Unsupported Network Version \"{version}\""));
+        }
+        let architecture = Architecture::from_id(reader.u32()?)?;
+        let hidden = reader.u32()? as usize;
+        if hidden == 0 || hidden > MAX_HIDDEN {
+            return Err(format!("Invalid Network Hidden Size \"{hidden}\""));
+        }
+        let network = Network {
+            architecture,
+            hidden,
+            feature_weights: reader.i16s(architecture.inputs() * hidden)?,
+            feature_biases: reader.i16s(hidden)?,
+            output_weights: reader.i16s(2 * hidden)?,
+            output_bias: reader.u32()? as i32,
+        };
+        if !reader.bytes.is_empty() {
+            return Err(format!("Invalid Network Size \"{}\"", bytes.len()));
+        }
+        Ok(network)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|err| format!("Cannot Read Network \"{path}\": {err}"))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Adds (or takes away) the weights of an input to one side's hidden layer.
+    fn apply(&self, sums: &mut [i16], feature: Option<usize>, add: bool) {
+        let Some(feature) = feature else {
+            return;
+        };
+        let weights = &self.feature_weights[feature * self.hidden..(feature + 1) * self.hidden];
+        for (sum, &weight) in sums.iter_mut().zip(weights) {
+            *sum = if add {
+                sum.wrapping_add(weight)
+            } else {
+                sum.wrapping_sub(weight)
+            };
+        }
+    }
+
+    /// One side's hidden layer computed from every piece of the board.
+    fn refresh(&self, board: &Board, white: bool) -> Vec<i16> {
+        let mut sums = self.feature_biases.clone();
+        let king = board.king(white).unwrap_or_default();
+        for index in 0..64 {
+            let square = (index >> 3) * 16 + (index & 7);
+            let piece = board[square];
+            if !piece.is_empty() {
+                let feature = self.architecture.feature(white, king, piece, square);
+                self.apply(&mut sums, feature, true);
+            }
+        }
+        sums
+    }
+
+    fn accumulate(&self, board: &Board) -> Accumulator {
+        Accumulator {
+            board: *board,
+            white: self.refresh(board, true),
+            black: self.refresh(board, false),
+        }
+    }
+
+    /// The accumulator after a move from that of the position before it, changing only the
+    /// inputs of the pieces the move touched. A side whose king moved has all its inputs
+    /// changed and is computed again.
+    fn update(
+        &self,
+        previous: &Accumulator,
+        board: &Board,
+        changes: &[SquareChange],
+    ) -> Accumulator {
+        let mut next = Accumulator {
+            board: *board,
+            white: previous.white.clone(),
+            black: previous.black.clone(),
+        };
+        for white in [true, false] {
+            let king_moved = changes.iter().any(|change| {
+                matches!(change, SquareChange::Moved { piece, .. }
+                    if piece.is_king() && piece.is_white() == white)
+            });
+            if king_moved {
+                *next.side_mut(white) = self.refresh(board, white);
+                continue;
+            }
+            let king = board.king(white).unwrap_or_default();
+            let feature = |piece, square| self.architecture.feature(white, king, piece, square);
+            let sums = next.side_mut(white);
+            for change in changes {
+                match *change {
+                    SquareChange::Moved { piece, from, to } => {
+                        self.apply(sums, feature(piece, from), false);
+                        self.apply(sums, feature(piece, to), true);
+                    }
+                    SquareChange::Removed { piece, square } => {
+                        self.apply(sums, feature(piece, square), false)
+                    }
+                    SquareChange::Placed { piece, square } => {
+                        self.apply(sums, feature(piece, square), true)
+                    }
+                }
+            }
+        }
+        next
+    }
+
+    /// The score in centipawns for the side to move, in integers only.
+    fn output(&self, accumulator: &Accumulator, white_to_move: bool) -> i32 {
+        let (ours, theirs) = if white_to_move {
+            (&accumulator.white, &accumulator.black)
+        } else {
+            (&accumulator.black, &accumulator.white)
+        };
+        let (our_weights, their_weights) = self.output_weights.split_at(self.hidden);
+        let mut sum = self.output_bias as i64;
+        for (sums, weights) in [(ours, our_weights), (theirs, their_weights)] {
+            for (&value, &weight) in sums.iter().zip(weights) {
+                sum += (value as i64).clamp(0, QA) * weight as i64;
+            }
+        }
+        (sum * SCALE / (QA * QB)) as i32
+    }
+}
+
+/// The hidden layer of both sides for a position.
+#[derive(Debug, Clone, PartialEq)]
+struct Accumulator {
+    /// The position the sums are for, so ones left behind by a position set some other way
+    /// than with `make` are noticed.
+    board: Board,
+    white: Vec<i16>,
+    black: Vec<i16>,
+}
+
+impl Accumulator {
+    fn side_mut(&mut self, white: bool) -> &mut Vec<i16> {
+        if white {
+            &mut self.white
+        } else {
+            &mut self.black
+        }
+    }
+}
+
+/// A loaded network with the accumulators of the moves made, one per `make` taken back by
+/// `unmake`. An accumulator is only worked out when the one before it is known, else on the
+/// next evaluation.
+#[derive(Debug, Clone)]
+pub struct Nnue {
+    network: Arc<Network>,
+    stack: Vec<Option<Accumulator>>,
+}
+
+impl Nnue {
+    pub fn new(network: Arc<Network>) -> Self {
+        Self {
+            network,
+            stack: Vec::new(),
+        }
+    }
+
+    /// The same network without the accumulators, for a copy of the engine.
+    pub fn fresh(&self) -> Self {
+        Self::new(self.network.clone())
+    }
+
+    pub(crate) fn push(&mut self, before: &Board, after: &Board, changes: &[SquareChange]) {
+        let next = match self.stack.last() {
+            Some(Some(previous)) if previous.board == *before => {
+                Some(self.network.update(previous, after, changes))
+            }
+            _ => None,
+        };
+        self.stack.push(next);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.stack.pop();
+    }
+
+    pub(crate) fn evaluate(&mut self, board: &Board, white_to_move: bool) -> i32 {
+        let current = matches!(self.stack.last(), Some(Some(top)) if top.board == *board);
+        if !current {
+            let accumulator = Some(self.network.accumulate(board));
+            match self.stack.last_mut() {
+                Some(top) => *top = accumulator,
+                None => self.stack.push(accumulator),
+            }
+        }
+        let top = self.stack.last().and_then(Option::as_ref);
+        self.network
+            .output(top.expect("an accumulator"), white_to_move)
+    }
+}
+
+impl ChessEngine {
+    /// Scores the position in centipawns for the side to move with the loaded network, or
+    /// with the handcrafted evaluation and its weights when there is none.
+    pub(crate) fn evaluate(&mut self) -> i32 {
+        match &mut self.nnue {
+            Some(nnue) => nnue.evaluate(&self.board, self.is_white_turn),
+            None => evaluation::evaluate(&self.weights, &self.board, self.is_white_turn),
+        }
+    }
+
+    pub(crate) fn load_network_bytes(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.nnue = Some(Nnue::new(Arc::new(Network::from_bytes(bytes)?)));
+        Ok(())
+    }
+
+    /// Loads an NNUE network from a local file, see [`Network`].
+    pub fn load_network_file(&mut self, path: &str) -> Result<(), String> {
+        self.nnue = Some(Nnue::new(Arc::new(Network::from_file(path)?)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluation::Weights;
+    use crate::fen_trait::FenParser;
+    use crate::random::Random;
+
+    /// A small network of random weights in the file format.
+    fn network_bytes(architecture: u32, hidden: usize, seed: u64) -> Vec<u8> {
+        let inputs = Architecture::from_id(architecture).unwrap().inputs();
+        let mut random = Random::new(seed);
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend(architecture.to_le_bytes());
+        bytes.extend((hidden as u32).to_le_bytes());
+        for _ in 0..(inputs + 1) * hidden {
+            bytes.extend((random.below(81) as i16 - 40).to_le_bytes());
+        }
+        for _ in 0..2 * hidden {
+            bytes.extend((random.below(129) as i16 - 64).to_le_bytes());
+        }
+        bytes.extend(25i32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_network_from_bytes() {
+        let bytes = network_bytes(0, 4, 1);
+        let network = Network::from_bytes(&bytes).unwrap();
+        assert_eq!(network.architecture, Architecture::HalfKp);
+        assert_eq!(network.hidden, 4);
+        assert_eq!(network.output_bias, 25);
+
+        let mut version = bytes.clone();
+        version[4] = 2;
+        let mut architecture = bytes.clone();
+        architecture[8] = 7;
+        let mut longer = bytes.clone();
+        longer.push(0);
+        let size = format!("Invalid Network Size \"{}\"", longer.len());
+        let arr: Vec<(Vec<u8>, &str)> = vec![
+            (b"CENX".to_vec(), "Invalid Network Header"),
+            (version, "Unsupported Network Version \"2\""),
+            (architecture, "Invalid Network Architecture \"7\""),
+            (bytes[..bytes.len() - 1].to_vec(), "Truncated Network"),
+            (longer, size.as_str()),
+        ];
+        for (bytes, err) in arr {
+            assert_eq!(Network::from_bytes(&bytes), Err(err.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_incremental_accumulators() {
+        for architecture in [0, 1] {
+            let mut chess_position = ChessEngine::new();
+            chess_position
+                .load_network_bytes(&network_bytes(architecture, 8, 2))
+                .unwrap();
+            let network = chess_position.nnue.as_ref().unwrap().network.clone();
+            // castling both ways, en passant, promotions with and without a capture and king moves
+            let arr: Vec<(&str, Vec<&str>)> = vec![
+                (
+                    "r3k2r/pppq1ppp/2n2n2/3pp3/1b1PP3/2N2N2/PPPQ1PPP/R3K2R w KQkq - 0 1",
+                    vec!["e1g1", "e8c8", "d4e5", "d7g4", "g1h1"],
+                ),
+                (
+                    "4k3/1P6/8/3pP3/8/8/6p1/4K2R w K d6 0 1",
+                    vec!["e5d6", "g2h1q", "e1d2", "e8f7", "b7b8n"],
+                ),
+            ];
+            for (fen, moves) in arr {
+                chess_position.parse_fen(fen).unwrap();
+                let root = chess_position.evaluate();
+                let mut undos = Vec::new();
+                for uci in moves {
+                    let r#move = chess_position
+                        .generate_moves()
+                        .into_iter()
+                        .find(|r#move| r#move.to_uci() == uci)
+                        .unwrap();
+                    undos.push(chess_position.make(r#move));
+                    let top = chess_position.nnue.as_ref().unwrap().stack.last().cloned();
+                    let expected = network.accumulate(&chess_position.board);
+                    assert_eq!(top, Some(Some(expected.clone())), "{fen} {uci}");
+                    assert_eq!(
+                        chess_position.evaluate(),
+                        network.output(&expected, chess_position.is_white_turn),
+                        "{fen} {uci}"
+                    );
+                }
+                for undo in undos.into_iter().rev() {
+                    chess_position.unmake(undo);
+                }
+                assert_eq!(chess_position.nnue.as_ref().unwrap().stack.len(), 1);
+                assert_eq!(chess_position.evaluate(), root, "{fen}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut chess_position = ChessEngine::new();
+        let handcrafted = evaluation::evaluate(&Weights::default(), &chess_position.board, true);
+        assert_eq!(chess_position.evaluate(), handcrafted);
+
+        chess_position
+            .load_network_bytes(&network_bytes(1, 8, 3))
+            .unwrap();
+        assert!(chess_position.has_network());
+        // both sides see the same thing in the starting position, only the bias is left over
+        let network = chess_position.nnue.as_ref().unwrap().network.clone();
+        let accumulator = network.accumulate(&chess_position.board);
+        assert_eq!(accumulator.white, accumulator.black);
+        assert_eq!(
+            chess_position.evaluate(),
+            network.output(&accumulator, true)
+        );
+
+        // a position set without `make` is evaluated from scratch
+        chess_position
+            .parse_fen("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1")
+            .unwrap();
+        let expected = network.accumulate(&chess_position.board);
+        assert_eq!(chess_position.evaluate(), network.output(&expected, true));
+        assert!(chess_position.analyse(2).best_move.is_some());
+
+        chess_position.unload_network();
+        assert_eq!(
+            chess_position.evaluate(),
+            evaluation::evaluate(&Weights::default(), &chess_position.board, true)
+        );
+    }
+}
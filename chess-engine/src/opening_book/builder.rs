@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use crate::fen_trait::FenParser;
+use crate::pgn::PgnGame;
+use crate::piece::PieceTrait;
+use crate::ChessEngine;
+
+use super::{encode_move, ENTRY_SIZE};
+
+#[derive(Debug, Default, Clone, Copy)]
+struct MoveStats {
+    count: u32,
+    wins: u32,
+    draws: u32,
+}
+
+impl MoveStats {
+    /// Polyglot weighs a move by two points per win and one per draw, unfinished games count as draws.
+    /// A move that only lost still weighs 1, so it stays in the book.
+    fn score(&self) -> u64 {
+        (2 * self.wins as u64 + self.draws as u64).max(1)
+    }
+}
+
+/// Aggregates the moves played in a set of games into a Polyglot book.
+#[derive(Debug, Clone)]
+pub struct BookBuilder {
+    max_ply: usize,
+    min_count: u32,
+    stats: HashMap<(u64, u16), MoveStats>,
+}
+
+impl BookBuilder {
+    /// Only the first `max_ply` moves of each game are kept, and only moves played at least `min_count` times.
+    pub fn new(max_ply: usize, min_count: u32) -> Self {
+        Self {
+            max_ply,
+            min_count,
+            stats: HashMap::new(),
+        }
+    }
+
+    /// Replays a game from its start position and counts its moves with the game result.
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), String> {
+        let mut engine = ChessEngine::new();
+        if let Some(fen) = game.tags.get("FEN") {
+            engine.parse_fen(fen)?;
+        }
+        let winner = match game.result.as_str() {
+            "1-0" => Some(true),
+            "0-1" => Some(false),
+            _ => None,
+        };
+        for san in game.moves.iter().take(self.max_ply) {
+            let r#move = engine.parse_san(san)?;
+            let castles =
+                engine.board[r#move.from].is_king() && r#move.from.abs_diff(r#move.to) == 2;
+            let stats = self
+                .stats
+                .entry((engine.polyglot_key(), encode_move(r#move, castles)))
+                .or_default();
+            stats.count += 1;
+            match winner {
+                Some(white) if white == engine.is_white_turn => stats.wins += 1,
+                Some(_) => {}
+                None => stats.draws += 1,
+            }
+            engine.make(r#move);
+        }
+        Ok(())
+    }
+
+    /// Writes the book sorted by key, heaviest moves first, scaling weights down to fit 16 bits.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<(u64, u16, u64)> = self
+            .stats
+            .iter()
+            .filter(|(_, stats)| stats.count >= self.min_count)
+            .map(|(&(key, r#move), stats)| (key, r#move, stats.score()))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(&b.1)));
+        let max = entries.iter().map(|entry| entry.2).max().unwrap_or(0);
+        let scale = |score: u64| match max > u16::MAX as u64 {
+            true => (score * u16::MAX as u64 / max).max(1) as u16,
+            false => score as u16,
+        };
+
+        let mut bytes = Vec::with_capacity(entries.len() * ENTRY_SIZE);
+        for (key, r#move, score) in entries {
+            bytes.extend(key.to_be_bytes());
+            bytes.extend(r#move.to_be_bytes());
+            bytes.extend(scale(score).to_be_bytes());
+            bytes.extend(0u32.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+    use crate::opening_book::OpeningBook;
+    use crate::pgn::parse_pgn;
+
+    const START_KEY: u64 = 0x463b96181691fc9c;
+
+    #[test]
+    fn test_build_book() {
+        let games = parse_pgn(
+            "1. e4 e5 2. Nf3 1-0\n\n1. e4 c5 0-1\n\n1. d4 d5 1/2-1/2\n\n1. e4 e5 2. Bc4 1-0\n\n1. c4 0-1",
+        )
+        .unwrap();
+        let mut builder = BookBuilder::new(2, 1);
+        for game in &games {
+            builder.add_game(game).unwrap();
+        }
+        let book = OpeningBook::from_bytes(&builder.to_bytes()).unwrap();
+
+        let moves: Vec<(String, u16)> = book
+            .moves(START_KEY, &Board::default())
+            .into_iter()
+            .map(|book_move| (book_move.to_uci(), book_move.weight))
+            .collect();
+        // e4 won twice and lost once, d4 drew, c4 only lost but keeps the least weight
+        assert_eq!(
+            moves,
+            [
+                ("e2e4".to_string(), 4),
+                ("c2c4".to_string(), 1),
+                ("d2d4".to_string(), 1)
+            ]
+        );
+
+        // the third ply is beyond the depth limit
+        let mut engine = ChessEngine::new();
+        for san in ["e4", "e5"] {
+            let r#move = engine.parse_san(san).unwrap();
+            engine.make(r#move);
+        }
+        assert!(book.moves(engine.polyglot_key(), &engine.board).is_empty());
+
+        let mut builder = BookBuilder::new(2, 2);
+        for game in &games {
+            builder.add_game(game).unwrap();
+        }
+        let book = OpeningBook::from_bytes(&builder.to_bytes()).unwrap();
+        assert_eq!(book.moves(START_KEY, &Board::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_build_book_castling_and_errors() {
+        let games = parse_pgn(
+            "[FEN \"r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1\"]\n1. O-O O-O-O 1-0\n\n1. e5 *",
+        )
+        .unwrap();
+        let mut builder = BookBuilder::new(10, 1);
+        builder.add_game(&games[0]).unwrap();
+        assert!(builder.add_game(&games[1]).is_err());
+
+        let mut engine = ChessEngine::new();
+        engine
+            .parse_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1")
+            .unwrap();
+        let book = OpeningBook::from_bytes(&builder.to_bytes()).unwrap();
+        let moves = book.moves(engine.polyglot_key(), &engine.board);
+        assert_eq!(moves[0].to_uci(), "e1g1");
+    }
+}
@@ -0,0 +1,233 @@
+mod builder;
+mod random;
+mod zobrist;
+
+use serde::Serialize;
+
+use crate::algebric_notation::{self, serialize_square};
+use crate::board::Board;
+use crate::chess_move::Move;
+use crate::piece::PieceTrait;
+use crate::random::Random;
+pub use builder::BookBuilder;
+pub use zobrist::polyglot_key;
+
+/// Every entry is 16 bytes: key, move, weight and learn, all big-endian.
+const ENTRY_SIZE: usize = 16;
+const PROMOTION_PIECES: [char; 5] = [' ', 'n', 'b', 'r', 'q'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BookEntry {
+    pub key: u64,
+    pub r#move: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct BookMove {
+    #[serde(serialize_with = "serialize_square")]
+    pub from: usize,
+    #[serde(serialize_with = "serialize_square")]
+    pub to: usize,
+    pub promotion: Option<char>,
+    pub weight: u16,
+}
+
+impl BookMove {
+    pub fn to_uci(self) -> String {
+        algebric_notation::to_uci(self.from as u8, self.to as u8, self.promotion)
+    }
+}
+
+/// A Polyglot opening book, its entries sorted by key.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpeningBook {
+    entries: Vec<BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(format!("Invalid Book Size \"{}\"", bytes.len()));
+        }
+        let mut entries: Vec<BookEntry> = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(|chunk| BookEntry {
+                key: u64::from_be_bytes(chunk[0..8].try_into().unwrap()),
+                r#move: u16::from_be_bytes(chunk[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(chunk[10..12].try_into().unwrap()),
+                learn: u32::from_be_bytes(chunk[12..16].try_into().unwrap()),
+            })
+            .collect();
+        // books are sorted by key already, sorting again keeps hand-made books usable
+        entries.sort_by_key(|entry| entry.key);
+        Ok(Self { entries })
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let bytes =
+            std::fs::read(path).map_err(|err| format!("Cannot Read Book \"{path}\": {err}"))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns the entries stored for `key`, decoded against the position they belong to.
+    pub fn moves(&self, key: u64, board: &Board) -> Vec<BookMove> {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[start..]
+            .iter()
+            .take_while(|entry| entry.key == key)
+            .map(|entry| decode_move(entry.r#move, entry.weight, board))
+            .collect()
+    }
+
+    /// Picks one of `moves` at random in proportion to its weight, or the heaviest when `best_only`.
+    pub fn pick(moves: &[BookMove], best_only: bool, random: &mut Random) -> Option<BookMove> {
+        if best_only {
+            return moves
+                .iter()
+                .max_by_key(|book_move| book_move.weight)
+                .copied();
+        }
+        let total: u64 = moves.iter().map(|book_move| book_move.weight as u64).sum();
+        if total == 0 {
+            return (!moves.is_empty()).then(|| moves[random.below(moves.len() as u64) as usize]);
+        }
+        let mut choice = random.below(total);
+        for book_move in moves {
+            if choice < book_move.weight as u64 {
+                return Some(*book_move);
+            }
+            choice -= book_move.weight as u64;
+        }
+        None
+    }
+}
+
+/// Polyglot packs a move as to file, to row, from file, from row and promotion, three bits each.
+/// Castling is stored as the king capturing its own rook.
+fn decode_move(raw: u16, weight: u16, board: &Board) -> BookMove {
+    let square = |bits: u16| ((bits >> 3 & 0x7) * 16 + (bits & 0x7)) as usize;
+    let from = square(raw >> 6);
+    let mut to = square(raw);
+    if board[from].is_king()
+        && board[to].is_rook()
+        && board[from].is_white() == board[to].is_white()
+    {
+        to = if to > from { from + 2 } else { from - 2 };
+    }
+    let promotion = match (raw >> 12 & 0x7) as usize {
+        0 => None,
+        piece => PROMOTION_PIECES.get(piece).copied(),
+    };
+    BookMove {
+        from,
+        to,
+        promotion,
+        weight,
+    }
+}
+
+/// Packs a move the way [`decode_move`] reads it, moving a castling king onto its rook.
+fn encode_move(r#move: Move, castles: bool) -> u16 {
+    let square = |pos: usize| ((pos >> 4) << 3 | (pos & 0x7)) as u16;
+    let to = match castles {
+        true if r#move.to > r#move.from => r#move.from + 3,
+        true => r#move.from - 4,
+        false => r#move.to,
+    };
+    let promotion = r#move
+        .promotion
+        .and_then(|piece| {
+            let letter = char::from(piece).to_ascii_lowercase();
+            PROMOTION_PIECES.iter().position(|&ch| ch == letter)
+        })
+        .unwrap_or(0) as u16;
+    promotion << 12 | square(r#move.from) << 6 | square(to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    fn entry(key: u64, r#move: u16, weight: u16) -> Vec<u8> {
+        let mut bytes = key.to_be_bytes().to_vec();
+        bytes.extend(r#move.to_be_bytes());
+        bytes.extend(weight.to_be_bytes());
+        bytes.extend(0u32.to_be_bytes());
+        bytes
+    }
+
+    // e2e4: from e2 (row 1, file 4) to e4 (row 3, file 4)
+    const E2E4: u16 = (1 << 9) | (4 << 6) | (3 << 3) | 4;
+    const D2D4: u16 = (1 << 9) | (3 << 6) | (3 << 3) | 3;
+    const START_KEY: u64 = 0x463b96181691fc9c;
+
+    #[test]
+    fn test_read_book() {
+        let mut bytes = entry(START_KEY, D2D4, 1);
+        bytes.extend(entry(1, E2E4, 10));
+        bytes.extend(entry(START_KEY, E2E4, 3));
+        let book = OpeningBook::from_bytes(&bytes).unwrap();
+        assert_eq!(book.entries.len(), 3);
+        let moves = book.moves(START_KEY, &Board::default());
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[0].to_uci(), "d2d4");
+        assert_eq!(moves[1].to_uci(), "e2e4");
+        assert!(book.moves(2, &Board::default()).is_empty());
+        assert!(OpeningBook::from_bytes(&bytes[1..]).is_err());
+    }
+
+    #[test]
+    fn test_decode_castling_and_promotion() {
+        let mut board = Board::default();
+        board.parse_fen("r3k3/1P6/8/8/8/8/8/4K2R").unwrap();
+        // e1h1 is king side castling
+        let castle = decode_move((4 << 6) | 7, 1, &board);
+        assert_eq!((castle.from, castle.to), (4, 6));
+        // b7a8 promoting to a knight
+        let promotion = decode_move((1 << 12) | (6 << 9) | (1 << 6) | (7 << 3), 1, &board);
+        assert_eq!(promotion.to_uci(), "b7a8n");
+    }
+
+    #[test]
+    fn test_encode_move() {
+        let mut board = Board::default();
+        board.parse_fen("r3k3/1P6/8/8/8/8/8/4K2R").unwrap();
+        assert_eq!(encode_move(Move::new(0x14, 0x34, None), false), E2E4);
+        let castle = decode_move(encode_move(Move::new(4, 6, None), true), 1, &board);
+        assert_eq!((castle.from, castle.to), (4, 6));
+        let promotion = Move::new(0x61, 0x70, Some(crate::piece::Piece::WhiteKnight));
+        assert_eq!(
+            decode_move(encode_move(promotion, false), 1, &board).to_uci(),
+            "b7a8n"
+        );
+    }
+
+    #[test]
+    fn test_pick() {
+        let moves = OpeningBook::from_bytes(
+            &[entry(START_KEY, D2D4, 1), entry(START_KEY, E2E4, 3)].concat(),
+        )
+        .unwrap()
+        .moves(START_KEY, &Board::default());
+        let mut random = Random::new(7);
+        assert_eq!(
+            OpeningBook::pick(&moves, true, &mut random)
+                .unwrap()
+                .to_uci(),
+            "e2e4"
+        );
+        let picks: Vec<String> = (0..100)
+            .map(|_| {
+                OpeningBook::pick(&moves, false, &mut random)
+                    .unwrap()
+                    .to_uci()
+            })
+            .collect();
+        let e4 = picks.iter().filter(|uci| *uci == "e2e4").count();
+        assert!(e4 > 50 && e4 < 100, "{e4}");
+        assert_eq!(OpeningBook::pick(&[], false, &mut random), None);
+    }
+}
@@ -0,0 +1,70 @@
+use super::random::RANDOM64;
+use crate::board::Board;
+use crate::castling::{Castling, CastlingTrait};
+use crate::piece::{Piece, PieceTrait};
+use crate::utils;
+
+const CASTLING_OFFSET: usize = 768;
+const EN_PASSANT_OFFSET: usize = 772;
+const TURN_OFFSET: usize = 780;
+
+/// Polyglot orders the pieces black pawn, white pawn, black knight, white knight, and so on.
+fn piece_kind(piece: Piece) -> usize {
+    let index = piece as usize;
+    if piece.is_white() {
+        index * 2 + 1
+    } else {
+        (index - 6) * 2
+    }
+}
+
+/// Computes the Polyglot key of a position, as used to look it up in `.bin` opening books.
+pub fn polyglot_key(
+    board: &Board,
+    is_white_turn: bool,
+    castling: &Castling,
+    en_passant: Option<u8>,
+) -> u64 {
+    let mut key = 0;
+    for i in 0..64 {
+        let piece = board[utils::convert_postion_to_0x88(i)];
+        if !piece.is_empty() {
+            key ^= RANDOM64[64 * piece_kind(piece) + i];
+        }
+    }
+    let castling_rights = [
+        castling.can_white_king_castle(),
+        castling.can_white_queen_castle(),
+        castling.can_black_king_castle(),
+        castling.can_black_queen_castle(),
+    ];
+    for (i, _) in castling_rights
+        .iter()
+        .enumerate()
+        .filter(|(_, &right)| right)
+    {
+        key ^= RANDOM64[CASTLING_OFFSET + i];
+    }
+    // the en passant file only counts when a pawn can actually capture en passant
+    if let Some(en_passant) = en_passant {
+        let pos = en_passant as usize;
+        let (pawn, neighbours) = if is_white_turn {
+            (
+                Piece::WhitePawn,
+                [pos.wrapping_sub(17), pos.wrapping_sub(15)],
+            )
+        } else {
+            (Piece::BlackPawn, [pos + 15, pos + 17])
+        };
+        if neighbours
+            .iter()
+            .any(|&square| square & 0x88 == 0 && board[square] == pawn)
+        {
+            key ^= RANDOM64[EN_PASSANT_OFFSET + (pos & 0x7)];
+        }
+    }
+    if is_white_turn {
+        key ^= RANDOM64[TURN_OFFSET];
+    }
+    key
+}
@@ -0,0 +1,115 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::mcts::{Playout, MAX_ITERATIONS};
+use crate::search::{TranspositionTable, MAX_HASH_MB, MAX_THREADS};
+use crate::strength::{MAX_ELO, MAX_SKILL_LEVEL, MIN_ELO};
+use crate::ChessEngine;
+
+impl ChessEngine {
+    /// Sets an engine option by its UCI name, ignoring case.
+    /// "Hash" is the size of the transposition table in megabytes, which empties it.
+    /// "Skill Level" from 0 to 20 weakens `engineMove`, unless "UCI_LimitStrength" is true,
+    /// when "UCI_Elo" sets its strength instead. "Threads" is the number of threads searches
+    /// run on in native builds. "MCTS Iterations", "MCTS Move Time" in milliseconds (0 for no
+    /// limit) and "MCTS Playout" ("random" or "heuristic") set the search of the MCTS player.
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => {
+                let size_mb = parse_option(name, value, 1, MAX_HASH_MB)?;
+                self.hash = Arc::new(TranspositionTable::new(size_mb));
+            }
+            "threads" => self.threads = parse_option(name, value, 1, MAX_THREADS)?,
+            "skill level" => {
+                self.strength.skill_level = parse_option(name, value, 0, MAX_SKILL_LEVEL)?
+            }
+            "uci_limitstrength" => {
+                self.strength.limit_strength = match value.trim() {
+                    "true" => true,
+                    "false" => false,
+                    _ => {
+                        return Err(format!(
+                        "Invalid Value \"{value}\" for Option \"{name}\", expected true or false"
+                    ))
+                    }
+                }
+            }
+            "uci_elo" => self.strength.elo = parse_option(name, value, MIN_ELO, MAX_ELO)?,
+            "mcts iterations" => {
+                self.mcts.iterations = parse_option(name, value, 1, MAX_ITERATIONS)?
+            }
+            "mcts move time" => self.mcts.movetime = parse_option(name, value, 0, u32::MAX)?,
+            "mcts playout" => self.mcts.playout = Playout::parse(value.trim())?,
+            _ => return Err(format!("Unknown Option \"{name}\"")),
+        }
+        Ok(())
+    }
+
+    /// The value of an engine option, see [`ChessEngine::set_option`].
+    pub(crate) fn get_option(&self, name: &str) -> Result<String, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => Ok(self.hash.size_mb().to_string()),
+            "threads" => Ok(self.threads.to_string()),
+            "skill level" => Ok(self.strength.skill_level.to_string()),
+            "uci_limitstrength" => Ok(self.strength.limit_strength.to_string()),
+            "uci_elo" => Ok(self.strength.elo.to_string()),
+            "mcts iterations" => Ok(self.mcts.iterations.to_string()),
+            "mcts move time" => Ok(self.mcts.movetime.to_string()),
+            "mcts playout" => Ok(self.mcts.playout.to_string()),
+            _ => Err(format!("Unknown Option \"{name}\"")),
+        }
+    }
+}
+
+fn parse_option<T>(name: &str, value: &str, min: T, max: T) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + Display,
+{
+    match value.trim().parse::<T>() {
+        Ok(parsed) if parsed >= min && parsed <= max => Ok(parsed),
+        _ => Err(format!(
+            "Invalid Value \"{value}\" for Option \"{name}\", expected {min} to {max}"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_options() {
+        let mut chess_position = ChessEngine::new();
+        assert_eq!(chess_position.get_option("Hash").unwrap(), "16");
+        chess_position.set_option("hash", "64").unwrap();
+        assert_eq!(chess_position.get_option("HASH").unwrap(), "64");
+        let arr: Vec<(&str, &str)> = vec![
+            ("Hash", "0"),
+            ("Hash", "4096"),
+            ("Hash", "big"),
+            ("Threads", "0"),
+            ("MCTS Iterations", "0"),
+            ("MCTS Playout", "greedy"),
+            ("Skill Level", "21"),
+            ("UCI_LimitStrength", "yes"),
+            ("UCI_Elo", "3000"),
+            ("Contempt", "10"),
+        ];
+        for (name, value) in arr {
+            assert!(chess_position.set_option(name, value).is_err(), "{name}");
+        }
+        assert_eq!(chess_position.get_option("Hash").unwrap(), "64");
+        chess_position.set_option("Skill Level", "3").unwrap();
+        chess_position
+            .set_option("UCI_LimitStrength", "true")
+            .unwrap();
+        chess_position.set_option("UCI_Elo", "1320").unwrap();
+        assert_eq!(chess_position.strength.skill_level, 3);
+        assert_eq!(chess_position.strength.level(), 8.0);
+        assert_eq!(chess_position.get_option("uci_elo").unwrap(), "1320");
+        chess_position.set_option("MCTS Playout", "Random").unwrap();
+        assert_eq!(chess_position.get_option("MCTS Playout").unwrap(), "random");
+        assert!(chess_position.get_option("Contempt").is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+/// The tags every PGN game starts with, in this order.
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+/// Movetext lines are kept within this many characters.
+const LINE_LENGTH: usize = 80;
+
+/// A game read from PGN: its tag pairs, the mainline moves in SAN and the result.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PgnGame {
+    pub tags: BTreeMap<String, String>,
+    pub moves: Vec<String>,
+    /// NAGs and comments written after each move, by ply. Reading PGN leaves them out.
+    pub annotations: Vec<String>,
+    /// "1-0", "0-1", "1/2-1/2" or "*".
+    pub result: String,
+}
+
+impl std::fmt::Display for PgnGame {
+    /// Writes the seven tag roster, the other tags and the movetext, numbering moves from the FEN tag if any.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => &self.result,
+                _ => self.tags.get(name).map_or(default, String::as_str),
+            };
+            writeln!(f, "[{name} \"{}\"]", escape(value))?;
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name) {
+                writeln!(f, "[{name} \"{}\"]", escape(value))?;
+            }
+        }
+        writeln!(f)?;
+
+        let fen: Vec<&str> = self
+            .tags
+            .get("FEN")
+            .map(|fen| fen.split_whitespace().collect())
+            .unwrap_or_default();
+        let mut is_white_turn = fen.get(1) != Some(&"b");
+        let mut move_number: u16 = fen.get(5).and_then(|n| n.parse().ok()).unwrap_or(1);
+        let mut tokens = Vec::new();
+        let mut after_comment = false;
+        for (ply, san) in self.moves.iter().enumerate() {
+            if is_white_turn {
+                tokens.push(format!("{move_number}. {san}"));
+            } else if ply == 0 || after_comment {
+                tokens.push(format!("{move_number}... {san}"));
+            } else {
+                tokens.push(san.clone());
+            }
+            let annotation = self.annotations.get(ply).map_or("", String::as_str);
+            tokens.extend(annotation.split_whitespace().map(String::from));
+            after_comment = annotation.contains('}');
+            if !is_white_turn {
+                move_number += 1;
+            }
+            is_white_turn = !is_white_turn;
+        }
+        tokens.push(self.result.clone());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_LENGTH {
+                writeln!(f, "{line}")?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(f, "{line}")
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Reads every game of a PGN collection, skipping comments, variations and NAGs.
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, String> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    let mut chars = pgn.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '[' => {
+                chars.next();
+                if !game.moves.is_empty() {
+                    finish_game(&mut games, &mut game);
+                }
+                let (name, value) = parse_tag(&mut chars)?;
+                game.tags.insert(name, value);
+            }
+            '{' => skip_until(&mut chars, '}', "comment")?,
+            ';' => {
+                chars.find(|&ch| ch == '\n');
+            }
+            '(' => skip_variation(&mut chars)?,
+            '$' => {
+                chars.next();
+                while chars.next_if(|ch| ch.is_ascii_digit()).is_some() {}
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(ch) =
+                    chars.next_if(|ch| !ch.is_whitespace() && !"{}()[];$".contains(*ch))
+                {
+                    token.push(ch);
+                }
+                if token.is_empty() {
+                    return Err(format!("Invalid Character \"{ch}\" in PGN"));
+                }
+                if RESULTS.contains(&token.as_str()) {
+                    game.result = token;
+                    finish_game(&mut games, &mut game);
+                    continue;
+                }
+                // drop move numbers such as "12." or "12..." and keep what follows them
+                let san = token.trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == '.');
+                if !san.is_empty() {
+                    game.moves.push(san.to_string());
+                }
+            }
+        }
+    }
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        finish_game(&mut games, &mut game);
+    }
+    Ok(games)
+}
+
+fn finish_game(games: &mut Vec<PgnGame>, game: &mut PgnGame) {
+    let mut game = std::mem::take(game);
+    if game.result.is_empty() {
+        game.result = game
+            .tags
+            .get("Result")
+            .cloned()
+            .unwrap_or_else(|| "*".to_string());
+    }
+    games.push(game);
+}
+
+fn parse_tag(chars: &mut Peekable<Chars>) -> Result<(String, String), String> {
+    let mut name = String::new();
+    while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != '"' && *ch != ']') {
+        name.push(ch);
+    }
+    while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    if chars.next() != Some('"') {
+        return Err(format!("Invalid Tag \"{name}\""));
+    }
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            Some('\\') => value.extend(chars.next()),
+            Some('"') => break,
+            Some(ch) => value.push(ch),
+            None => return Err(format!("Unterminated Tag \"{name}\"")),
+        }
+    }
+    while chars.next_if(|ch| ch.is_whitespace()).is_some() {}
+    if chars.next() != Some(']') {
+        return Err(format!("Unterminated Tag \"{name}\""));
+    }
+    Ok((name, value))
+}
+
+fn skip_until(chars: &mut Peekable<Chars>, end: char, what: &str) -> Result<(), String> {
+    chars.next();
+    match chars.find(|&ch| ch == end) {
+        Some(_) => Ok(()),
+        None => Err(format!("Unterminated {what} in PGN")),
+    }
+}
+
+fn skip_variation(chars: &mut Peekable<Chars>) -> Result<(), String> {
+    chars.next();
+    let mut depth = 1;
+    while depth > 0 {
+        match chars.peek() {
+            Some('{') => skip_until(chars, '}', "comment")?,
+            Some(_) => match chars.next() {
+                Some('(') => depth += 1,
+                Some(')') => depth -= 1,
+                _ => {}
+            },
+            None => return Err("Unterminated variation in PGN".to_string()),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pgn() {
+        let pgn = r#"[Event "Club \"Open\""]
+[White "Alice"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4 {gambit} (2... d5)) 2... Nc6 $1 3. Bb5 ; Ruy Lopez
+a6 1-0
+
+[Event "Second"]
+1.d4 d5 *
+"#;
+        let games = parse_pgn(pgn).unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tags["Event"], "Club \"Open\"");
+        assert_eq!(games[0].moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+        assert_eq!(games[0].result, "1-0");
+        assert_eq!(games[1].moves, ["d4", "d5"]);
+        assert_eq!(games[1].result, "*");
+    }
+
+    #[test]
+    fn test_write_pgn() {
+        let mut game = PgnGame {
+            moves: vec!["e4".to_string(), "e5".to_string(), "Nf3".to_string()],
+            result: "*".to_string(),
+            ..Default::default()
+        };
+        game.tags
+            .insert("White".to_string(), "Alice \"Ace\"".to_string());
+        game.tags.insert("ECO".to_string(), "C40".to_string());
+        let pgn = game.to_string();
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"));
+        assert!(pgn.contains(
+            "[White \"Alice \\\"Ace\\\"\"]\n[Black \"?\"]\n[Result \"*\"]\n[ECO \"C40\"]\n\n"
+        ));
+        assert!(pgn.ends_with("\n1. e4 e5 2. Nf3 *\n"));
+        let parsed = &parse_pgn(&pgn).unwrap()[0];
+        assert_eq!(parsed.tags["White"], game.tags["White"]);
+        assert_eq!(parsed.moves, game.moves);
+
+        game.tags.insert(
+            "FEN".to_string(),
+            "4k3/8/8/8/8/8/8/4K3 b - - 0 40".to_string(),
+        );
+        game.moves = vec!["Kd7".to_string(); 60];
+        let pgn = game.to_string();
+        assert!(pgn.contains("\n40... Kd7 41. Kd7 Kd7"));
+        assert!(pgn.lines().all(|line| line.len() <= LINE_LENGTH));
+    }
+
+    #[test]
+    fn test_invalid_pgn() {
+        assert!(parse_pgn("1. e4 {unterminated").is_err());
+        assert!(parse_pgn("[Event Open]").is_err());
+        assert!(parse_pgn("1. e4 (1. d4").is_err());
+        assert_eq!(parse_pgn("1. e4 e5").unwrap()[0].result, "*");
+    }
+}
@@ -1,10 +1,11 @@
 mod piece_trait;
 pub use piece_trait::PieceTrait;
+use serde::{Serialize, Serializer};
 
 /// Following the Standard Algebraic Notation (SAN), each piece is identified by a single letter taken from the standard English names
 /// pawn = "P", knight = "N", bishop = "B", rook = "R", queen = "Q" and king = "K").
 /// White pieces are designated using upper-case letters,  while black pieces use lowercase.
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Piece {
     WhitePawn,
     WhiteKnight,
@@ -66,6 +67,19 @@ impl Piece {
             _ => Err(format!("Invalid Chess Piece \"{}\"", ch)),
         }
     }
+
+    /// The usual material value in centipawns, kings counting more than everything else.
+    pub fn value(&self) -> i32 {
+        match self {
+            Piece::WhitePawn | Piece::BlackPawn => 100,
+            Piece::WhiteKnight | Piece::BlackKnight => 300,
+            Piece::WhiteBishop | Piece::BlackBishop => 300,
+            Piece::WhiteRook | Piece::BlackRook => 500,
+            Piece::WhiteQueen | Piece::BlackQueen => 900,
+            Piece::WhiteKing | Piece::BlackKing => 20000,
+            Piece::Empty => 0,
+        }
+    }
 }
 
 impl PieceTrait for Piece {
@@ -120,6 +134,12 @@ impl std::fmt::Display for Piece {
     }
 }
 
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_char(char::from(*self))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,144 @@
+use super::{ProblemSolution, Proofs, SolutionMove};
+use crate::ChessEngine;
+
+impl ChessEngine {
+    /// Finds every helpmate in `moves` moves: the side to move and its opponent cooperate so
+    /// that the side to move is mated by the opponent's `moves`-th move.
+    pub(crate) fn solve_helpmate(&mut self, moves: usize) -> Result<ProblemSolution, String> {
+        if moves == 0 {
+            return Err(format!("Invalid problem depth \"{moves}\""));
+        }
+        let mut proofs = Proofs::new();
+        let mut finals = Vec::new();
+        let tree = self.helpmate_tree(moves, &mut proofs, &mut finals);
+        let lines = finals.len();
+        // lines ending in the same position only play the same moves in another order
+        finals.sort_unstable();
+        finals.dedup();
+        let solutions = finals.len();
+        Ok(ProblemSolution::new(
+            format!("h#{moves}"),
+            tree,
+            solutions,
+            lines - solutions,
+        ))
+    }
+
+    /// Whether the side to move can be mated on the opponent's `moves`-th move with its help.
+    fn allows_helpmate(&mut self, moves: usize, proofs: &mut Proofs) -> bool {
+        let key = (self.polyglot_key(), moves);
+        if let Some(&proof) = proofs.get(&key) {
+            return proof;
+        }
+        let proof = self.generate_moves().into_iter().any(|r#move| {
+            let undo = self.make(r#move);
+            let mated = self.generate_moves().into_iter().any(|reply| {
+                let undo = self.make(reply);
+                let mated = if moves == 1 {
+                    self.is_checkmated()
+                } else {
+                    self.allows_helpmate(moves - 1, proofs)
+                };
+                self.unmake(undo);
+                mated
+            });
+            self.unmake(undo);
+            mated
+        });
+        proofs.insert(key, proof);
+        proof
+    }
+
+    /// Every line of a helpmate in `moves` moves, pushing the key of each final position to `finals`.
+    fn helpmate_tree(
+        &mut self,
+        moves: usize,
+        proofs: &mut Proofs,
+        finals: &mut Vec<u64>,
+    ) -> Vec<SolutionMove> {
+        let mut tree = Vec::new();
+        for r#move in self.generate_moves() {
+            let undo = self.make(r#move);
+            let mut replies = Vec::new();
+            for reply in self.generate_moves() {
+                let undo = self.make(reply);
+                let continuations = if moves == 1 {
+                    self.is_checkmated().then(|| {
+                        finals.push(self.polyglot_key());
+                        Vec::new()
+                    })
+                } else if self.allows_helpmate(moves - 1, proofs) {
+                    Some(self.helpmate_tree(moves - 1, proofs, finals))
+                } else {
+                    None
+                };
+                self.unmake(undo);
+                if let Some(continuations) = continuations {
+                    replies.push(self.solution_move(reply, continuations));
+                }
+            }
+            self.unmake(undo);
+            if !replies.is_empty() {
+                tree.push(self.solution_move(r#move, replies));
+            }
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_solve_helpmate() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, usize, Vec<&str>, Vec<&str>)> = vec![
+            (
+                "k7/8/1K6/8/8/8/8/7R b - - 0 1",
+                1,
+                vec!["Kb8"],
+                vec!["Rh8#"],
+            ),
+            (
+                "rnbqkbnr/ppppp1pp/5p2/8/3PP3/8/PPP2PPP/RNBQKBNR b KQkq - 0 2",
+                1,
+                vec!["g5"],
+                vec!["Qh5#"],
+            ),
+            ("7k/8/5K2/8/8/8/8/1R6 b - - 0 1", 1, vec![], vec![]),
+        ];
+        for (fen, moves, keys, mates) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let solution = chess_position.solve_helpmate(moves).unwrap();
+            assert_eq!(solution.keys, keys, "{fen}");
+            let replies: Vec<&str> = solution
+                .tree
+                .iter()
+                .flat_map(|key| key.replies.iter().map(|reply| reply.san.as_str()))
+                .collect();
+            assert_eq!(replies, mates, "{fen}");
+            assert_eq!(solution.solutions, keys.len());
+            assert_eq!(solution.duplicates, 0);
+            assert!(!solution.cooked);
+        }
+    }
+
+    #[test]
+    fn test_helpmate_cooks() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("7k/8/5K2/8/8/8/8/1R6 b - - 0 1")
+            .unwrap();
+        let solution = chess_position.solve_helpmate(2).unwrap();
+        assert_eq!(solution.stipulation, "h#2");
+        assert_eq!(solution.keys, vec!["Kh7", "Kg8"]);
+        assert!(solution.cooked);
+        assert!(solution.duplicates > 0);
+        assert_eq!(
+            chess_position.solve_helpmate(0),
+            Err("Invalid problem depth \"0\"".to_string())
+        );
+    }
+}
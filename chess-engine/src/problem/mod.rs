@@ -0,0 +1,278 @@
+mod helpmate;
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::chess_move::Move;
+use crate::ChessEngine;
+
+/// A move of a solution tree with the replies that keep to the stipulation,
+/// empty when the move ends the problem.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+pub struct SolutionMove {
+    pub san: String,
+    pub uci: String,
+    pub replies: Vec<SolutionMove>,
+}
+
+/// Every key of a problem with its solution tree. A problem is sound when it has exactly
+/// one solution: one key for direct mates and selfmates, one line of play for helpmates.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemSolution {
+    /// The stipulation in the usual notation, e.g. "#2", "h#3" or "s#2".
+    pub stipulation: String,
+    pub keys: Vec<String>,
+    pub unique_key: bool,
+    /// The number of distinct solutions, more than one meaning the problem is cooked.
+    pub solutions: usize,
+    /// Helpmate lines ending in the same position as another one, by transposition or a dual.
+    pub duplicates: usize,
+    pub cooked: bool,
+    pub tree: Vec<SolutionMove>,
+}
+
+impl ProblemSolution {
+    fn new(
+        stipulation: String,
+        tree: Vec<SolutionMove>,
+        solutions: usize,
+        duplicates: usize,
+    ) -> Self {
+        let keys: Vec<String> = tree.iter().map(|key| key.san.clone()).collect();
+        Self {
+            stipulation,
+            unique_key: keys.len() == 1,
+            keys,
+            solutions,
+            duplicates,
+            cooked: solutions > 1,
+            tree,
+        }
+    }
+}
+
+/// The stipulations the side to move forces against any defence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stipulation {
+    /// The side to move mates.
+    Mate,
+    /// The side to move makes the other side mate it.
+    Selfmate,
+}
+
+/// Whether positions, by Polyglot key and moves left, are won for the side to move.
+type Proofs = HashMap<(u64, usize), bool>;
+
+impl ChessEngine {
+    /// Proves or refutes a forced mate in at most `moves` moves for the side to move,
+    /// with every key and, after each defence, the shortest mating continuations.
+    pub(crate) fn solve_mate(&mut self, moves: usize) -> Result<ProblemSolution, String> {
+        self.solve_forced(Stipulation::Mate, moves)
+    }
+
+    /// Proves or refutes a selfmate in `moves` moves: the side to move forces the other side
+    /// to mate it within `moves` of its own moves, whatever the other side plays.
+    pub(crate) fn solve_selfmate(&mut self, moves: usize) -> Result<ProblemSolution, String> {
+        self.solve_forced(Stipulation::Selfmate, moves)
+    }
+
+    fn solve_forced(
+        &mut self,
+        stipulation: Stipulation,
+        moves: usize,
+    ) -> Result<ProblemSolution, String> {
+        if moves == 0 {
+            return Err(format!("Invalid problem depth \"{moves}\""));
+        }
+        let mut proofs = Proofs::new();
+        let tree = self.attacker_tree(stipulation, moves, &mut proofs);
+        let notation = match stipulation {
+            Stipulation::Mate => format!("#{moves}"),
+            Stipulation::Selfmate => format!("s#{moves}"),
+        };
+        let solutions = tree.len();
+        Ok(ProblemSolution::new(notation, tree, solutions, 0))
+    }
+
+    fn solution_move(&mut self, r#move: Move, replies: Vec<SolutionMove>) -> SolutionMove {
+        SolutionMove {
+            san: self.move_to_san(r#move),
+            uci: r#move.to_uci(),
+            replies,
+        }
+    }
+
+    /// Whether the side to move is in check and has no legal move.
+    pub(crate) fn is_checkmated(&self) -> bool {
+        self.board.is_check(self.is_white_turn) && self.generate_moves().is_empty()
+    }
+
+    /// Whether the side to move can fulfil the stipulation in at most `moves` moves whatever the defence.
+    fn forces(&mut self, stipulation: Stipulation, moves: usize, proofs: &mut Proofs) -> bool {
+        if moves == 0 {
+            return false;
+        }
+        let key = (self.polyglot_key(), moves);
+        if let Some(&proof) = proofs.get(&key) {
+            return proof;
+        }
+        let proof = self.generate_moves().into_iter().any(|r#move| {
+            let undo = self.make(r#move);
+            let lost = self.is_lost_within(stipulation, moves, proofs);
+            self.unmake(undo);
+            lost
+        });
+        proofs.insert(key, proof);
+        proof
+    }
+
+    /// Whether the defending side to move cannot escape the stipulation, with `moves` moves
+    /// of the attacker left counting the one just played.
+    fn is_lost_within(
+        &mut self,
+        stipulation: Stipulation,
+        moves: usize,
+        proofs: &mut Proofs,
+    ) -> bool {
+        let replies = self.generate_moves();
+        if replies.is_empty() {
+            return stipulation == Stipulation::Mate && self.board.is_check(self.is_white_turn);
+        }
+        replies.into_iter().all(|reply| {
+            let undo = self.make(reply);
+            let lost = match stipulation {
+                Stipulation::Mate => self.forces(stipulation, moves - 1, proofs),
+                Stipulation::Selfmate => {
+                    self.is_checkmated() || self.forces(stipulation, moves - 1, proofs)
+                }
+            };
+            self.unmake(undo);
+            lost
+        })
+    }
+
+    /// The attacker moves that fulfil the stipulation in at most `moves` moves, with the defences that follow.
+    fn attacker_tree(
+        &mut self,
+        stipulation: Stipulation,
+        moves: usize,
+        proofs: &mut Proofs,
+    ) -> Vec<SolutionMove> {
+        let mut tree = Vec::new();
+        for r#move in self.generate_moves() {
+            let undo = self.make(r#move);
+            let replies = self
+                .is_lost_within(stipulation, moves, proofs)
+                .then(|| self.defence_tree(stipulation, moves, proofs));
+            self.unmake(undo);
+            if let Some(replies) = replies {
+                tree.push(self.solution_move(r#move, replies));
+            }
+        }
+        tree
+    }
+
+    /// Every defence with `moves` moves of the attacker left, each with the shortest continuations
+    /// that follow, none when the defence itself completes a selfmate.
+    fn defence_tree(
+        &mut self,
+        stipulation: Stipulation,
+        moves: usize,
+        proofs: &mut Proofs,
+    ) -> Vec<SolutionMove> {
+        let mut tree = Vec::new();
+        for reply in self.generate_moves() {
+            let undo = self.make(reply);
+            let continuations = if self.is_checkmated() {
+                Vec::new()
+            } else {
+                let shortest = (1..moves)
+                    .find(|&left| self.forces(stipulation, left, proofs))
+                    .unwrap_or(moves - 1);
+                self.attacker_tree(stipulation, shortest, proofs)
+            };
+            self.unmake(undo);
+            tree.push(self.solution_move(reply, continuations));
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_solve_mate() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, usize, Vec<&str>)> = vec![
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 1, vec!["Ra8#"]),
+            ("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", 1, vec![]),
+            ("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1", 2, vec!["Ra6"]),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 1, vec![]),
+            (
+                "k7/2Q5/1K6/8/8/8/8/8 w - - 0 1",
+                1,
+                vec!["Qd8#", "Qc8#", "Qb7#", "Qa7#"],
+            ),
+        ];
+        for (fen, moves, keys) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let solution = chess_position.solve_mate(moves).unwrap();
+            assert_eq!(solution.keys, keys, "{fen}");
+            assert_eq!(solution.unique_key, keys.len() == 1);
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+        assert_eq!(
+            chess_position.solve_mate(0),
+            Err("Invalid problem depth \"0\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_solution_tree() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1")
+            .unwrap();
+        let solution = chess_position.solve_mate(2).unwrap();
+        assert_eq!(solution.stipulation, "#2");
+        let defences: Vec<(&str, Vec<&str>)> = solution.tree[0]
+            .replies
+            .iter()
+            .map(|reply| {
+                let mates = reply.replies.iter().map(|mate| mate.san.as_str());
+                (reply.san.as_str(), mates.collect())
+            })
+            .collect();
+        let mut expected = vec![("bxa6", vec!["b7#"])];
+        expected.extend(["Bc7", "Bd6", "Be5", "Bf4", "Bg3", "Bh2"].map(|san| (san, vec!["Rxa7#"])));
+        assert_eq!(defences, expected);
+    }
+
+    #[test]
+    fn test_solve_selfmate() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("8/8/8/3r4/3Q4/8/2R3PP/1k5K w - - 0 1")
+            .unwrap();
+        let solution = chess_position.solve_selfmate(1).unwrap();
+        assert_eq!(solution.stipulation, "s#1");
+        assert_eq!(solution.keys, vec!["Qd1+"]);
+        assert!(!solution.cooked);
+        let replies: Vec<&str> = solution.tree[0]
+            .replies
+            .iter()
+            .map(|reply| reply.san.as_str())
+            .collect();
+        assert_eq!(replies, vec!["Rxd1#"]);
+
+        chess_position
+            .parse_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .unwrap();
+        assert!(chess_position.solve_selfmate(1).unwrap().keys.is_empty());
+    }
+}
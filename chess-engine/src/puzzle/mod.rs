@@ -0,0 +1,307 @@
+use serde::Serialize;
+
+use crate::algebric_notation;
+use crate::chess_move::Move;
+use crate::fen_trait::FenParser;
+use crate::piece::PieceTrait;
+use crate::ChessEngine;
+
+/// A puzzle of the Lichess puzzle database.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Puzzle {
+    pub id: String,
+    /// The position before the opponent's setup move.
+    pub fen: String,
+    /// The setup move followed by the solution, alternating between the solver and the opponent, in UCI notation.
+    pub moves: Vec<String>,
+    pub rating: u16,
+    pub themes: Vec<String>,
+}
+
+/// Reads puzzles in the Lichess CSV format:
+/// `PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags`.
+/// The header line is optional and the columns after the rating may be left out.
+pub fn parse_puzzles(csv: &str) -> Result<Vec<Puzzle>, String> {
+    let mut puzzles = Vec::new();
+    for line in csv.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields[0] == "PuzzleId" {
+            continue;
+        }
+        let invalid = || format!("Invalid Puzzle \"{line}\"");
+        if fields.len() < 4 {
+            return Err(invalid());
+        }
+        let moves: Vec<String> = fields[2].split_whitespace().map(String::from).collect();
+        if moves.len() < 2 {
+            return Err(invalid());
+        }
+        puzzles.push(Puzzle {
+            id: fields[0].to_string(),
+            fen: fields[1].to_string(),
+            moves,
+            rating: fields[3].parse().map_err(|_| invalid())?,
+            themes: fields
+                .get(7)
+                .map(|themes| themes.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+        });
+    }
+    Ok(puzzles)
+}
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum PuzzleStatus {
+    Playing,
+    Solved,
+    Failed,
+}
+
+/// What became of the solver's move.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PuzzleReport {
+    pub status: PuzzleStatus,
+    /// The opponent's move played in reply, in UCI notation.
+    pub reply: Option<String>,
+    /// The move of the solution, when the solver's move was wrong.
+    pub expected: Option<String>,
+}
+
+/// The puzzle being solved and how far the solver got.
+#[derive(Debug, Clone)]
+pub struct PuzzleState {
+    pub puzzle: Puzzle,
+    /// The index in the puzzle moves of the next move to play.
+    next: usize,
+    status: PuzzleStatus,
+}
+
+impl ChessEngine {
+    /// Sets up the puzzle loaded at `index` and plays the opponent's first move.
+    pub(crate) fn start_puzzle(&mut self, index: usize) -> Result<(), String> {
+        let puzzle = self
+            .puzzles
+            .get(index)
+            .cloned()
+            .ok_or_else(|| format!("Invalid Puzzle Index \"{index}\""))?;
+        // checked on a copy first, so a broken puzzle leaves the game as it was
+        let mut position = ChessEngine::new();
+        position.parse_fen(&puzzle.fen)?;
+        let setup = position.puzzle_move_at(&puzzle, 0)?;
+        self.parse_fen(&puzzle.fen)?;
+        self.game_status = self.get_status();
+        self.play(setup.from, setup.to, setup.promotion);
+        self.puzzle = Some(PuzzleState {
+            puzzle,
+            next: 1,
+            status: PuzzleStatus::Playing,
+        });
+        Ok(())
+    }
+
+    /// Checks the solver's move against the solution, accepting any mate when the solution mates,
+    /// then plays the opponent's reply. A wrong move is not played and fails the puzzle.
+    pub(crate) fn play_puzzle_move(&mut self, r#move: Move) -> Result<PuzzleReport, String> {
+        let Some(mut state) = self.puzzle.take() else {
+            return Err("No Puzzle In Progress".to_string());
+        };
+        let report = self.play_puzzle_move_in(&mut state, r#move);
+        self.puzzle = Some(state);
+        report
+    }
+
+    /// Plays the solver's move in the puzzle of `state`, which the caller keeps in every case.
+    fn play_puzzle_move_in(
+        &mut self,
+        state: &mut PuzzleState,
+        r#move: Move,
+    ) -> Result<PuzzleReport, String> {
+        if state.status != PuzzleStatus::Playing {
+            return Err("No Puzzle In Progress".to_string());
+        }
+        let r#move = self.with_default_promotion(r#move);
+        if !self.generate_moves().contains(&r#move) {
+            return Err(format!("Illegal Move \"{}\"", r#move.to_uci()));
+        }
+        let expected = self.puzzle_move_at(&state.puzzle, state.next)?;
+        let is_last = state.next + 1 == state.puzzle.moves.len();
+        let undo = self.make(r#move);
+        let is_mate = self.is_checkmated();
+        self.unmake(undo);
+
+        let mut report = PuzzleReport {
+            status: PuzzleStatus::Playing,
+            reply: None,
+            expected: None,
+        };
+        if r#move != expected && !(is_last && is_mate) {
+            state.status = PuzzleStatus::Failed;
+            report.status = PuzzleStatus::Failed;
+            report.expected = Some(expected.to_uci());
+            return Ok(report);
+        }
+        self.play(r#move.from, r#move.to, r#move.promotion);
+        state.next += 1;
+        if state.next < state.puzzle.moves.len() {
+            let reply = self.puzzle_move_at(&state.puzzle, state.next)?;
+            self.play(reply.from, reply.to, reply.promotion);
+            report.reply = Some(reply.to_uci());
+            state.next += 1;
+        }
+        if state.next >= state.puzzle.moves.len() {
+            state.status = PuzzleStatus::Solved;
+        }
+        report.status = state.status;
+        Ok(report)
+    }
+
+    /// The move of the puzzle at `index`, checked to be legal in the current position.
+    fn puzzle_move_at(&self, puzzle: &Puzzle, index: usize) -> Result<Move, String> {
+        let uci = &puzzle.moves[index];
+        let (from, to, promotion) = algebric_notation::from_uci(uci)?;
+        let promotion = promotion
+            .map(|promotion| self.parse_promotion(&promotion.to_string()))
+            .transpose()?;
+        let r#move = self.with_default_promotion(Move::new(from.into(), to.into(), promotion));
+        if !self.generate_moves().contains(&r#move) {
+            return Err(format!(
+                "Illegal Move \"{uci}\" in Puzzle \"{}\"",
+                puzzle.id
+            ));
+        }
+        Ok(r#move)
+    }
+
+    /// The move with a queen promotion when a pawn reaches the last rank without one.
+    fn with_default_promotion(&self, r#move: Move) -> Move {
+        if r#move.promotion.is_none()
+            && self.board[r#move.from].is_pawn()
+            && matches!(r#move.to >> 4, 0 | 7)
+        {
+            let queen = self
+                .parse_promotion("q")
+                .expect("A queen is a valid promotion");
+            return Move::new(r#move.from, r#move.to, Some(queen));
+        }
+        r#move
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN_STARTING_POSITION;
+
+    const PUZZLES: &str = "PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags
+00008,r6k/pp2r2p/4Rp1Q/3p4/8/1N1P2R1/PqP2bPP/7K b - - 0 24,f2g3 e6e7 b2b1 b3c1 b1c1 h6c1,1913,75,94,6230,crushing hangingPiece long middlegame,https://lichess.org/787zsVup/black#47,
+mate1,1k6/2Q5/1K6/8/8/8/8/8 b - - 0 1,b8a8 c7c8,800
+";
+
+    fn play_uci(chess_position: &mut ChessEngine, uci: &str) -> Result<PuzzleReport, String> {
+        let (from, to, _) = algebric_notation::from_uci(uci)?;
+        chess_position.play_puzzle_move(Move::new(from.into(), to.into(), None))
+    }
+
+    #[test]
+    fn test_parse_puzzles() {
+        let puzzles = parse_puzzles(PUZZLES).unwrap();
+        assert_eq!(puzzles.len(), 2);
+        assert_eq!(puzzles[0].id, "00008");
+        assert_eq!(puzzles[0].moves.len(), 6);
+        assert_eq!(puzzles[0].rating, 1913);
+        assert_eq!(
+            puzzles[0].themes,
+            vec!["crushing", "hangingPiece", "long", "middlegame"]
+        );
+        assert!(puzzles[1].themes.is_empty());
+
+        let arr: Vec<&str> = vec![
+            "1,8/8/8/8/8/8/8/8 w - - 0 1",
+            "1,fen,e2e4,1500",
+            "1,fen,e2e4 e7e5,high",
+        ];
+        for csv in arr {
+            assert_eq!(parse_puzzles(csv), Err(format!("Invalid Puzzle \"{csv}\"")));
+        }
+    }
+
+    #[test]
+    fn test_solve_puzzle() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.puzzles = parse_puzzles(PUZZLES).unwrap();
+        chess_position.start_puzzle(0).unwrap();
+        assert!(chess_position.is_white_turn);
+        let arr: Vec<(&str, PuzzleStatus, Option<&str>)> = vec![
+            ("e6e7", PuzzleStatus::Playing, Some("b2b1")),
+            ("b3c1", PuzzleStatus::Playing, Some("b1c1")),
+            ("h6c1", PuzzleStatus::Solved, None),
+        ];
+        for (uci, status, reply) in arr {
+            let report = play_uci(&mut chess_position, uci).unwrap();
+            assert_eq!(report.status, status, "{uci}");
+            assert_eq!(report.reply.as_deref(), reply);
+        }
+        assert_eq!(chess_position.history.len(), 6);
+        assert!(play_uci(&mut chess_position, "c1c2").is_err());
+    }
+
+    #[test]
+    fn test_failed_puzzle() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.puzzles = parse_puzzles(PUZZLES).unwrap();
+        chess_position.start_puzzle(0).unwrap();
+        let fen = chess_position.get_fen();
+        assert!(play_uci(&mut chess_position, "e6d5").is_err());
+        let report = play_uci(&mut chess_position, "h6h7").unwrap();
+        assert_eq!(report.status, PuzzleStatus::Failed);
+        assert_eq!(report.expected.as_deref(), Some("e6e7"));
+        assert_eq!(chess_position.get_fen(), fen);
+        assert_eq!(
+            chess_position.start_puzzle(2),
+            Err("Invalid Puzzle Index \"2\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_broken_puzzle() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.puzzles = parse_puzzles(
+            "setup,1k6/2Q5/1K6/8/8/8/8/8 b - - 0 1,b8b7 c7c8,800
+solution,1k6/2Q5/1K6/8/8/8/8/8 b - - 0 1,b8a8 h1h2,800",
+        )
+        .unwrap();
+        chess_position.parse_fen(FEN_STARTING_POSITION).unwrap();
+        assert!(chess_position.start_puzzle(0).is_err());
+        assert_eq!(chess_position.get_fen(), FEN_STARTING_POSITION);
+        assert!(chess_position.puzzle.is_none());
+
+        chess_position.start_puzzle(1).unwrap();
+        let fen = chess_position.get_fen();
+        assert!(play_uci(&mut chess_position, "c7c8").is_err());
+        assert_eq!(chess_position.get_fen(), fen);
+        assert!(chess_position.puzzle.is_some());
+    }
+
+    #[test]
+    fn test_alternative_mate() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.puzzles = parse_puzzles(PUZZLES).unwrap();
+        let arr: Vec<(&str, PuzzleStatus)> = vec![
+            ("c7c8", PuzzleStatus::Solved),
+            ("c7b7", PuzzleStatus::Solved),
+            ("c7d8", PuzzleStatus::Solved),
+            ("c7c6", PuzzleStatus::Failed),
+        ];
+        for (uci, status) in arr {
+            chess_position.start_puzzle(1).unwrap();
+            assert_eq!(
+                play_uci(&mut chess_position, uci).unwrap().status,
+                status,
+                "{uci}"
+            );
+        }
+    }
+}
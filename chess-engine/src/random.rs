@@ -0,0 +1,44 @@
+/// A small splitmix64 generator, seedable so games against the engine can be replayed in tests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Random(u64);
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, `n` must not be zero.
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Self::new(crate::utils::now() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_is_reproducible() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert!((0..100).all(|_| a.below(6) < 6));
+        assert_ne!(Random::new(1).next_u64(), Random::new(2).next_u64());
+    }
+}
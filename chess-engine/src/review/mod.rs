@@ -0,0 +1,253 @@
+use serde::Serialize;
+
+use crate::search::{Score, SearchResult};
+use crate::ChessEngine;
+
+/// Centipawn scores are kept within this limit when comparing moves, mates counting as the limit.
+const SCORE_LIMIT: i32 = 1000;
+/// The advantage from which a position counts as won.
+const WINNING: i32 = 300;
+/// The advantage below which a won position counts as let go.
+const NOT_WINNING: i32 = 100;
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum Classification {
+    Best,
+    Excellent,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+    /// A move that lets a winning position go.
+    MissedWin,
+}
+
+impl Classification {
+    fn from_loss(loss: i32) -> Self {
+        match loss {
+            0 => Classification::Best,
+            1..=20 => Classification::Excellent,
+            21..=50 => Classification::Good,
+            51..=100 => Classification::Inaccuracy,
+            101..=300 => Classification::Mistake,
+            _ => Classification::Blunder,
+        }
+    }
+
+    /// The Numeric Annotation Glyph written after the move in PGN, if any.
+    pub fn nag(self) -> Option<&'static str> {
+        match self {
+            Classification::Inaccuracy => Some("$6"),
+            Classification::Mistake | Classification::MissedWin => Some("$2"),
+            Classification::Blunder => Some("$4"),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Classification {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Classification::Best => write!(f, "Best"),
+            Classification::Excellent => write!(f, "Excellent"),
+            Classification::Good => write!(f, "Good"),
+            Classification::Inaccuracy => write!(f, "Inaccuracy"),
+            Classification::Mistake => write!(f, "Mistake"),
+            Classification::Blunder => write!(f, "Blunder"),
+            Classification::MissedWin => write!(f, "Missed Win"),
+        }
+    }
+}
+
+/// A move of the game with how it compares to the engine's choice.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewedMove {
+    /// The ply of the move, the first move of the game being 1.
+    pub ply: usize,
+    pub white: bool,
+    pub san: String,
+    pub uci: String,
+    /// The evaluation after the move, from white's side.
+    pub eval: Score,
+    /// The move the engine prefers, in SAN.
+    pub best_move: Option<String>,
+    /// The evaluation after the best move, from white's side.
+    pub best_eval: Score,
+    pub centipawn_loss: i32,
+    pub classification: Classification,
+    /// From 0 to 100, how much of the winning chances the move kept.
+    pub accuracy: f64,
+}
+
+/// The review of a whole game, with the annotated PGN.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GameReview {
+    pub moves: Vec<ReviewedMove>,
+    /// The mean accuracy of white's moves, if white played any.
+    pub white_accuracy: Option<f64>,
+    pub black_accuracy: Option<f64>,
+    /// The game as PGN with a NAG on the weak moves and an `[%eval]` comment after every move.
+    pub pgn: String,
+}
+
+fn clamp(score: i32) -> i32 {
+    Score::from_internal(score).clamped(SCORE_LIMIT)
+}
+
+impl Score {
+    /// Centipawns kept within `limit`, mates counting as the limit.
+    fn clamped(self, limit: i32) -> i32 {
+        match self {
+            Score::Cp(cp) => cp.clamp(-limit, limit),
+            Score::Mate(moves) if moves > 0 => limit,
+            Score::Mate(_) => -limit,
+        }
+    }
+}
+
+/// The chances of winning in percent for an advantage in centipawns, as fitted by Lichess.
+fn winning_chances(cp: i32) -> f64 {
+    50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * cp as f64).exp()) - 1.0)
+}
+
+/// The accuracy of a move that takes the winning chances from `before` to `after`.
+fn move_accuracy(before: i32, after: i32) -> f64 {
+    let drop = (winning_chances(before) - winning_chances(after)).max(0.0);
+    (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+impl ChessEngine {
+    /// Searches every position of the game to `depth` plies and grades each move by the
+    /// centipawns it gave away compared to the engine's choice.
+    pub(crate) fn review(&self, depth: u32) -> GameReview {
+        let positions = self.replay();
+        let mut analyses: Vec<SearchResult> = positions
+            .iter()
+            .map(|(engine, _)| engine.clone().analyse(depth))
+            .collect();
+        if let Some((engine, r#move)) = positions.last() {
+            let mut last = engine.clone();
+            last.make(*r#move);
+            analyses.push(last.analyse(depth));
+        }
+
+        let mut moves = Vec::new();
+        for (ply, (engine, r#move)) in positions.into_iter().enumerate() {
+            let mut engine = engine;
+            let white = engine.is_white_turn;
+            let before = &analyses[ply];
+            let is_best = before.best_move == Some(r#move);
+            // the best move keeps the score of the deeper search, unless it ends the game
+            let after = &analyses[ply + 1];
+            let played = if is_best && after.best_move.is_some() {
+                before.score
+            } else {
+                -after.score
+            };
+            let (best_cp, played_cp) = (clamp(before.score), clamp(played));
+            let centipawn_loss = (best_cp - played_cp).max(0);
+            let classification = if is_best {
+                Classification::Best
+            } else if best_cp >= WINNING && played_cp < NOT_WINNING {
+                Classification::MissedWin
+            } else {
+                Classification::from_loss(centipawn_loss)
+            };
+            let from_white = |score: i32| Score::from_internal(if white { score } else { -score });
+            moves.push(ReviewedMove {
+                ply: ply + 1,
+                white,
+                san: engine.move_to_san(r#move),
+                uci: r#move.to_uci(),
+                eval: from_white(played),
+                best_move: before.best_move.map(|best| engine.move_to_san(best)),
+                best_eval: from_white(before.score),
+                centipawn_loss,
+                classification,
+                accuracy: move_accuracy(best_cp, played_cp),
+            });
+        }
+
+        let mut game = self.to_pgn();
+        game.annotations = moves
+            .iter()
+            .map(
+                |reviewed| match (reviewed.classification.nag(), &reviewed.best_move) {
+                    (Some(nag), Some(best)) => format!(
+                        "{nag} {{ [%eval {}] {}. {best} was best. }}",
+                        reviewed.eval, reviewed.classification
+                    ),
+                    _ => format!("{{ [%eval {}] }}", reviewed.eval),
+                },
+            )
+            .collect();
+        GameReview {
+            white_accuracy: mean(moves.iter().filter(|m| m.white).map(|m| m.accuracy)),
+            black_accuracy: mean(moves.iter().filter(|m| !m.white).map(|m| m.accuracy)),
+            moves,
+            pgn: game.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review() {
+        let mut chess_position = ChessEngine::new();
+        for (from, to) in [
+            ("e2", "e4"),
+            ("e7", "e5"),
+            ("d1", "h5"),
+            ("b8", "c6"),
+            ("f1", "c4"),
+            ("g8", "f6"),
+            ("h5", "f7"),
+        ] {
+            let from = crate::algebric_notation::from_string(from).unwrap().into();
+            let to = crate::algebric_notation::from_string(to).unwrap().into();
+            chess_position.play(from, to, None).unwrap();
+        }
+        let review = chess_position.review(2);
+        assert_eq!(review.moves.len(), 7);
+        let nf6 = &review.moves[5];
+        assert_eq!((nf6.san.as_str(), nf6.white), ("Nf6", false));
+        assert_eq!(nf6.classification, Classification::Blunder);
+        assert_eq!(nf6.eval, Score::Mate(1));
+        assert!(nf6.centipawn_loss > SCORE_LIMIT);
+        let mate = &review.moves[6];
+        assert_eq!(mate.san, "Qxf7#");
+        assert_eq!(mate.classification, Classification::Best);
+        assert!(review.white_accuracy.unwrap() > review.black_accuracy.unwrap());
+        assert!(review.pgn.contains("3... Nf6 $4 { [%eval #1] Blunder."));
+        assert!(review.pgn.contains("4. Qxf7# { [%eval #0] } 1-0"));
+    }
+
+    #[test]
+    fn test_classification() {
+        let arr: Vec<(i32, Classification)> = vec![
+            (0, Classification::Best),
+            (15, Classification::Excellent),
+            (40, Classification::Good),
+            (80, Classification::Inaccuracy),
+            (250, Classification::Mistake),
+            (900, Classification::Blunder),
+        ];
+        for (loss, classification) in arr {
+            assert_eq!(Classification::from_loss(loss), classification);
+        }
+        assert!(move_accuracy(0, 0) > 99.99);
+        assert!(move_accuracy(100, -200) < move_accuracy(100, 50));
+        assert_eq!(ChessEngine::new().review(1).white_accuracy, None);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::algebric_notation;
+use crate::chess_move::Move;
+use crate::piece::{Piece, PieceTrait};
+use crate::ChessEngine;
+
+fn piece_letter(piece: Piece) -> Option<char> {
+    match char::from(piece).to_ascii_uppercase() {
+        'P' | ' ' => None,
+        letter => Some(letter),
+    }
+}
+
+impl ChessEngine {
+    /// Finds the legal move written in Standard Algebraic Notation, e.g. "Nbd7", "exd6" or "e8=Q+".
+    pub(crate) fn parse_san(&self, san: &str) -> Result<Move, String> {
+        let invalid = || format!("Invalid SAN \"{san}\"");
+        let clean = san.trim_end_matches(['+', '#', '!', '?']);
+        let moves = self.generate_moves();
+
+        let castling = match clean {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castling {
+            return moves
+                .into_iter()
+                .find(|r#move| {
+                    self.board[r#move.from].is_king()
+                        && r#move.from.abs_diff(r#move.to) == 2
+                        && (r#move.to > r#move.from) == king_side
+                })
+                .ok_or_else(|| format!("Illegal Move \"{san}\""));
+        }
+
+        if !clean.is_ascii() || clean.len() < 2 {
+            return Err(invalid());
+        }
+        let (letter, rest) = match clean.chars().next() {
+            Some(letter @ ('N' | 'B' | 'R' | 'Q' | 'K')) => (Some(letter), &clean[1..]),
+            _ => (None, clean),
+        };
+        let (rest, promotion) = match rest.rsplit_once('=') {
+            Some((rest, promotion)) => (rest, promotion.chars().next()),
+            None if letter.is_none() && rest.ends_with(['N', 'B', 'R', 'Q']) => {
+                (&rest[..rest.len() - 1], rest.chars().last())
+            }
+            None => (rest, None),
+        };
+        if rest.len() < 2 {
+            return Err(invalid());
+        }
+        let to = algebric_notation::from_string(&rest[rest.len() - 2..])? as usize;
+        let disambiguation = rest[..rest.len() - 2].trim_end_matches(['x', ':', '-']);
+
+        let candidates: Vec<Move> = moves
+            .into_iter()
+            .filter(|r#move| {
+                let piece = self.board[r#move.from];
+                let square = algebric_notation::to_string(r#move.from as u8);
+                r#move.to == to
+                    && piece_letter(piece) == letter
+                    && disambiguation.chars().all(|ch| square.contains(ch))
+                    && match (r#move.promotion, promotion) {
+                        (Some(piece), Some(promotion)) => {
+                            piece_letter(piece) == Some(promotion.to_ascii_uppercase())
+                        }
+                        (Some(piece), None) => piece.is_queen(),
+                        (None, Some(_)) => false,
+                        (None, None) => true,
+                    }
+            })
+            .collect();
+        match candidates.as_slice() {
+            [r#move] => Ok(*r#move),
+            [] => Err(format!("Illegal Move \"{san}\"")),
+            _ => Err(format!("Ambiguous Move \"{san}\"")),
+        }
+    }
+
+    /// Writes a legal move in Standard Algebraic Notation, including the check or mate suffix.
+    pub(crate) fn move_to_san(&mut self, r#move: Move) -> String {
+        let piece = self.board[r#move.from];
+        let target = algebric_notation::to_string(r#move.to as u8);
+        let mut san = String::new();
+        if piece.is_king() && r#move.from.abs_diff(r#move.to) == 2 {
+            san.push_str(if r#move.to > r#move.from {
+                "O-O"
+            } else {
+                "O-O-O"
+            });
+        } else {
+            let is_capture = !self.board[r#move.to].is_empty()
+                || (piece.is_pawn() && r#move.from % 16 != r#move.to % 16);
+            match piece_letter(piece) {
+                Some(letter) => {
+                    san.push(letter);
+                    let rivals: Vec<usize> = self
+                        .legal_moves()
+                        .into_iter()
+                        .filter(|&(from, to)| {
+                            to == r#move.to && from != r#move.from && self.board[from] == piece
+                        })
+                        .map(|(from, _)| from)
+                        .collect();
+                    let square = algebric_notation::to_string(r#move.from as u8);
+                    if !rivals.is_empty() {
+                        if rivals.iter().all(|rival| rival % 16 != r#move.from % 16) {
+                            san.push_str(&square[..1]);
+                        } else if rivals.iter().all(|rival| rival / 16 != r#move.from / 16) {
+                            san.push_str(&square[1..]);
+                        } else {
+                            san.push_str(&square);
+                        }
+                    }
+                }
+                None if is_capture => {
+                    san.push_str(&algebric_notation::to_string(r#move.from as u8)[..1]);
+                }
+                None => {}
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&target);
+            if let Some(letter) = r#move.promotion.and_then(piece_letter) {
+                san.push('=');
+                san.push(letter);
+            }
+        }
+
+        let undo = self.make(r#move);
+        if self.board.is_check(self.is_white_turn) {
+            san.push(if self.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        self.unmake(undo);
+        san
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_san_round_trip() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, &str, Move)> = vec![
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                "Nf3",
+                Move::new(6, 37, None),
+            ),
+            (
+                "3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1",
+                "O-O-O+",
+                Move::new(4, 2, None),
+            ),
+            (
+                "4k3/8/8/8/8/8/4K3/R6R w - - 0 1",
+                "Rhf1",
+                Move::new(7, 5, None),
+            ),
+            (
+                "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1",
+                "R1a3",
+                Move::new(0, 32, None),
+            ),
+            (
+                "4k3/8/8/8/1Q6/8/1Q1Q4/4K3 w - - 0 1",
+                "Qb2c3",
+                Move::new(17, 34, None),
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                "exd6",
+                Move::new(68, 83, None),
+            ),
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                "cxd8=N",
+                Move::new(98, 115, Some(Piece::WhiteKnight)),
+            ),
+            (
+                "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2",
+                "Qh4#",
+                Move::new(115, 55, None),
+            ),
+        ];
+        for (fen, san, r#move) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(chess_position.parse_san(san), Ok(r#move), "{fen} {san}");
+            assert_eq!(chess_position.move_to_san(r#move), san, "{fen}");
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_invalid_san() {
+        let mut chess_position = ChessEngine::new();
+        assert!(chess_position
+            .parse_san("Nf4")
+            .unwrap_err()
+            .contains("Illegal Move \"Nf4\""));
+        assert!(chess_position.parse_san("x").is_err());
+        assert!(chess_position.parse_san("e4e5e6").is_err());
+        chess_position
+            .parse_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1")
+            .unwrap();
+        assert!(chess_position
+            .parse_san("Rd1")
+            .unwrap_err()
+            .contains("Ambiguous Move \"Rd1\""));
+        assert!(chess_position.parse_san("O-O").is_err());
+        assert_eq!(
+            chess_position.parse_san("e8=Q"),
+            Err("Illegal Move \"e8=Q\"".to_string())
+        );
+    }
+}
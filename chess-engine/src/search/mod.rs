@@ -0,0 +1,696 @@
+mod session;
+mod smp;
+mod transposition;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chess_move::Move;
+use crate::piece::PieceTrait;
+use crate::tablebase::Wdl;
+use crate::utils;
+use crate::{ChessEngine, Undo};
+
+pub(crate) use session::SearchSession;
+pub(crate) use smp::MAX_THREADS;
+use transposition::Bound;
+pub(crate) use transposition::{TranspositionTable, MAX_HASH_MB};
+
+/// The score of mating at the root, mates further away scoring one less per ply.
+pub const MATE: i32 = 30000;
+/// Bounds every score.
+const INFINITY: i32 = 32000;
+/// Scores beyond this are mates.
+const MATE_BOUND: i32 = MATE - 1000;
+/// The deepest ply the search goes, checks and captures included.
+const MAX_PLY: usize = 64;
+/// The score of a tablebase win at the root, below every mate so that mates found are preferred.
+pub(crate) const TABLEBASE_WIN: i32 = MATE_BOUND - 1 - MAX_PLY as i32;
+
+/// A score for the side to move: centipawns, or the moves to mate, negative when it gets mated.
+#[derive(Serialize, Debug, PartialEq, Eq, Clone, Copy)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum Score {
+    Cp(i32),
+    Mate(i32),
+}
+
+impl Score {
+    /// Reads a search score, where mates count down from [`MATE`] by the plies to go.
+    pub fn from_internal(score: i32) -> Self {
+        if score > MATE_BOUND {
+            Score::Mate((MATE - score + 1) / 2)
+        } else if score < -MATE_BOUND {
+            Score::Mate(-(MATE + score) / 2)
+        } else {
+            Score::Cp(score)
+        }
+    }
+}
+
+impl std::fmt::Display for Score {
+    /// Writes the score in pawns as in PGN `[%eval]` comments, e.g. "0.35", "-1.20" or "#-3".
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Score::Cp(cp) => write!(f, "{:.2}", *cp as f64 / 100.0),
+            Score::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+/// The outcome of a search.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    /// The score for the side to move, `MATE` less the plies to mate when it mates.
+    pub score: i32,
+    /// The principal variation, starting with the best move and ending with the captures
+    /// the quiescence search settles.
+    pub pv: Vec<Move>,
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// What stops a search, besides running out of moves to search. Unset limits do not apply.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchLimits {
+    /// The deepest iteration, in plies.
+    pub depth: Option<u32>,
+    /// The positions to search at most.
+    pub nodes: Option<u64>,
+    /// The milliseconds the search may take from its start.
+    pub movetime: Option<f64>,
+    /// The number of best lines to search, 1 when unset.
+    pub multi_pv: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NodeKind {
+    Full,
+    Quiescence,
+}
+
+/// A position of the search being searched, kept on a stack so the search can be paused.
+#[derive(Debug, Clone)]
+struct Frame {
+    kind: NodeKind,
+    depth: i32,
+    ply: usize,
+    alpha: i32,
+    beta: i32,
+    moves: Vec<Move>,
+    /// The index of the next move to search.
+    next: usize,
+    best: i32,
+    pv: Vec<Move>,
+    /// The move being searched below this position, to take back once its score is known.
+    current: Option<(Move, Undo)>,
+    /// The moves to try first here and below.
+    hint: Vec<Move>,
+    /// The Zobrist key of the position, to store its score in the transposition table.
+    key: u64,
+    /// Alpha when the position was opened, to tell an exact score from an upper bound.
+    original_alpha: i32,
+}
+
+/// An alpha-beta search over the engine's legal moves, deepening one ply at a time,
+/// with a quiescence search of the captures that do not lose material.
+///
+/// The search runs in steps of a few positions so it can share a thread with a UI: between
+/// two steps, the moves on the path to the position being searched stay made on the engine.
+#[derive(Debug, Default, Clone)]
+pub struct Search {
+    pub nodes: u64,
+    /// Positions on the path from the root, to score repetitions as draws.
+    keys: Vec<u64>,
+    /// Root moves left out of the search, those of the lines already found.
+    excluded: Vec<Move>,
+    stack: Vec<Frame>,
+    limits: SearchLimits,
+    /// When the search started, in milliseconds since the Unix epoch.
+    started: f64,
+    /// The positions searched when the time was last looked at.
+    time_checked: u64,
+    /// The depth of the iteration being searched.
+    depth: u32,
+    /// The lines of the last finished iteration, best first.
+    lines: Vec<SearchResult>,
+    /// The lines found so far by the iteration being searched.
+    iteration: Vec<SearchResult>,
+    running: bool,
+    /// Set by another thread to end the search, see [`ChessEngine::search_threads`].
+    stop_signal: Option<Arc<AtomicBool>>,
+    /// The most pieces of the engine's Syzygy tables, 0 without tables.
+    tablebase_pieces: usize,
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts searching `engine` within `limits`, dropping the search under way if any.
+    pub fn start(&mut self, engine: &mut ChessEngine, limits: SearchLimits) {
+        engine.hash.new_search();
+        self.begin(engine, limits, 1);
+    }
+
+    /// Settles the captures and promotions of `engine`'s position with the quiescence search
+    /// alone, returning the score for the side to move and the line played to the quiet position.
+    pub(crate) fn quiesce(&mut self, engine: &mut ChessEngine) -> (i32, Vec<Move>) {
+        self.stop(engine);
+        *self = Search::default();
+        match self.enter_quiescence(engine, 0, -INFINITY, INFINITY) {
+            Ok(frame) => {
+                self.stack.push(frame);
+                self.run(engine, u64::MAX)
+                    .expect("a search without limits to finish")
+            }
+            Err(score) => (score, Vec::new()),
+        }
+    }
+
+    /// Starts the search at `depth`.
+    fn begin(&mut self, engine: &mut ChessEngine, limits: SearchLimits, depth: u32) {
+        self.stop(engine);
+        *self = Search {
+            limits,
+            started: utils::now(),
+            depth: depth.max(1),
+            running: true,
+            stop_signal: self.stop_signal.take(),
+            tablebase_pieces: engine.tablebase.max_pieces(),
+            ..Default::default()
+        };
+        self.start_line(engine);
+    }
+
+    /// Searches about `budget` more positions and returns whether the search goes on.
+    pub fn step(&mut self, engine: &mut ChessEngine, budget: u64) -> bool {
+        let limit = self.nodes.saturating_add(budget);
+        while self.running {
+            let limit = limit.min(self.limits.nodes.unwrap_or(u64::MAX));
+            let Some((score, pv)) = self.run(engine, limit) else {
+                if self.nodes >= limit && self.nodes < self.limits.nodes.unwrap_or(u64::MAX) {
+                    break;
+                }
+                // out of time or positions: the iteration under way is dropped
+                self.stop(engine);
+                break;
+            };
+            if !self.iteration.is_empty() && pv.is_empty() {
+                self.finish_iteration(engine);
+                continue;
+            }
+            self.excluded.extend(pv.first());
+            self.iteration.push(SearchResult {
+                best_move: pv.first().copied(),
+                score,
+                pv,
+                depth: self.depth,
+                nodes: self.nodes,
+            });
+            if self.iteration.len() < self.limits.multi_pv.max(1) {
+                self.start_line(engine);
+            } else {
+                self.finish_iteration(engine);
+            }
+        }
+        self.running
+    }
+
+    /// Ends the search, taking back the moves it made on `engine`. Stopped before its first
+    /// iteration finishes, the search keeps the best line found so far.
+    pub fn stop(&mut self, engine: &mut ChessEngine) {
+        if self.lines.is_empty() {
+            self.lines.extend(self.best_so_far());
+        }
+        while let Some(frame) = self.stack.pop() {
+            if let Some((_, undo)) = frame.current {
+                engine.unmake(undo);
+            }
+        }
+        self.keys.clear();
+        self.running = false;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// The milliseconds since the search started.
+    pub fn elapsed(&self) -> f64 {
+        utils::now() - self.started
+    }
+
+    /// The depth of the last finished iteration, 0 before the first one.
+    pub fn depth(&self) -> u32 {
+        self.lines.first().map_or(0, |line| line.depth)
+    }
+
+    /// The lines of the last finished iteration, best first.
+    pub fn lines(&self) -> &[SearchResult] {
+        &self.lines
+    }
+
+    /// The best line of the last finished iteration or, before the first one finishes,
+    /// the best line found so far by the first one.
+    pub fn best_so_far(&self) -> Option<SearchResult> {
+        if let Some(line) = self.lines.first() {
+            return Some(line.clone());
+        }
+        let root = self.stack.first()?;
+        (root.kind == NodeKind::Full && !root.pv.is_empty()).then(|| SearchResult {
+            best_move: root.pv.first().copied(),
+            score: root.best,
+            pv: root.pv.clone(),
+            depth: self.depth,
+            nodes: self.nodes,
+        })
+    }
+
+    /// Keeps the lines of the iteration just searched, then starts the next one unless a limit
+    /// is reached, every line is a mate, or the position has no moves.
+    fn finish_iteration(&mut self, engine: &mut ChessEngine) {
+        let mut lines = std::mem::take(&mut self.iteration);
+        lines.sort_by_key(|line| -line.score);
+        self.lines = lines;
+        let is_over = self.lines.iter().all(|line| line.score.abs() > MATE_BOUND)
+            || self
+                .lines
+                .first()
+                .is_some_and(|line| line.best_move.is_none())
+            || self
+                .limits
+                .depth
+                .is_some_and(|depth| self.depth >= depth.max(1))
+            || self.depth as usize >= MAX_PLY
+            || self.is_out_of_time();
+        if is_over {
+            self.stop(engine);
+        } else {
+            self.depth += 1;
+            self.start_line(engine);
+        }
+    }
+
+    /// Starts searching the best root move not in the lines of the iteration so far.
+    fn start_line(&mut self, engine: &mut ChessEngine) {
+        self.excluded = self
+            .iteration
+            .iter()
+            .filter_map(|line| line.best_move)
+            .collect();
+        let hint = self
+            .lines
+            .iter()
+            .find(|line| {
+                line.best_move
+                    .is_some_and(|r#move| !self.excluded.contains(&r#move))
+            })
+            .map_or_else(Vec::new, |line| line.pv.clone());
+        match self.enter(engine, self.depth as i32, 0, -INFINITY, INFINITY, &hint) {
+            Ok(frame) => self.stack.push(frame),
+            Err(score) => self.stack.push(Frame {
+                kind: NodeKind::Quiescence,
+                depth: 0,
+                ply: 0,
+                alpha: score,
+                beta: INFINITY,
+                moves: Vec::new(),
+                next: 0,
+                best: score,
+                pv: Vec::new(),
+                current: None,
+                hint: Vec::new(),
+                key: 0,
+                original_alpha: score,
+            }),
+        }
+    }
+
+    fn is_signaled(&self) -> bool {
+        self.stop_signal
+            .as_ref()
+            .is_some_and(|signal| signal.load(Ordering::Relaxed))
+    }
+
+    fn is_out_of_time(&self) -> bool {
+        self.limits
+            .movetime
+            .is_some_and(|movetime| self.elapsed() >= movetime)
+    }
+
+    /// Searches until the root position has a score and returns it with its principal
+    /// variation, or returns `None` when `limit` positions have been searched or time is up.
+    fn run(&mut self, engine: &mut ChessEngine, limit: u64) -> Option<(i32, Vec<Move>)> {
+        loop {
+            let top = self.stack.last()?;
+            if top.next < top.moves.len() {
+                if self.nodes >= limit || self.is_signaled() {
+                    return None;
+                }
+                if self.nodes - self.time_checked >= 1024 {
+                    self.time_checked = self.nodes;
+                    if self.is_out_of_time() {
+                        return None;
+                    }
+                }
+                let frame = self.stack.last_mut()?;
+                let r#move = frame.moves[frame.next];
+                frame.next += 1;
+                let child_hint = if frame.hint.first() == Some(&r#move) {
+                    frame.hint[1..].to_vec()
+                } else {
+                    Vec::new()
+                };
+                let (kind, depth, ply, alpha, beta) = (
+                    frame.kind,
+                    frame.depth - 1,
+                    frame.ply + 1,
+                    -frame.beta,
+                    -frame.alpha,
+                );
+                frame.current = Some((r#move, engine.make(r#move)));
+                let child = match kind {
+                    NodeKind::Full => self.enter(engine, depth, ply, alpha, beta, &child_hint),
+                    NodeKind::Quiescence => self.enter_quiescence(engine, ply, alpha, beta),
+                };
+                match child {
+                    Ok(child) => self.stack.push(child),
+                    Err(score) => self.backup(engine, score, Vec::new()),
+                }
+            } else {
+                let frame = self.stack.pop()?;
+                if frame.kind == NodeKind::Full {
+                    self.keys.pop();
+                    self.store(engine, &frame);
+                }
+                if self.stack.is_empty() {
+                    return Some((frame.best, frame.pv));
+                }
+                self.backup(engine, frame.best, frame.pv);
+            }
+        }
+    }
+
+    /// Keeps the score of a position searched to the end in the transposition table, except at
+    /// the root of a line that leaves moves out.
+    fn store(&self, engine: &ChessEngine, frame: &Frame) {
+        if frame.ply == 0 && !self.excluded.is_empty() {
+            return;
+        }
+        let (bound, best_move) = if frame.best <= frame.original_alpha {
+            (Bound::Upper, None)
+        } else if frame.best >= frame.beta {
+            (Bound::Lower, frame.pv.first().copied())
+        } else {
+            (Bound::Exact, frame.pv.first().copied())
+        };
+        engine.hash.store(
+            frame.key,
+            frame.ply,
+            frame.depth,
+            frame.best,
+            bound,
+            best_move,
+        );
+    }
+
+    /// Takes back the move searched from the top position, which scores `score` with `line`
+    /// for the other side, and keeps it if it is the best so far.
+    fn backup(&mut self, engine: &mut ChessEngine, score: i32, line: Vec<Move>) {
+        let frame = self.stack.last_mut().expect("a position to back up to");
+        let (r#move, undo) = frame.current.take().expect("a move searched");
+        engine.unmake(undo);
+        let score = -score;
+        match frame.kind {
+            NodeKind::Full => {
+                if score > frame.best {
+                    let mut pv = vec![r#move];
+                    pv.extend(line);
+                    frame.best = score;
+                    frame.pv = pv;
+                }
+                frame.alpha = frame.alpha.max(score);
+            }
+            NodeKind::Quiescence => {
+                if score > frame.alpha {
+                    let mut pv = vec![r#move];
+                    pv.extend(line);
+                    frame.pv = pv;
+                }
+                frame.best = if score >= frame.beta {
+                    score
+                } else {
+                    frame.alpha.max(score)
+                };
+                frame.alpha = frame.best;
+            }
+        }
+        if frame.alpha >= frame.beta {
+            frame.next = frame.moves.len();
+        }
+    }
+
+    /// Opens a position of the alpha-beta search, trying the moves of `hint` first,
+    /// or returns its score for the side to move when it needs no search.
+    fn enter(
+        &mut self,
+        engine: &mut ChessEngine,
+        depth: i32,
+        ply: usize,
+        alpha: i32,
+        beta: i32,
+        hint: &[Move],
+    ) -> Result<Frame, i32> {
+        self.nodes += 1;
+        let key = engine.polyglot_key();
+        // the tables answer for the positions they cover once a capture or pawn move resets
+        // the fifty-move count, which they assume to be 0: no repetition or fifty-move draw
+        // applies there yet, and they know better than the material count
+        if ply > 0 && engine.half_move_clock == 0 && self.tablebase_pieces > 0 {
+            if let Some(wdl) = engine.probe_wdl() {
+                return Err(match wdl {
+                    Wdl::Win => TABLEBASE_WIN - ply as i32,
+                    Wdl::Loss => -TABLEBASE_WIN + ply as i32,
+                    _ => 0,
+                });
+            }
+        }
+        if ply > 0
+            && (engine.half_move_clock >= 100
+                || engine.board.is_insufficient_material()
+                || self
+                    .keys
+                    .iter()
+                    .rev()
+                    .skip(1)
+                    .step_by(2)
+                    .any(|&seen| seen == key))
+        {
+            return Err(0);
+        }
+        let in_check = engine.board.is_check(engine.is_white_turn);
+        let depth = if in_check { depth + 1 } else { depth };
+        let entry = engine.hash.probe(key, ply);
+        if let Some(score) = entry.and_then(|entry| entry.cutoff(depth, alpha, beta)) {
+            if ply > 0 {
+                return Err(score);
+            }
+        }
+        let mut moves = engine.generate_moves();
+        if moves.is_empty() {
+            return Err(if in_check { -MATE + ply as i32 } else { 0 });
+        }
+        if depth <= 0 || ply >= MAX_PLY {
+            return self.enter_quiescence(engine, ply, alpha, beta);
+        }
+
+        if ply == 0 {
+            moves.retain(|r#move| !self.excluded.contains(r#move));
+        }
+        let hash_move = entry.and_then(|entry| entry.best_move);
+        order_moves(engine, &mut moves, hint.first().copied().or(hash_move));
+        self.keys.push(key);
+        Ok(Frame {
+            kind: NodeKind::Full,
+            depth,
+            ply,
+            alpha,
+            beta,
+            moves,
+            next: 0,
+            best: -INFINITY,
+            pv: Vec::new(),
+            current: None,
+            hint: hint.to_vec(),
+            key,
+            original_alpha: alpha,
+        })
+    }
+
+    /// Opens a position of the quiescence search, which settles its captures and promotions
+    /// so the evaluation is not taken in the middle of an exchange. Captures that lose material
+    /// are left out.
+    fn enter_quiescence(
+        &mut self,
+        engine: &mut ChessEngine,
+        ply: usize,
+        alpha: i32,
+        beta: i32,
+    ) -> Result<Frame, i32> {
+        self.nodes += 1;
+        let stand_pat = engine.evaluate();
+        if stand_pat >= beta || ply >= MAX_PLY {
+            return Err(stand_pat);
+        }
+        let alpha = alpha.max(stand_pat);
+        let mut moves: Vec<Move> = engine
+            .generate_moves()
+            .into_iter()
+            .filter(|r#move| is_tactical(engine, r#move) && engine.see(*r#move) >= 0)
+            .collect();
+        if moves.is_empty() {
+            return Err(alpha);
+        }
+        order_moves(engine, &mut moves, None);
+        Ok(Frame {
+            kind: NodeKind::Quiescence,
+            depth: 0,
+            ply,
+            alpha,
+            beta,
+            moves,
+            next: 0,
+            best: alpha,
+            pv: Vec::new(),
+            current: None,
+            hint: Vec::new(),
+            key: 0,
+            original_alpha: alpha,
+        })
+    }
+}
+
+fn is_tactical(engine: &ChessEngine, r#move: &Move) -> bool {
+    r#move.promotion.is_some()
+        || !engine.board[r#move.to].is_empty()
+        || (engine.board[r#move.from].is_pawn() && r#move.from % 16 != r#move.to % 16)
+}
+
+/// Puts the best move found so far first, then captures of the most valuable pieces
+/// by the least valuable ones, then promotions.
+fn order_moves(engine: &ChessEngine, moves: &mut [Move], first: Option<Move>) {
+    moves.sort_by_cached_key(|r#move| {
+        if Some(*r#move) == first {
+            return i32::MIN;
+        }
+        let victim = engine.board[r#move.to].value();
+        let attacker = engine.board[r#move.from].value();
+        let promotion = r#move.promotion.map_or(0, |piece| piece.value());
+        if victim > 0 || promotion > 0 {
+            -(victim * 10 + promotion - attacker / 100)
+        } else {
+            0
+        }
+    });
+}
+
+impl ChessEngine {
+    /// Searches the position to `depth` plies, on as many threads as the "Threads" option.
+    pub(crate) fn analyse(&mut self, depth: u32) -> SearchResult {
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        let search = self.search_threads(limits);
+        SearchResult {
+            nodes: search.nodes,
+            ..search.best_so_far().unwrap_or_default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_search() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, u32, Score, Option<&str>)> = vec![
+            (
+                "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+                2,
+                Score::Mate(1),
+                Some("a1a8"),
+            ),
+            (
+                "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1",
+                4,
+                Score::Mate(2),
+                Some("a1a6"),
+            ),
+            ("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3, Score::Cp(0), None),
+            ("Q5k1/8/6K1/8/8/8/8/8 b - - 0 1", 3, Score::Mate(0), None),
+            (
+                "4k3/8/4p3/3q4/8/8/8/3RK3 w - - 0 1",
+                1,
+                Score::Cp(0),
+                Some("d1d5"),
+            ),
+        ];
+        for (fen, depth, score, best_move) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let result = chess_position.analyse(depth);
+            if let Score::Cp(_) = score {
+                assert!(
+                    matches!(Score::from_internal(result.score), Score::Cp(_)),
+                    "{fen}"
+                );
+            } else {
+                assert_eq!(Score::from_internal(result.score), score, "{fen}");
+            }
+            assert_eq!(
+                result.best_move.map(|r#move| r#move.to_uci()),
+                best_move.map(String::from),
+                "{fen}"
+            );
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn test_transpositions() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+            .unwrap();
+        let first = chess_position.analyse(4);
+        let second = chess_position.analyse(4);
+        assert!(second.nodes < first.nodes / 2);
+        assert_eq!(second.best_move, first.best_move);
+        chess_position.clear_hash();
+        assert_eq!(chess_position.analyse(4).nodes, first.nodes);
+    }
+
+    #[test]
+    fn test_score() {
+        let arr: Vec<(i32, Score, &str)> = vec![
+            (35, Score::Cp(35), "0.35"),
+            (-120, Score::Cp(-120), "-1.20"),
+            (MATE - 1, Score::Mate(1), "#1"),
+            (MATE - 5, Score::Mate(3), "#3"),
+            (-MATE + 4, Score::Mate(-2), "#-2"),
+        ];
+        for (internal, score, text) in arr {
+            assert_eq!(Score::from_internal(internal), score);
+            assert_eq!(score.to_string(), text);
+        }
+    }
+}
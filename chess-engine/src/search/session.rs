@@ -0,0 +1,165 @@
+use serde::Serialize;
+
+use super::{Score, Search, SearchLimits};
+use crate::ChessEngine;
+
+/// A search of a copy of the engine's position, run a slice at a time.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchSession {
+    /// The copy searched. Between two steps, the moves on the path being searched are made on it.
+    engine: ChessEngine,
+    search: Search,
+    /// The Polyglot key of the position searched, to notice when the game moves on.
+    key: u64,
+}
+
+/// The progress of a search session, for the UI.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchInfo {
+    /// Whether more steps are needed to finish the search.
+    pub running: bool,
+    /// The depth of the last finished iteration.
+    pub depth: u32,
+    pub nodes: u64,
+    /// Milliseconds since the search started.
+    pub elapsed: f64,
+    /// The score of the best line for the side to move, if any.
+    pub score: Option<Score>,
+    pub best_move: Option<String>,
+    /// The best line in SAN.
+    pub pv: Vec<String>,
+}
+
+impl ChessEngine {
+    /// Starts searching the current position within `limits`, dropping the search under way.
+    pub(crate) fn start_search(&mut self, limits: SearchLimits) {
+        let mut engine = self.clone();
+        engine.search = None;
+        let mut search = Search::new();
+        search.start(&mut engine, limits);
+        self.search = Some(Box::new(SearchSession {
+            engine,
+            search,
+            key: self.polyglot_key(),
+        }));
+    }
+
+    /// Searches about `budget` more positions, or drops the search if a move was played since
+    /// it started. Returns the progress of the search, if there is one.
+    pub(crate) fn step_search(&mut self, budget: u64) -> Option<SearchInfo> {
+        let key = self.polyglot_key();
+        let session = self.search.as_mut()?;
+        if session.key != key {
+            self.search = None;
+            return None;
+        }
+        session.search.step(&mut session.engine, budget);
+        self.search_info()
+    }
+
+    /// Stops the search, keeping its best line for `bestSoFar`, and returns its progress.
+    pub(crate) fn stop_search(&mut self) -> Option<SearchInfo> {
+        let session = self.search.as_mut()?;
+        session.search.stop(&mut session.engine);
+        self.search_info()
+    }
+
+    /// The best move of the search, from its last finished iteration.
+    pub(crate) fn best_so_far(&self) -> Option<String> {
+        let session = self.search.as_ref()?;
+        let best = session.search.best_so_far()?;
+        Some(best.best_move?.to_uci())
+    }
+
+    fn search_info(&mut self) -> Option<SearchInfo> {
+        let session = self.search.as_ref()?;
+        let best = session.search.best_so_far();
+        let mut info = SearchInfo {
+            running: session.search.is_running(),
+            depth: session.search.depth(),
+            nodes: session.search.nodes,
+            elapsed: session.search.elapsed(),
+            score: best.as_ref().map(|best| Score::from_internal(best.score)),
+            best_move: None,
+            pv: Vec::new(),
+        };
+        if let Some(best) = best {
+            info.best_move = best.best_move.map(|r#move| r#move.to_uci());
+            let mut undos = Vec::new();
+            for r#move in best.pv {
+                info.pv.push(self.move_to_san(r#move));
+                undos.push(self.make(r#move));
+            }
+            for undo in undos.into_iter().rev() {
+                self.unmake(undo);
+            }
+        }
+        Some(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_search_session() {
+        let mut chess_position = ChessEngine::new();
+        let fen = "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1";
+        chess_position.parse_fen(fen).unwrap();
+        assert_eq!(chess_position.step_search(100), None);
+        assert_eq!(chess_position.best_so_far(), None);
+
+        chess_position.start_search(SearchLimits {
+            depth: Some(4),
+            ..Default::default()
+        });
+        let mut steps = 0;
+        let mut nodes = 0;
+        let info = loop {
+            let info = chess_position.step_search(100).unwrap();
+            steps += 1;
+            // a step ends once its budget is spent, within the positions of a single move
+            assert!(info.nodes - nodes <= 102);
+            nodes = info.nodes;
+            if !info.running {
+                break info;
+            }
+        };
+        assert!(steps > 1);
+        assert_eq!(info.score, Some(Score::Mate(2)));
+        assert_eq!(info.best_move.as_deref(), Some("a1a6"));
+        assert_eq!(info.pv[0], "Ra6");
+        assert_eq!(chess_position.best_so_far().as_deref(), Some("a1a6"));
+        assert_eq!(chess_position.get_fen(), fen);
+        let again = chess_position.step_search(100).unwrap();
+        assert_eq!((again.nodes, again.pv), (info.nodes, info.pv));
+    }
+
+    #[test]
+    fn test_stop_search() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.start_search(SearchLimits::default());
+        let info = chess_position.step_search(500).unwrap();
+        assert!(info.running);
+        let info = chess_position.stop_search().unwrap();
+        assert!(!info.running);
+        assert!(info.best_move.is_some());
+        assert!(chess_position.best_so_far().is_some());
+        assert!(!chess_position.step_search(500).unwrap().running);
+
+        chess_position.start_search(SearchLimits {
+            nodes: Some(300),
+            ..Default::default()
+        });
+        while chess_position.step_search(1000).unwrap().running {}
+        assert!(chess_position.stop_search().unwrap().nodes <= 302);
+
+        chess_position.start_search(SearchLimits::default());
+        chess_position.play(0x14, 0x34, None).unwrap();
+        assert_eq!(chess_position.step_search(500), None);
+        assert_eq!(chess_position.best_so_far(), None);
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::{Search, SearchLimits};
+use crate::nnue::Nnue;
+use crate::ChessEngine;
+
+/// The most threads a search can use.
+pub const MAX_THREADS: usize = 256;
+
+impl ChessEngine {
+    /// Searches the position within `limits` until the search is over and returns it.
+    ///
+    /// With more than one thread (Lazy SMP), helper threads search copies of the position
+    /// at the same time, every other one an iteration ahead, and share what they find
+    /// through the transposition table. The search of the calling thread decides the move,
+    /// and the helpers stop when it ends. Builds for wasm always search on one thread, so
+    /// their results are the same from one run to the next.
+    pub(crate) fn search_threads(&mut self, limits: SearchLimits) -> Search {
+        let mut search = Search::new();
+        let threads = if cfg!(target_arch = "wasm32") {
+            1
+        } else {
+            self.threads
+        };
+        if threads <= 1 {
+            search.start(self, limits);
+            while search.step(self, u64::MAX) {}
+            return search;
+        }
+
+        search.start(self, limits);
+        let signal = Arc::new(AtomicBool::new(false));
+        let helpers: Vec<ChessEngine> = (1..threads).map(|_| self.search_copy()).collect();
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = helpers
+                .into_iter()
+                .enumerate()
+                .map(|(index, mut engine)| {
+                    let signal = signal.clone();
+                    scope.spawn(move || {
+                        let mut helper = Search {
+                            stop_signal: Some(signal),
+                            ..Default::default()
+                        };
+                        helper.begin(&mut engine, limits, 1 + (index as u32 + 1) % 2);
+                        while helper.step(&mut engine, u64::MAX) {}
+                        helper.nodes
+                    })
+                })
+                .collect();
+            while search.step(self, u64::MAX) {}
+            signal.store(true, Ordering::Relaxed);
+            for handle in handles {
+                search.nodes += handle.join().unwrap_or(0);
+            }
+        });
+        search
+    }
+
+    /// A copy of the position for a helper thread, sharing the transposition table, the
+    /// evaluation weights, the network and the tablebases but leaving out the game, the book
+    /// and the puzzles.
+    fn search_copy(&self) -> ChessEngine {
+        ChessEngine {
+            board: self.board,
+            castling: self.castling,
+            is_white_turn: self.is_white_turn,
+            en_passant: self.en_passant,
+            half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
+            hash: self.hash.clone(),
+            weights: self.weights.clone(),
+            nnue: self.nnue.as_ref().map(Nnue::fresh),
+            tablebase: self.tablebase.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+    use crate::search::Score;
+
+    #[test]
+    fn test_search_threads() {
+        let mut chess_position = ChessEngine::new();
+        chess_position.set_option("Threads", "4").unwrap();
+        let arr: Vec<(&str, u32, Score, &str)> = vec![
+            (
+                "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+                2,
+                Score::Mate(1),
+                "a1a8",
+            ),
+            (
+                "kbK5/pp6/1P6/8/8/8/8/R7 w - - 0 1",
+                4,
+                Score::Mate(2),
+                "a1a6",
+            ),
+            (
+                "4k3/8/4p3/3q4/8/8/8/3RK3 w - - 0 1",
+                3,
+                Score::Cp(0),
+                "d1d5",
+            ),
+        ];
+        for (fen, depth, score, best_move) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            chess_position.clear_hash();
+            let result = chess_position.analyse(depth);
+            if let Score::Mate(_) = score {
+                assert_eq!(Score::from_internal(result.score), score, "{fen}");
+            }
+            assert_eq!(result.best_move.unwrap().to_uci(), best_move, "{fen}");
+            assert!(result.depth <= depth, "{fen}");
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::OnceLock;
+
+use super::MATE_BOUND;
+use crate::chess_move::Move;
+use crate::piece::Piece;
+
+/// The size of the table of a new engine, in megabytes.
+pub const DEFAULT_HASH_MB: usize = 16;
+/// The largest table, in megabytes.
+pub const MAX_HASH_MB: usize = 1024;
+
+/// The pieces a promotion can be stored as, by their index in the entry plus one.
+const PROMOTIONS: [Piece; 8] = [
+    Piece::WhiteKnight,
+    Piece::WhiteBishop,
+    Piece::WhiteRook,
+    Piece::WhiteQueen,
+    Piece::BlackKnight,
+    Piece::BlackBishop,
+    Piece::BlackRook,
+    Piece::BlackQueen,
+];
+
+/// How the stored score relates to the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The true score is at least the stored one: a move failed high.
+    Lower,
+    /// The true score is at most the stored one: no move reached alpha.
+    Upper,
+}
+
+/// What the table knows of a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub best_move: Option<Move>,
+    /// The score, with mates counted from the root of the search that probed it.
+    pub score: i32,
+    pub depth: i32,
+    pub bound: Bound,
+    generation: u8,
+}
+
+impl Entry {
+    /// The score if it settles a search of the position to `depth` plies between `alpha`
+    /// and `beta`.
+    pub fn cutoff(&self, depth: i32, alpha: i32, beta: i32) -> Option<i32> {
+        let settles = match self.bound {
+            Bound::Exact => true,
+            Bound::Lower => self.score >= beta,
+            Bound::Upper => self.score <= alpha,
+        };
+        (self.depth >= depth && settles).then_some(self.score)
+    }
+
+    /// Packs the entry in 52 bits: from and to in 7 bits each, the promotion in 4, the score in
+    /// 16, the depth in 8, the bound in 2 and the generation in 8.
+    fn pack(&self) -> u64 {
+        let (from, to, promotion) = self.best_move.map_or((0, 0, 0), |r#move| {
+            let promotion = r#move.promotion.map_or(0, |piece| {
+                PROMOTIONS.iter().position(|&p| p == piece).unwrap_or(0) + 1
+            });
+            (r#move.from, r#move.to, promotion)
+        });
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        from as u64
+            | (to as u64) << 7
+            | (promotion as u64) << 14
+            | (self.score as i16 as u16 as u64) << 18
+            | (self.depth.clamp(0, u8::MAX as i32) as u64) << 34
+            | bound << 42
+            | (self.generation as u64) << 44
+    }
+
+    fn unpack(data: u64) -> Self {
+        let from = (data & 0x7f) as usize;
+        let to = (data >> 7 & 0x7f) as usize;
+        let promotion = (data >> 14 & 0xf) as usize;
+        Entry {
+            best_move: (from != to).then(|| {
+                Move::new(
+                    from,
+                    to,
+                    promotion.checked_sub(1).map(|index| PROMOTIONS[index]),
+                )
+            }),
+            score: (data >> 18 & 0xffff) as u16 as i16 as i32,
+            depth: (data >> 34 & 0xff) as i32,
+            bound: match data >> 42 & 0x3 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            generation: (data >> 44 & 0xff) as u8,
+        }
+    }
+}
+
+/// A slot of the table. The key is stored xor-ed with the data so that a slot written by two
+/// searches at once reads as empty instead of mixing their entries.
+#[derive(Debug, Default)]
+struct Slot {
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A fixed-size hash table of searched positions keyed by their Zobrist key, shared by the
+/// copies of the engine. A slot is taken by a new entry unless it holds another position
+/// searched deeper during the same search.
+#[derive(Debug)]
+pub struct TranspositionTable {
+    size_mb: usize,
+    /// Allocated by the first search, so engines that never search cost nothing.
+    slots: OnceLock<Box<[Slot]>>,
+    generation: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> Self {
+        Self {
+            size_mb: size_mb.clamp(1, MAX_HASH_MB),
+            slots: OnceLock::new(),
+            generation: AtomicU8::new(0),
+        }
+    }
+
+    pub fn size_mb(&self) -> usize {
+        self.size_mb
+    }
+
+    fn slots(&self) -> &[Slot] {
+        self.slots.get_or_init(|| {
+            let count = self.size_mb * 1024 * 1024 / std::mem::size_of::<Slot>();
+            (0..count).map(|_| Slot::default()).collect()
+        })
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        let slots = self.slots();
+        &slots[(key % slots.len() as u64) as usize]
+    }
+
+    /// Marks the entries stored until now as older than those of the search starting.
+    pub fn new_search(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Empties the table.
+    pub fn clear(&self) {
+        if let Some(slots) = self.slots.get() {
+            for slot in slots.iter() {
+                slot.check.store(0, Ordering::Relaxed);
+                slot.data.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// The entry of the position with `key` found `ply` plies from the root, if any.
+    pub fn probe(&self, key: u64, ply: usize) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if data == 0 || slot.check.load(Ordering::Relaxed) ^ data != key {
+            return None;
+        }
+        let mut entry = Entry::unpack(data);
+        entry.score = score_from_table(entry.score, ply);
+        Some(entry)
+    }
+
+    /// Stores what a search of the position with `key`, `ply` plies from the root, found.
+    pub fn store(
+        &self,
+        key: u64,
+        ply: usize,
+        depth: i32,
+        score: i32,
+        bound: Bound,
+        best_move: Option<Move>,
+    ) {
+        let slot = self.slot(key);
+        let generation = self.generation.load(Ordering::Relaxed);
+        let data = slot.data.load(Ordering::Relaxed);
+        let stored_key = slot.check.load(Ordering::Relaxed) ^ data;
+        let stored = (data != 0).then(|| Entry::unpack(data));
+        if let Some(stored) = stored {
+            if stored_key != key && stored.generation == generation && stored.depth > depth {
+                return;
+            }
+        }
+        let entry = Entry {
+            best_move: best_move.or_else(|| {
+                stored
+                    .filter(|_| stored_key == key)
+                    .and_then(|stored| stored.best_move)
+            }),
+            score: score_to_table(score, ply),
+            depth,
+            bound,
+            generation,
+        };
+        let data = entry.pack();
+        slot.check.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// Mates are stored counted from the position rather than from the root of the search.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_BOUND {
+        score + ply as i32
+    } else if score < -MATE_BOUND {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_BOUND {
+        score - ply as i32
+    } else if score < -MATE_BOUND {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{INFINITY, MATE};
+
+    #[test]
+    fn test_transposition_table() {
+        let table = TranspositionTable::new(1);
+        assert_eq!(table.slots().len(), 65536);
+        let r#move = Move::new(0x64, 0x74, Some(Piece::BlackKnight));
+        table.store(42, 3, 5, MATE - 7, Bound::Lower, Some(r#move));
+        let entry = table.probe(42, 1).unwrap();
+        assert_eq!(entry.best_move, Some(r#move));
+        assert_eq!((entry.score, entry.depth), (MATE - 5, 5));
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.cutoff(5, 0, 100), Some(MATE - 5));
+        assert_eq!(entry.cutoff(6, 0, 100), None);
+        assert_eq!(table.probe(43, 1), None);
+
+        // a shallower entry for another position does not replace a deeper one of this search
+        let other = 42 + table.slots().len() as u64;
+        table.store(other, 0, 2, -35, Bound::Upper, None);
+        assert_eq!(table.probe(other, 0), None);
+        table.new_search();
+        table.store(other, 0, 2, -35, Bound::Upper, None);
+        let entry = table.probe(other, 0).unwrap();
+        assert_eq!((entry.score, entry.bound), (-35, Bound::Upper));
+        assert_eq!(entry.cutoff(2, -50, 50), None);
+        assert_eq!(entry.cutoff(2, -30, 0), Some(-35));
+
+        // the same position keeps its move when stored again without one
+        table.store(other, 0, 3, -MATE + 4, Bound::Exact, Some(r#move));
+        table.store(other, 0, 4, 10, Bound::Exact, None);
+        assert_eq!(table.probe(other, 0).unwrap().best_move, Some(r#move));
+        assert_eq!(
+            table.probe(other, 0).unwrap().cutoff(1, -INFINITY, 0),
+            Some(10)
+        );
+
+        table.clear();
+        assert_eq!(table.probe(other, 0), None);
+        assert_eq!(TranspositionTable::new(0).size_mb(), 1);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::board::Board;
+use crate::chess_move::Move;
+use crate::piece::{Piece, PieceTrait};
+use crate::ChessEngine;
+
+/// The piece of colour `white` attacking `pos` with the lowest value.
+fn least_valuable_attacker(board: &Board, pos: usize, white: bool) -> Option<usize> {
+    board
+        .attackers(pos, white)
+        .into_iter()
+        .min_by_key(|&from| board[from].value())
+}
+
+/// The material the side playing `r#move` wins, in centipawns, when both sides keep capturing on
+/// its target square with their least valuable attacker for as long as it pays. Sliders lined up
+/// behind a capturer join in once it has left, pins are not taken into account.
+pub fn static_exchange(board: &Board, r#move: Move) -> i32 {
+    let mut board = *board;
+    let to = r#move.to;
+    let mut from = r#move.from;
+    let mut piece = board[from];
+    let mut promotion = r#move.promotion;
+    let mut captured = board[to].value();
+    if piece.is_pawn() && board[to].is_empty() && from % 16 != to % 16 {
+        // en passant, the captured pawn stands next to the one taking it
+        let passed = if piece.is_white() { to - 16 } else { to + 16 };
+        captured = board.remove_piece(passed).value();
+    }
+
+    let mut gains: Vec<i32> = Vec::new();
+    loop {
+        let placed = promotion.unwrap_or(piece);
+        let gain = captured + placed.value() - piece.value();
+        gains.push(gain - gains.last().copied().unwrap_or(0));
+        board.remove_piece(from);
+        board.put_piece(to, placed);
+        captured = placed.value();
+
+        let white = !placed.is_white();
+        let Some(next) = least_valuable_attacker(&board, to, white) else {
+            break;
+        };
+        // a king only takes back what nothing defends any more
+        if board[next].is_king() && !board.attackers(to, !white).is_empty() {
+            break;
+        }
+        from = next;
+        piece = board[next];
+        promotion = (piece.is_pawn() && matches!(to >> 4, 0 | 7)).then_some(if white {
+            Piece::WhiteQueen
+        } else {
+            Piece::BlackQueen
+        });
+    }
+
+    // each side may stop capturing when going on would lose more
+    while gains.len() > 1 {
+        let last = gains.pop().unwrap();
+        let previous = gains.last_mut().unwrap();
+        *previous = -(-*previous).max(last);
+    }
+    gains[0]
+}
+
+impl ChessEngine {
+    /// The static exchange evaluation of a capture or quiet move, in centipawns for the side playing it.
+    pub(crate) fn see(&self, r#move: Move) -> i32 {
+        static_exchange(&self.board, r#move)
+    }
+
+    /// The material the side to move wins by starting the exchange on `pos` with its least
+    /// valuable attacker, 0 when it has no piece of the opponent to take there.
+    pub(crate) fn exchange_value(&self, pos: usize) -> i32 {
+        let target = self.board[pos];
+        if target.is_empty() || target.is_white() == self.is_white_turn {
+            return 0;
+        }
+        least_valuable_attacker(&self.board, pos, self.is_white_turn)
+            .filter(|&from| {
+                !self.board[from].is_king()
+                    || self.board.attackers(pos, !self.is_white_turn).is_empty()
+            })
+            .map(|from| {
+                let promotion =
+                    (self.board[from].is_pawn() && matches!(pos >> 4, 0 | 7)).then(|| {
+                        self.parse_promotion("q")
+                            .expect("A queen is a valid promotion")
+                    });
+                self.see(Move::new(from, pos, promotion))
+            })
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_see() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, Move, i32)> = vec![
+            // a pawn takes a knight defended by a pawn
+            (
+                "4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1",
+                Move::new(52, 67, None),
+                200,
+            ),
+            // a rook takes a pawn defended by a rook
+            (
+                "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1",
+                Move::new(4, 68, None),
+                100,
+            ),
+            // a knight takes a pawn, losing the knight to the rook behind the bishop
+            (
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                Move::new(35, 68, None),
+                -200,
+            ),
+            // a queen takes a defended pawn
+            (
+                "4k3/3p4/4p3/8/8/8/8/4QK2 w - - 0 1",
+                Move::new(4, 84, None),
+                -800,
+            ),
+            // the rook behind the queen wins the exchange on e6
+            (
+                "4k3/8/4r3/8/8/4Q3/4R3/4K3 w - - 0 1",
+                Move::new(36, 84, None),
+                500,
+            ),
+            // a quiet move to an attacked square
+            (
+                "4k3/8/3p4/8/1N6/8/8/4K3 w - - 0 1",
+                Move::new(49, 66, None),
+                -300,
+            ),
+            // en passant
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                Move::new(68, 83, None),
+                100,
+            ),
+            // a promotion capture
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                Move::new(98, 115, Some(Piece::WhiteQueen)),
+                400,
+            ),
+        ];
+        for (fen, r#move, value) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(chess_position.see(r#move), value, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_exchange_value() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, usize, i32)> = vec![
+            ("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1", 67, 200),
+            ("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1", 84, 0),
+            ("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1", 52, 0),
+            ("4k3/8/8/8/8/8/4p3/4K3 w - - 0 1", 20, 100),
+            ("4k3/8/8/8/8/8/3qp3/4K3 w - - 0 1", 20, 0),
+        ];
+        for (fen, pos, value) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(chess_position.exchange_value(pos), value, "{fen} {pos}");
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use crate::fen_trait::FenParser;
+use crate::piece::{Piece, PieceTrait};
+use crate::search::{Score, SearchLimits};
+use crate::{ChessEngine, FEN_STARTING_POSITION};
+
+/// The bytes of a position in the binary format, see [`TrainingPosition::to_bytes`].
+pub const RECORD_SIZE: usize = 32;
+
+/// How the engine plays against itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfPlayConfig {
+    /// The plies searched for each move, and the positions too if set.
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// The random moves the game opens with, so no two games are alike.
+    pub random_plies: u32,
+    /// The plies after which a game still going is scored as a draw.
+    pub max_plies: u32,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        Self {
+            depth: Some(4),
+            nodes: None,
+            random_plies: 8,
+            max_plies: 400,
+        }
+    }
+}
+
+/// A quiet position of a self-play game, with the search score and the game's result, both
+/// from white's point of view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingPosition {
+    pub fen: String,
+    /// Centipawns.
+    pub score: i16,
+    /// 1 for a white win, 0.5 for a draw and 0 for a black win.
+    pub result: f64,
+}
+
+impl TrainingPosition {
+    /// The plain-text format, "FEN | score | result", e.g.
+    /// "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 | 153 | 1.0".
+    pub fn to_text(&self) -> String {
+        format!("{} | {} | {:.1}", self.fen, self.score, self.result)
+    }
+
+    pub fn from_text(line: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid Training Position \"{line}\"");
+        let mut parts = line.split('|').map(str::trim);
+        let (Some(fen), Some(score), Some(result), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        Ok(Self {
+            fen: fen.to_string(),
+            score: score.parse().map_err(|_| invalid())?,
+            result: parse_result(result).ok_or_else(invalid)?,
+        })
+    }
+
+    /// The binary format, 32 little-endian bytes:
+    /// - 0..8, the occupied squares, bit 0 being a1 and bit 63 h8,
+    /// - 8..24, a nibble for the piece of each occupied square in turn, low nibble first:
+    ///   0 to 5 for white pawn, knight, bishop, rook, queen and king, 6 to 11 for black's,
+    /// - 24, bit 0 set when black is to move, bits 1 to 4 the castling rights KQkq,
+    /// - 25, the en passant square from 0 to 63, 64 for none,
+    /// - 26, the halfmove clock, 27..29 the fullmove number,
+    /// - 29..31, the score, 31 the result: 2 for a white win, 1 for a draw, 0 for a black win.
+    pub fn to_bytes(&self) -> Result<[u8; RECORD_SIZE], String> {
+        let invalid = || format!("Invalid FEN \"{}\"", self.fen);
+        let fields: Vec<&str> = self.fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(invalid());
+        }
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(invalid());
+        }
+        let mut squares = [None; 64];
+        for (row, rank) in ranks.iter().enumerate() {
+            let mut file = 0;
+            for ch in rank.chars() {
+                if let Some(empty) = ch.to_digit(10) {
+                    file += empty as usize;
+                    continue;
+                }
+                let piece = Piece::from_char(ch).map_err(|_| invalid())?;
+                if piece.is_empty() || file > 7 {
+                    return Err(invalid());
+                }
+                squares[(7 - row) * 8 + file] = Some(piece);
+                file += 1;
+            }
+            if file != 8 {
+                return Err(invalid());
+            }
+        }
+
+        let mut bytes = [0; RECORD_SIZE];
+        let mut occupancy = 0u64;
+        let pieces = squares
+            .iter()
+            .enumerate()
+            .filter_map(|(square, piece)| Some((square, (*piece)?)));
+        for (index, (square, piece)) in pieces.enumerate() {
+            if index == 32 {
+                return Err(invalid());
+            }
+            occupancy |= 1 << square;
+            bytes[8 + index / 2] |= (piece as u8) << (4 * (index % 2));
+        }
+        bytes[0..8].copy_from_slice(&occupancy.to_le_bytes());
+
+        let mut flags = match fields[1] {
+            "w" => 0,
+            "b" => 1,
+            _ => return Err(invalid()),
+        };
+        for ch in fields[2].chars().filter(|&ch| ch != '-') {
+            let bit = "KQkq".find(ch).ok_or_else(invalid)?;
+            flags |= 2 << bit;
+        }
+        bytes[24] = flags;
+        bytes[25] = match fields[3] {
+            "-" => 64,
+            square => {
+                let square = crate::algebric_notation::from_string(square)? as usize;
+                ((square >> 4) * 8 + (square & 7)) as u8
+            }
+        };
+        bytes[26] = fields[4].parse().map_err(|_| invalid())?;
+        let full_move: u16 = fields[5].parse().map_err(|_| invalid())?;
+        bytes[27..29].copy_from_slice(&full_move.to_le_bytes());
+        bytes[29..31].copy_from_slice(&self.score.to_le_bytes());
+        bytes[31] = (self.result * 2.0).round() as u8;
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != RECORD_SIZE {
+            return Err(format!("Invalid Training Record Size \"{}\"", bytes.len()));
+        }
+        let occupancy = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let mut board = [None; 64];
+        let mut index = 0;
+        for (square, piece) in board.iter_mut().enumerate() {
+            if occupancy & (1 << square) != 0 {
+                if index == 32 {
+                    return Err("Invalid Training Record Occupancy".to_string());
+                }
+                let nibble = (bytes[8 + index / 2] >> (4 * (index % 2))) & 0xF;
+                *piece = Some(char::from(
+                    PIECES
+                        .get(nibble as usize)
+                        .copied()
+                        .ok_or_else(|| format!("Invalid Training Record Piece \"{nibble}\""))?,
+                ));
+                index += 1;
+            }
+        }
+
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match board[rank * 8 + file] {
+                    Some(ch) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(ch);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+        fen.push_str(if bytes[24] & 1 == 0 { " w " } else { " b " });
+        let castling: String = "KQkq"
+            .chars()
+            .enumerate()
+            .filter(|(bit, _)| bytes[24] & (2 << bit) != 0)
+            .map(|(_, ch)| ch)
+            .collect();
+        fen.push_str(if castling.is_empty() { "-" } else { &castling });
+        fen.push(' ');
+        match bytes[25] {
+            64 => fen.push('-'),
+            square if square < 64 => fen.push_str(&crate::algebric_notation::to_string(
+                (square / 8) * 16 + square % 8,
+            )),
+            square => return Err(format!("Invalid Training Record Square \"{square}\"")),
+        }
+        let full_move = u16::from_le_bytes([bytes[27], bytes[28]]);
+        fen.push_str(&format!(" {} {full_move}", bytes[26]));
+        Ok(Self {
+            fen,
+            score: i16::from_le_bytes([bytes[29], bytes[30]]),
+            result: match bytes[31] {
+                0..=2 => bytes[31] as f64 / 2.0,
+                result => return Err(format!("Invalid Training Record Result \"{result}\"")),
+            },
+        })
+    }
+}
+
+/// A game result from white's side, as a number or in PGN notation.
+pub(crate) fn parse_result(result: &str) -> Option<f64> {
+    match result {
+        "1" | "1.0" | "1-0" => Some(1.0),
+        "0.5" | "1/2-1/2" => Some(0.5),
+        "0" | "0.0" | "0-1" => Some(0.0),
+        _ => None,
+    }
+}
+
+/// The pieces of the binary format, by nibble.
+const PIECES: [Piece; 12] = [
+    Piece::WhitePawn,
+    Piece::WhiteKnight,
+    Piece::WhiteBishop,
+    Piece::WhiteRook,
+    Piece::WhiteQueen,
+    Piece::WhiteKing,
+    Piece::BlackPawn,
+    Piece::BlackKnight,
+    Piece::BlackBishop,
+    Piece::BlackRook,
+    Piece::BlackQueen,
+    Piece::BlackKing,
+];
+
+impl ChessEngine {
+    /// Plays a game against itself from the starting position opened with random moves,
+    /// searching each move within `config`, and returns its quiet positions: those not in
+    /// check, without a capture to play and not with a mate found. Games of repeated
+    /// positions, the fifty-move rule, material too short to mate or over `max_plies` are
+    /// drawn. Random choices follow `setSeed`.
+    pub fn self_play_game(&mut self, config: SelfPlayConfig) -> Vec<TrainingPosition> {
+        self.parse_fen(FEN_STARTING_POSITION)
+            .expect("the starting position");
+        for _ in 0..config.random_plies {
+            let moves = self.generate_moves();
+            if moves.is_empty() {
+                return Vec::new();
+            }
+            let index = self.random.below(moves.len() as u64) as usize;
+            self.make(moves[index]);
+        }
+
+        let limits = SearchLimits {
+            depth: config.depth,
+            nodes: config.nodes,
+            ..Default::default()
+        };
+        let mut keys = Vec::new();
+        let mut positions: Vec<(String, i16)> = Vec::new();
+        let mut plies = 0;
+        let result = loop {
+            let key = self.polyglot_key();
+            keys.push(key);
+            let moves = self.generate_moves();
+            let check = self.board.is_check(self.is_white_turn);
+            if moves.is_empty() {
+                let white_won = !self.is_white_turn;
+                break if !check {
+                    0.5
+                } else if white_won {
+                    1.0
+                } else {
+                    0.0
+                };
+            }
+            if self.half_move_clock >= 100
+                || self.board.is_insufficient_material()
+                || keys.iter().filter(|&&seen| seen == key).count() >= 3
+                || plies >= config.max_plies
+            {
+                break 0.5;
+            }
+
+            let search = self.search_threads(limits);
+            let Some(line) = search.lines().first() else {
+                break 0.5;
+            };
+            let Some(r#move) = line.best_move else {
+                break 0.5;
+            };
+            let quiet = !check && !moves.iter().any(|&r#move| self.is_capture(r#move));
+            if let (true, Score::Cp(score)) = (quiet, Score::from_internal(line.score)) {
+                let score = if self.is_white_turn { score } else { -score };
+                let score = score.clamp(i16::MIN as i32, i16::MAX as i32) as i16;
+                positions.push((self.to_fen(), score));
+            }
+            self.make(r#move);
+            plies += 1;
+        };
+        positions
+            .into_iter()
+            .map(|(fen, score)| TrainingPosition { fen, score, result })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_training_position() {
+        let arr: Vec<(&str, i16, f64)> = vec![
+            (FEN_STARTING_POSITION, 25, 0.5),
+            (
+                "r3k2r/pppq1ppp/2n2n2/3pp3/1b1PP3/2N2N2/PPPQ1PPP/R3K2R b Kq - 5 12",
+                -140,
+                0.0,
+            ),
+            ("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 40", 310, 1.0),
+        ];
+        for (fen, score, result) in arr {
+            let position = TrainingPosition {
+                fen: fen.to_string(),
+                score,
+                result,
+            };
+            let bytes = position.to_bytes().unwrap();
+            assert_eq!(TrainingPosition::from_bytes(&bytes), Ok(position.clone()));
+            let text = position.to_text();
+            assert_eq!(TrainingPosition::from_text(&text), Ok(position));
+        }
+        assert_eq!(
+            TrainingPosition::from_text("8/8/8/8/8/8/8/8 w - - 0 1 | 10 | 1.0").unwrap(),
+            TrainingPosition {
+                fen: "8/8/8/8/8/8/8/8 w - - 0 1".to_string(),
+                score: 10,
+                result: 1.0
+            }
+        );
+        assert!(TrainingPosition::from_text("8/8/8/8/8/8/8/8 w - - 0 1 | 10").is_err());
+        assert!(TrainingPosition::from_text("8/8/8/8/8/8/8/8 w - - 0 1 | ten | 1.0").is_err());
+        assert!(TrainingPosition::from_bytes(&[0; 31]).is_err());
+
+        let invalid = TrainingPosition {
+            fen: "8/8/8/8/8/8/8/9 w - - 0 1".to_string(),
+            score: 0,
+            result: 0.5,
+        };
+        assert!(invalid.to_bytes().is_err());
+    }
+
+    #[test]
+    fn test_self_play_game() {
+        let mut chess_position = ChessEngine::new();
+        let config = SelfPlayConfig {
+            depth: Some(1),
+            max_plies: 40,
+            ..Default::default()
+        };
+        chess_position.set_seed(4);
+        let positions = chess_position.self_play_game(config);
+        assert!(!positions.is_empty());
+        let result = positions[0].result;
+        for position in &positions {
+            assert_eq!(position.result, result);
+            let mut engine = ChessEngine::new();
+            engine.parse_fen(&position.fen).unwrap();
+            assert!(
+                !engine.board.is_check(engine.is_white_turn),
+                "{}",
+                position.fen
+            );
+            let moves = engine.generate_moves();
+            assert!(!moves.iter().any(|&r#move| engine.is_capture(r#move)));
+            let bytes = position.to_bytes().unwrap();
+            assert_eq!(TrainingPosition::from_bytes(&bytes).as_ref(), Ok(position));
+        }
+
+        chess_position.clear_hash();
+        chess_position.set_seed(4);
+        assert_eq!(chess_position.self_play_game(config), positions);
+    }
+}
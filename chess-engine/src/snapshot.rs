@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// A complete, serialisable view of the position, so the UI can render from a single call.
+/// Squares are given in algebraic notation and pieces as FEN letters.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Snapshot {
+    pub pieces: BTreeMap<String, char>,
+    /// The active player, "w" or "b".
+    pub turn: char,
+    pub castling: CastlingRights,
+    pub en_passant: Option<String>,
+    pub half_move_clock: u8,
+    pub full_move_number: u16,
+    pub last_move: Option<MoveSnapshot>,
+    /// The square of the king in check, if any.
+    pub check: Option<String>,
+    pub status: String,
+    pub legal_moves: Vec<MoveSnapshot>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MoveSnapshot {
+    pub from: String,
+    pub to: String,
+}
@@ -0,0 +1,222 @@
+use crate::chess_move::Move;
+use crate::mcts::Player;
+use crate::random::Random;
+use crate::search::{SearchLimits, SearchResult};
+use crate::ChessEngine;
+
+/// The level of full strength.
+pub const MAX_SKILL_LEVEL: u8 = 20;
+/// The range of `UCI_Elo`, mapped linearly onto the skill levels.
+pub const MIN_ELO: u32 = 600;
+pub const MAX_ELO: u32 = 2400;
+/// The lines a weakened engine chooses among.
+const WEAK_LINES: usize = 4;
+/// How much worse than the best line, per level below full strength, a line can be and still
+/// be chosen.
+const MARGIN_PER_LEVEL: f64 = 10.0;
+
+/// How well the engine plays: a skill level from 0 to 20 or, when strength is limited,
+/// an approximate Elo rating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Strength {
+    pub skill_level: u8,
+    /// Whether `elo` sets the level instead of `skill_level`.
+    pub limit_strength: bool,
+    pub elo: u32,
+}
+
+impl Default for Strength {
+    fn default() -> Self {
+        Self {
+            skill_level: MAX_SKILL_LEVEL,
+            limit_strength: false,
+            elo: 1500,
+        }
+    }
+}
+
+impl Strength {
+    /// The skill level played at, fractional when set from an Elo rating.
+    pub fn level(&self) -> f64 {
+        if self.limit_strength {
+            let elo = self.elo.clamp(MIN_ELO, MAX_ELO) - MIN_ELO;
+            elo as f64 * MAX_SKILL_LEVEL as f64 / (MAX_ELO - MIN_ELO) as f64
+        } else {
+            self.skill_level.min(MAX_SKILL_LEVEL) as f64
+        }
+    }
+
+    /// The search of a move: deeper with the level, and below full strength limited in
+    /// positions and looking at a few lines to choose from.
+    pub fn limits(&self) -> SearchLimits {
+        let level = self.level();
+        if level >= MAX_SKILL_LEVEL as f64 {
+            return SearchLimits {
+                depth: Some(6),
+                ..Default::default()
+            };
+        }
+        SearchLimits {
+            depth: Some(1 + (level / 4.0) as u32),
+            nodes: Some(200 << (level as u32 / 2)),
+            multi_pv: WEAK_LINES,
+            ..Default::default()
+        }
+    }
+
+    /// Chooses the move of one of `lines`, best first: the best one at full strength, else the
+    /// one whose score is the highest once each gets a random bonus. The bonus reaches 10
+    /// centipawns per level below full strength, so weaker levels settle for worse lines more
+    /// often while lines much worse than the best are never played.
+    pub fn pick(&self, lines: &[SearchResult], random: &mut Random) -> Option<Move> {
+        let top = lines.first()?;
+        let level = self.level();
+        if level >= MAX_SKILL_LEVEL as f64 {
+            return top.best_move;
+        }
+        let margin = ((MAX_SKILL_LEVEL as f64 - level) * MARGIN_PER_LEVEL) as u64;
+        lines
+            .iter()
+            .filter(|line| line.best_move.is_some())
+            .max_by_key(|line| line.score as i64 + random.below(margin + 1) as i64)
+            .and_then(|line| line.best_move)
+    }
+}
+
+impl ChessEngine {
+    /// The move the engine plays: that of the tree search for the MCTS player, else at full
+    /// strength that of the distance to mate tables or the Syzygy tables when they cover the
+    /// position, else that of the alpha-beta search at the engine's strength, see [`Strength`].
+    pub(crate) fn engine_move(&mut self) -> Option<Move> {
+        let full_strength = self.strength.level() >= MAX_SKILL_LEVEL as f64;
+        if full_strength && self.player != Player::Mcts {
+            if let Some(r#move) = self.endgame_move().or_else(|| self.tablebase_move()) {
+                return Some(r#move);
+            }
+        }
+        if self.player == Player::Mcts {
+            return self.mcts_move();
+        }
+        let search = self.search_threads(self.strength.limits());
+        self.strength.pick(search.lines(), &mut self.random)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::endgame::Endgame;
+    use crate::fen_trait::FenParser;
+
+    #[test]
+    fn test_strength() {
+        let arr: Vec<(u8, bool, u32, f64, u32)> = vec![
+            (20, false, 600, 20.0, 6),
+            (0, false, 2400, 0.0, 1),
+            (9, false, 1500, 9.0, 3),
+            (20, true, 600, 0.0, 1),
+            (0, true, 1500, 10.0, 3),
+            (0, true, 3000, 20.0, 6),
+        ];
+        for (skill_level, limit_strength, elo, level, depth) in arr {
+            let strength = Strength {
+                skill_level,
+                limit_strength,
+                elo,
+            };
+            assert_eq!(strength.level(), level);
+            assert_eq!(strength.limits().depth, Some(depth));
+            assert_eq!(strength.limits().nodes.is_none(), level == 20.0);
+        }
+    }
+
+    #[test]
+    fn test_pick() {
+        let line = |uci: &str, score: i32| SearchResult {
+            best_move: Some(Move::new(
+                crate::algebric_notation::from_string(&uci[..2])
+                    .unwrap()
+                    .into(),
+                crate::algebric_notation::from_string(&uci[2..])
+                    .unwrap()
+                    .into(),
+                None,
+            )),
+            score,
+            ..Default::default()
+        };
+        let lines = vec![
+            line("e2e4", 30),
+            line("d2d4", 25),
+            line("g1f3", 10),
+            line("a2a4", -400),
+        ];
+        let mut random = Random::new(3);
+        let full = Strength::default();
+        assert_eq!(full.pick(&lines, &mut random), lines[0].best_move);
+        assert_eq!(full.pick(&[], &mut random), None);
+
+        let beginner = Strength {
+            skill_level: 0,
+            ..Default::default()
+        };
+        let picks: Vec<Option<Move>> = (0..50)
+            .map(|_| beginner.pick(&lines, &mut random))
+            .collect();
+        assert!(picks.contains(&lines[0].best_move));
+        assert!(picks.iter().any(|&pick| pick != lines[0].best_move));
+        assert!(!picks.contains(&lines[3].best_move));
+
+        let mut again = Random::new(3);
+        full.pick(&lines, &mut again);
+        let replay: Vec<Option<Move>> =
+            (0..50).map(|_| beginner.pick(&lines, &mut again)).collect();
+        assert_eq!(picks, replay);
+    }
+
+    #[test]
+    fn test_engine_move() {
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1")
+            .unwrap();
+        chess_position.set_option("Skill Level", "5").unwrap();
+        chess_position.clear_hash();
+        chess_position.random = Random::new(11);
+        let r#move = chess_position.engine_move().unwrap();
+        chess_position.clear_hash();
+        chess_position.random = Random::new(11);
+        assert_eq!(chess_position.engine_move(), Some(r#move));
+
+        chess_position.set_option("Skill Level", "20").unwrap();
+        assert_eq!(
+            chess_position.engine_move().map(|r#move| r#move.to_uci()),
+            Some("a1a8".to_string())
+        );
+        chess_position
+            .parse_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.engine_move(), None);
+    }
+
+    #[test]
+    fn test_table_move_at_full_strength() {
+        while Endgame::Kqk.build(&mut 1_000_000) {}
+        let mut chess_position = ChessEngine::new();
+        chess_position
+            .parse_fen("8/8/2k5/8/8/8/8/K2Q4 w - - 0 1")
+            .unwrap();
+        let table_move = chess_position.endgame_move();
+        assert!(table_move.is_some());
+        assert_eq!(chess_position.engine_move(), table_move);
+
+        // a weakened engine searches, and so does the MCTS player
+        chess_position.set_option("Skill Level", "0").unwrap();
+        let moves: Vec<Option<Move>> = (0..10).map(|_| chess_position.engine_move()).collect();
+        assert!(moves.iter().any(|&r#move| r#move != table_move));
+        chess_position.set_option("Skill Level", "20").unwrap();
+        chess_position.player = Player::Mcts;
+        let mut mcts = chess_position.clone();
+        assert_eq!(chess_position.engine_move(), mcts.mcts_move());
+    }
+}
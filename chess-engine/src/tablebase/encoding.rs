@@ -0,0 +1,162 @@
+use std::sync::OnceLock;
+
+/// Squares here are numbered like the tablebase generator does: a1 = 0, b1 = 1, ..., h8 = 63.
+pub fn rank_of(square: usize) -> usize {
+    square >> 3
+}
+
+pub fn file_of(square: usize) -> usize {
+    square & 7
+}
+
+/// Positive above the a1-h8 diagonal, negative below it and zero on it.
+pub fn off_diagonal(square: usize) -> i32 {
+    rank_of(square) as i32 - file_of(square) as i32
+}
+
+/// The lookup tables used to turn a position into an index of a Syzygy table.
+pub struct Encoding {
+    /// Squares a2-h7 numbered so that the leading pawn has the highest value.
+    pub map_pawns: [usize; 64],
+    /// Squares below the a1-h8 diagonal numbered 0..28.
+    pub map_b1h1h7: [usize; 64],
+    /// Squares of the a1-d1-d4 triangle numbered 0..10, the diagonal last.
+    pub map_a1d1d4: [usize; 64],
+    /// The 462 placements of two kings with the first one in the a1-d1-d4 triangle.
+    pub map_kk: [[usize; 64]; 10],
+    /// `binomial[k][n]` ways to choose `k` of `n` squares.
+    pub binomial: [[u64; 64]; 7],
+    pub lead_pawn_idx: [[u64; 64]; 6],
+    pub lead_pawns_size: [[u64; 4]; 6],
+}
+
+impl Encoding {
+    pub fn get() -> &'static Encoding {
+        static ENCODING: OnceLock<Encoding> = OnceLock::new();
+        ENCODING.get_or_init(Encoding::new)
+    }
+
+    fn new() -> Self {
+        let mut encoding = Encoding {
+            map_pawns: [0; 64],
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 7],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64 {
+            if off_diagonal(square) < 0 {
+                encoding.map_b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        let mut diagonal = Vec::new();
+        code = 0;
+        for square in 0..=27 {
+            if off_diagonal(square) < 0 && file_of(square) <= 3 {
+                encoding.map_a1d1d4[square] = code;
+                code += 1;
+            } else if off_diagonal(square) == 0 && file_of(square) <= 3 {
+                diagonal.push(square);
+            }
+        }
+        for square in diagonal {
+            encoding.map_a1d1d4[square] = code;
+            code += 1;
+        }
+
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10 {
+            for first in 0..=27 {
+                // b1 is the only square of the triangle mapped to 0
+                if encoding.map_a1d1d4[first] != idx || (idx == 0 && first != 1) {
+                    continue;
+                }
+                for second in 0..64 {
+                    let touching = file_of(first).abs_diff(file_of(second)) <= 1
+                        && rank_of(first).abs_diff(rank_of(second)) <= 1;
+                    if touching || (off_diagonal(first) == 0 && off_diagonal(second) > 0) {
+                        continue;
+                    }
+                    if off_diagonal(first) == 0 && off_diagonal(second) == 0 {
+                        both_on_diagonal.push((idx, second));
+                    } else {
+                        encoding.map_kk[idx][second] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, second) in both_on_diagonal {
+            encoding.map_kk[idx][second] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..7.min(n + 1) {
+                encoding.binomial[k][n] = if k > 0 {
+                    encoding.binomial[k - 1][n - 1]
+                } else {
+                    0
+                } + if k < n {
+                    encoding.binomial[k][n - 1]
+                } else {
+                    0
+                };
+            }
+        }
+
+        let mut available = 47;
+        for lead_pawns in 1..=5 {
+            for file in 0..4 {
+                let mut idx = 0;
+                for rank in 1..=6 {
+                    let square = rank * 8 + file;
+                    if lead_pawns == 1 {
+                        encoding.map_pawns[square] = available;
+                        encoding.map_pawns[square ^ 7] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    encoding.lead_pawn_idx[lead_pawns][square] = idx;
+                    idx += encoding.binomial[lead_pawns - 1][encoding.map_pawns[square]];
+                }
+                encoding.lead_pawns_size[lead_pawns][file] = idx;
+            }
+        }
+        encoding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_tables() {
+        let encoding = Encoding::get();
+        let kings: Vec<usize> = encoding
+            .map_kk
+            .iter()
+            .flat_map(|row| row.iter().copied())
+            .filter(|&code| code > 0)
+            .collect();
+        // 462 placements, code 0 being the only zero
+        assert_eq!(kings.len(), 461);
+        assert_eq!(kings.iter().max(), Some(&461));
+        assert_eq!(encoding.binomial[2][64 - 1], 63 * 62 / 2);
+        assert_eq!(encoding.binomial[5][48], 1712304);
+        // a2 leads, e7 is the last square any other pawn can be on
+        assert_eq!(encoding.map_pawns[8], 47);
+        assert_eq!(encoding.map_pawns[52], 0);
+        assert_eq!(encoding.lead_pawns_size[1], [6, 6, 6, 6]);
+        assert_eq!(encoding.map_a1d1d4[0], 6);
+        assert_eq!(encoding.map_a1d1d4[27], 9);
+    }
+}
@@ -0,0 +1,513 @@
+mod encoding;
+mod table;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::castling::CastlingTrait;
+use crate::chess_move::Move;
+use crate::piece::{Piece, PieceTrait};
+use crate::utils;
+use crate::ChessEngine;
+use table::{Table, TableKind, TableProbe, TbPosition};
+
+/// The result of a position for the side to move. Cursed wins and blessed losses
+/// are wins and losses that the fifty-move rule turns into draws.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Self {
+        match value {
+            ..=-2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+}
+
+/// Syzygy tables found on disk or handed over as bytes, read on first use.
+#[derive(Debug, Default)]
+pub struct Tablebase {
+    paths: Mutex<HashMap<String, PathBuf>>,
+    tables: Mutex<HashMap<String, Option<Arc<Table>>>>,
+    max_pieces: Mutex<usize>,
+}
+
+impl Tablebase {
+    /// Registers every `.rtbw` and `.rtbz` file of `dir`, returning how many were found.
+    pub fn add_directory(&self, dir: &Path) -> Result<usize, String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|err| format!("Cannot Read Tablebases \"{}\": {err}", dir.display()))?;
+        let mut found = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            if let Some(pieces) = Self::piece_count(file_name) {
+                self.paths
+                    .lock()
+                    .unwrap()
+                    .insert(file_name.to_string(), path.clone());
+                self.tables.lock().unwrap().remove(file_name);
+                let mut max_pieces = self.max_pieces.lock().unwrap();
+                *max_pieces = (*max_pieces).max(pieces);
+                found += 1;
+            }
+        }
+        Ok(found)
+    }
+
+    /// Adds a table from its bytes, `file_name` being e.g. "KQvK.rtbw".
+    pub fn add_table(&self, file_name: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let pieces = Self::piece_count(file_name)
+            .ok_or_else(|| format!("Invalid Tablebase \"{file_name}\""))?;
+        let (name, extension) = file_name.split_once('.').unwrap();
+        let kind = match extension {
+            "rtbw" => TableKind::Wdl,
+            _ => TableKind::Dtz,
+        };
+        let table = Table::new(kind, name, bytes)?;
+        self.tables
+            .lock()
+            .unwrap()
+            .insert(file_name.to_string(), Some(Arc::new(table)));
+        let mut max_pieces = self.max_pieces.lock().unwrap();
+        *max_pieces = (*max_pieces).max(pieces);
+        Ok(())
+    }
+
+    /// The number of pieces of the largest table available.
+    pub fn max_pieces(&self) -> usize {
+        *self.max_pieces.lock().unwrap()
+    }
+
+    fn piece_count(file_name: &str) -> Option<usize> {
+        let (name, extension) = file_name.split_once('.')?;
+        let (white, black) = name.split_once('v')?;
+        let valid =
+            |side: &str| side.starts_with('K') && side.chars().all(|ch| "KQRBNP".contains(ch));
+        (matches!(extension, "rtbw" | "rtbz") && valid(white) && valid(black))
+            .then_some(white.len() + black.len())
+    }
+
+    fn table(&self, name: &str, kind: TableKind) -> Option<Arc<Table>> {
+        let file_name = format!("{name}.{}", kind.extension());
+        if let Some(table) = self.tables.lock().unwrap().get(&file_name) {
+            return table.clone();
+        }
+        let path = self.paths.lock().unwrap().get(&file_name).cloned()?;
+        // a broken file is remembered as missing rather than read again
+        let table = std::fs::read(path)
+            .ok()
+            .and_then(|bytes| Table::new(kind, name, bytes).ok())
+            .map(Arc::new);
+        self.tables.lock().unwrap().insert(file_name, table.clone());
+        table
+    }
+}
+
+/// The piece code used by the tables: pawn 1 to king 6, plus 8 for black.
+fn table_piece(piece: Piece) -> u8 {
+    let code = match piece {
+        _ if piece.is_pawn() => 1,
+        _ if piece.is_knight() => 2,
+        _ if piece.is_bishop() => 3,
+        _ if piece.is_rook() => 4,
+        _ if piece.is_queen() => 5,
+        _ => 6,
+    };
+    if piece.is_white() {
+        code
+    } else {
+        code + 8
+    }
+}
+
+/// The DTZ of the move before a zeroing move: captures and pawn moves are not stored in DTZ tables.
+fn dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+impl ChessEngine {
+    /// Loads the Syzygy tables of a local directory, returning how many files were found.
+    pub fn load_tablebases(&mut self, dir: &str) -> Result<usize, String> {
+        self.tablebase.add_directory(Path::new(dir))
+    }
+
+    fn tablebase_position(&self) -> TbPosition {
+        let mut pieces = Vec::new();
+        for i in 0..64 {
+            let pos = utils::convert_postion_to_0x88(i);
+            let piece = self.board[pos];
+            if !piece.is_empty() {
+                pieces.push(((pos >> 4) * 8 + (pos & 7), table_piece(piece)));
+            }
+        }
+        pieces.sort_by_key(|&(square, _)| square);
+        TbPosition {
+            pieces,
+            white_to_move: self.is_white_turn,
+        }
+    }
+
+    /// Whether the tables can answer for this position at all.
+    fn in_tablebase(&self) -> bool {
+        let pieces = (0..64)
+            .filter(|&i| !self.board[utils::convert_postion_to_0x88(i)].is_empty())
+            .count();
+        pieces <= self.tablebase.max_pieces()
+            && !self.castling.can_white_king_castle()
+            && !self.castling.can_white_queen_castle()
+            && !self.castling.can_black_king_castle()
+            && !self.castling.can_black_queen_castle()
+    }
+
+    fn probe_table(&self, kind: TableKind, wdl: i32) -> Option<TableProbe> {
+        let position = self.tablebase_position();
+        if position.pieces.len() == 2 {
+            return Some(TableProbe::Value(0));
+        }
+        let (white, black) = (position.material(true), position.material(false));
+        if let Some(table) = self.tablebase.table(&format!("{white}v{black}"), kind) {
+            return table.probe(&position, false, wdl);
+        }
+        let table = self.tablebase.table(&format!("{black}v{white}"), kind)?;
+        table.probe(&position, true, wdl)
+    }
+
+    /// Whether a move takes a piece, en passant included.
+    pub(crate) fn is_capture(&self, r#move: Move) -> bool {
+        let piece = self.board[r#move.from];
+        !self.board[r#move.to].is_empty() || (piece.is_pawn() && r#move.from % 16 != r#move.to % 16)
+    }
+
+    /// Tables store "don't care" values where a capture decides the game, so captures
+    /// (and pawn moves for DTZ) are searched first. Returns the WDL value and whether
+    /// the best move found is a zeroing one.
+    fn tablebase_search(&mut self, zeroing_moves: bool) -> Option<(i32, bool)> {
+        let moves = self.generate_moves();
+        let total = moves.len();
+        let mut searched = 0;
+        let mut best = -2;
+        for r#move in moves {
+            let zeroing =
+                self.is_capture(r#move) || (zeroing_moves && self.board[r#move.from].is_pawn());
+            if !zeroing {
+                continue;
+            }
+            searched += 1;
+            let undo = self.make(r#move);
+            let value = self.tablebase_search(false);
+            self.unmake(undo);
+            let value = -value?.0;
+            if value > best {
+                best = value;
+                if value >= 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+
+        let no_more_moves = searched > 0 && searched == total;
+        let value = if no_more_moves {
+            best
+        } else {
+            match self.probe_table(TableKind::Wdl, 0)? {
+                TableProbe::Value(value) => value,
+                TableProbe::WrongSide => return None,
+            }
+        };
+        if best >= value {
+            return Some((best, best > 0 || no_more_moves));
+        }
+        Some((value, false))
+    }
+
+    /// The tablebase result for the side to move, if the tables cover the position.
+    pub(crate) fn probe_wdl(&mut self) -> Option<Wdl> {
+        if !self.in_tablebase() {
+            return None;
+        }
+        self.tablebase_search(false)
+            .map(|(value, _)| Wdl::from_value(value))
+    }
+
+    /// The distance in plies to the next capture or pawn move that keeps the result,
+    /// positive when winning, negative when losing and 0 for draws.
+    pub(crate) fn probe_dtz(&mut self) -> Option<i32> {
+        if !self.in_tablebase() {
+            return None;
+        }
+        self.tablebase_dtz()
+    }
+
+    fn tablebase_dtz(&mut self) -> Option<i32> {
+        let (wdl, zeroing_best) = self.tablebase_search(true)?;
+        if wdl == 0 {
+            return Some(0);
+        }
+        if zeroing_best {
+            return Some(dtz_before_zeroing(wdl));
+        }
+        if let TableProbe::Value(dtz) = self.probe_table(TableKind::Dtz, wdl)? {
+            let rule50 = if wdl.abs() == 1 { 100 } else { 0 };
+            return Some((dtz + rule50) * wdl.signum());
+        }
+
+        // the table stores the other side to move, look one ply ahead
+        let mut min_dtz = i32::MAX;
+        for r#move in self.generate_moves() {
+            let zeroing = self.is_capture(r#move) || self.board[r#move.from].is_pawn();
+            let undo = self.make(r#move);
+            let dtz = if zeroing {
+                self.tablebase_search(false)
+                    .map(|(value, _)| -dtz_before_zeroing(value))
+            } else {
+                self.tablebase_dtz().map(|dtz| -dtz)
+            };
+            let mates = self.board.is_check(self.is_white_turn) && self.legal_moves().is_empty();
+            self.unmake(undo);
+            let mut dtz = dtz?;
+            if dtz == 1 && mates {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        Some(if min_dtz == i32::MAX { -1 } else { min_dtz })
+    }
+}
+
+impl ChessEngine {
+    /// Picks the move keeping the best tablebase result, winning as fast and losing as slowly
+    /// as the fifty-move rule allows.
+    pub(crate) fn tablebase_move(&mut self) -> Option<Move> {
+        if !self.in_tablebase() {
+            return None;
+        }
+        let clock = self.half_move_clock as i32;
+        let mut best: Option<(i32, Move)> = None;
+        for r#move in self.generate_moves() {
+            let undo = self.make(r#move);
+            let dtz = if self.half_move_clock == 0 {
+                self.tablebase_search(false)
+                    .map(|(value, _)| dtz_before_zeroing(-value))
+            } else {
+                self.tablebase_dtz().map(|dtz| -dtz - dtz.signum())
+            };
+            let mates = self.board.is_check(self.is_white_turn) && self.legal_moves().is_empty();
+            self.unmake(undo);
+            let dtz = if mates { 1 } else { dtz? };
+            let rank = match dtz {
+                dtz if dtz > 0 && dtz + clock <= 100 => 10000 - dtz,
+                dtz if dtz > 0 => 1000 - dtz,
+                0 => 0,
+                dtz if clock - dtz > 100 => -1000 - dtz,
+                dtz => -10000 - dtz,
+            };
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, r#move));
+            }
+        }
+        best.map(|(_, r#move)| r#move)
+    }
+
+    /// Describes the tablebase result of a game in progress, e.g. "Tablebase Win for White".
+    pub(crate) fn tablebase_status(&mut self) -> Option<String> {
+        let side = |white: bool| if white { "White" } else { "Black" };
+        Some(match self.probe_wdl()? {
+            Wdl::Win => format!("Tablebase Win for {}", side(self.is_white_turn)),
+            Wdl::Loss => format!("Tablebase Win for {}", side(!self.is_white_turn)),
+            _ => "Tablebase Draw".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+    use crate::game_status::GameStatus;
+    use crate::search::TABLEBASE_WIN;
+
+    /// A KQvK table storing one bit per position for white to move (0 a win, 1 a draw)
+    /// and a loss for every position with black to move.
+    fn kqvk_table(draws: &[usize]) -> Vec<u8> {
+        let mut bytes = vec![0x71, 0xe8, 0x23, 0x5d, 0x01, 0x00, 0x66, 0x55, 0xee, 0x00];
+        // Huffman header: 4096 byte blocks, one sparse entry, one block, 1 bit symbols
+        bytes.extend([0x00, 12, 15, 0]);
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend([1, 1, 0, 0, 2, 0]);
+        // symbol 0 is the value 4 (win), symbol 1 the value 2 (draw)
+        bytes.extend([4, 0xf0, 0xff, 2, 0xf0, 0xff]);
+        bytes.extend([0x80, 0]);
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(16384u16.to_le_bytes());
+        bytes.extend(31331u16.to_le_bytes());
+        bytes.resize(64, 0);
+        let mut block = vec![0u8; 4096];
+        for &idx in draws {
+            block[idx / 8] |= 0x80 >> (idx % 8);
+        }
+        bytes.extend(block);
+        bytes
+    }
+
+    fn engine(fen: &str, tablebase: &Arc<Tablebase>) -> ChessEngine {
+        let mut chess_position = ChessEngine::new();
+        chess_position.parse_fen(fen).unwrap();
+        chess_position.tablebase = tablebase.clone();
+        chess_position
+    }
+
+    #[test]
+    fn test_probe() {
+        let tablebase = Arc::new(Tablebase::default());
+        // white king c1, queen h4 and black king e8 is index 5824
+        tablebase
+            .add_table("KQvK.rtbw", kqvk_table(&[5824]))
+            .unwrap();
+        assert_eq!(tablebase.max_pieces(), 3);
+
+        let arr: Vec<(&str, Option<Wdl>)> = vec![
+            ("4k3/8/8/8/7Q/8/8/2K5 w - - 0 1", Some(Wdl::Draw)),
+            ("4k3/8/8/8/8/7Q/8/2K5 w - - 0 1", Some(Wdl::Win)),
+            ("4k3/8/8/8/8/7Q/8/2K5 b - - 0 1", Some(Wdl::Loss)),
+            // the same positions with colors swapped
+            ("2k5/8/7q/8/8/8/8/4K3 b - - 0 1", Some(Wdl::Win)),
+            ("2k5/8/7q/8/8/8/8/4K3 w - - 0 1", Some(Wdl::Loss)),
+            // the king takes the queen
+            ("8/8/8/8/8/8/6Qk/2K5 b - - 0 1", Some(Wdl::Draw)),
+            ("8/8/8/8/8/8/8/K6k w - - 0 1", Some(Wdl::Draw)),
+            // no table for these
+            ("4k3/8/8/8/8/7R/8/2K5 w - - 0 1", None),
+            ("r3k3/8/8/8/8/8/8/2K5 b q - 0 1", None),
+        ];
+        for (fen, wdl) in arr {
+            assert_eq!(engine(fen, &tablebase).probe_wdl(), wdl, "{fen}");
+        }
+
+        let mut chess_position = engine("4k3/8/8/8/8/7Q/8/2K5 w - - 0 1", &tablebase);
+        assert_eq!(
+            chess_position.game_status(),
+            "In Progress (Tablebase Win for White)"
+        );
+        // without DTZ tables only zeroing moves can be ranked
+        assert_eq!(chess_position.probe_dtz(), None);
+        let mut chess_position = engine("8/8/8/8/8/8/6Qk/2K5 b - - 0 1", &tablebase);
+        assert_eq!(
+            chess_position.tablebase_move().map(Move::to_uci),
+            Some("h2g2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_search_probe() {
+        let tablebase = Arc::new(Tablebase::default());
+        tablebase.add_table("KQvK.rtbw", kqvk_table(&[])).unwrap();
+        // taking the rook leaves a won KQvK, which the search scores from the table
+        let mut chess_position = engine("4k3/8/8/8/8/8/r7/Q3K3 w - - 0 1", &tablebase);
+        let result = chess_position.analyse(2);
+        assert_eq!(result.best_move.map(Move::to_uci), Some("a1a2".to_string()));
+        assert_eq!(result.score, TABLEBASE_WIN - 1);
+        let mut chess_position = engine("8/8/8/8/8/8/6Qk/2K5 b - - 0 1", &tablebase);
+        assert_eq!(
+            chess_position.engine_move().map(Move::to_uci),
+            Some("h2g2".to_string())
+        );
+    }
+
+    /// Checks known results against the real 3 and 4 piece tables, which are too large to
+    /// keep in the repository.
+    #[test]
+    #[ignore = "needs the 3 and 4 piece Syzygy tables in the directory of SYZYGY_PATH"]
+    fn test_syzygy_tables() {
+        let dir = std::env::var("SYZYGY_PATH").unwrap();
+        let mut chess_position = ChessEngine::new();
+        assert!(chess_position.load_tablebases(&dir).unwrap() > 0);
+        let arr: Vec<(&str, Wdl, Option<i32>)> = vec![
+            // mates in one
+            ("k7/8/1K6/8/8/8/7Q/8 w - - 0 1", Wdl::Win, Some(1)),
+            ("k7/8/1K6/8/8/8/8/7R w - - 0 1", Wdl::Win, Some(1)),
+            ("k7/p7/2K5/8/8/8/7Q/8 w - - 0 1", Wdl::Win, Some(1)),
+            // stalemate and a lone minor piece
+            ("k7/8/1Q6/8/8/8/8/7K b - - 0 1", Wdl::Draw, Some(0)),
+            ("8/8/8/4k3/8/8/8/4KB2 w - - 0 1", Wdl::Draw, Some(0)),
+            // the king in front of its pawn wins whoever moves
+            ("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1", Wdl::Win, None),
+            ("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1", Wdl::Loss, None),
+        ];
+        for (fen, wdl, dtz) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            assert_eq!(chess_position.probe_wdl(), Some(wdl), "{fen}");
+            let probed = chess_position.probe_dtz().unwrap();
+            assert_eq!(probed.signum(), wdl as i32 / 2, "{fen}");
+            if let Some(dtz) = dtz {
+                assert_eq!(probed, dtz, "{fen}");
+            }
+        }
+
+        // the engine plays the tablebase moves for both sides and white keeps its win
+        chess_position
+            .parse_fen("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1")
+            .unwrap();
+        for _ in 0..100 {
+            let Some(r#move) = chess_position.engine_move() else {
+                break;
+            };
+            chess_position.make(r#move);
+        }
+        assert_eq!(chess_position.get_status(), GameStatus::WhiteWon);
+    }
+
+    #[test]
+    fn test_tablebase_files() {
+        let dir = std::env::temp_dir().join(format!("syzygy-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("KQvK.rtbw"), kqvk_table(&[])).unwrap();
+        std::fs::write(dir.join("KRvK.rtbw"), [0u8; 16]).unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let mut chess_position = ChessEngine::new();
+        assert_eq!(chess_position.load_tablebases(dir.to_str().unwrap()), Ok(2));
+        chess_position
+            .parse_fen("4k3/8/8/8/7Q/8/8/2K5 w - - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.probe_wdl(), Some(Wdl::Win));
+        // a broken file is treated as missing
+        chess_position
+            .parse_fen("4k3/8/8/8/7R/8/8/2K5 w - - 0 1")
+            .unwrap();
+        assert_eq!(chess_position.probe_wdl(), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(chess_position.load_tablebases("/nonexistent").is_err());
+        assert!(chess_position
+            .tablebase
+            .add_table("KQK.rtbw", kqvk_table(&[]))
+            .is_err());
+    }
+}
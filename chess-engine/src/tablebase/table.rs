@@ -0,0 +1,705 @@
+use super::encoding::{file_of, off_diagonal, rank_of, Encoding};
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xe8, 0x23, 0x5d];
+const DTZ_MAGIC: [u8; 4] = [0xd7, 0x66, 0x0c, 0xa5];
+
+/// Flags stored for every subtable.
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+/// Piece codes used by the tables, black pieces have the 8 bit set.
+pub const PAWN: u8 = 1;
+pub const KING: u8 = 6;
+const PIECE_LETTERS: [char; 7] = [' ', 'P', 'N', 'B', 'R', 'Q', 'K'];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableKind {
+    Wdl,
+    Dtz,
+}
+
+impl TableKind {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableKind::Wdl => "rtbw",
+            TableKind::Dtz => "rtbz",
+        }
+    }
+}
+
+/// The pieces of a position as (square, piece code), squares numbered a1 = 0 to h8 = 63 in ascending order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TbPosition {
+    pub pieces: Vec<(usize, u8)>,
+    pub white_to_move: bool,
+}
+
+impl TbPosition {
+    /// The material of one side as in table names, e.g. "KRP".
+    pub fn material(&self, white: bool) -> String {
+        let mut codes: Vec<u8> = self
+            .pieces
+            .iter()
+            .map(|&(_, piece)| piece)
+            .filter(|piece| (piece & 8 == 0) == white)
+            .map(|piece| piece & 7)
+            .collect();
+        codes.sort_by(|a, b| b.cmp(a));
+        codes
+            .into_iter()
+            .map(|code| PIECE_LETTERS[code as usize])
+            .collect()
+    }
+}
+
+pub enum TableProbe {
+    Value(i32),
+    /// DTZ tables store only one side to move, the other one has to be searched.
+    WrongSide,
+}
+
+/// Decoding information of one subtable, there is one per side to move and leading pawn file.
+#[derive(Debug, Default, Clone)]
+struct PairsData {
+    flags: u8,
+    min_sym_len: u8,
+    block_size: u64,
+    span: u64,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    num_blocks: usize,
+    base64: Vec<u64>,
+    symlen: Vec<u8>,
+    pieces: [u8; 7],
+    group_idx: [u64; 8],
+    group_len: [usize; 8],
+    map_idx: [usize; 4],
+}
+
+/// A Syzygy `.rtbw` or `.rtbz` file read into memory.
+pub struct Table {
+    kind: TableKind,
+    bytes: Vec<u8>,
+    piece_count: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    /// Pawns of the leading color and of the other one.
+    pawn_count: [usize; 2],
+    /// Both sides have the same material, only white to move is stored.
+    symmetric: bool,
+    /// Indexed by side to move, then by leading pawn file.
+    items: Vec<[PairsData; 4]>,
+    map: usize,
+}
+
+impl std::fmt::Debug for Table {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Table")
+            .field("kind", &self.kind)
+            .field("size", &self.bytes.len())
+            .field("piece_count", &self.piece_count)
+            .finish()
+    }
+}
+
+impl Table {
+    /// Reads a table named after its material, e.g. "KRPvKR".
+    pub fn new(kind: TableKind, name: &str, bytes: Vec<u8>) -> Result<Self, String> {
+        let invalid = || format!("Invalid Tablebase \"{name}.{}\"", kind.extension());
+        let (white, black) = name.split_once('v').ok_or_else(invalid)?;
+        let mut counts = [[0usize; 7]; 2];
+        for (side, material) in [white, black].iter().enumerate() {
+            for letter in material.chars() {
+                let code = PIECE_LETTERS
+                    .iter()
+                    .position(|&ch| ch == letter && ch != ' ')
+                    .ok_or_else(invalid)?;
+                counts[side][code] += 1;
+            }
+        }
+        let magic = match kind {
+            TableKind::Wdl => WDL_MAGIC,
+            TableKind::Dtz => DTZ_MAGIC,
+        };
+        if bytes.len() < 5 || bytes[..4] != magic {
+            return Err(invalid());
+        }
+
+        let (white_pawns, black_pawns) = (counts[0][PAWN as usize], counts[1][PAWN as usize]);
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let mut table = Table {
+            kind,
+            bytes,
+            piece_count: white.len() + black.len(),
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: counts
+                .iter()
+                .any(|side| side[1..KING as usize].contains(&1)),
+            pawn_count: match white_leads {
+                true => [white_pawns, black_pawns],
+                false => [black_pawns, white_pawns],
+            },
+            symmetric: white == black,
+            items: Vec::new(),
+            map: 0,
+        };
+        if table.piece_count > 7 || counts[0][KING as usize] != 1 || counts[1][KING as usize] != 1 {
+            return Err(invalid());
+        }
+        table.set_up().ok_or_else(invalid)?;
+        Ok(table)
+    }
+
+    fn u8_at(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(offset).copied()
+    }
+
+    fn u16_le(&self, offset: usize) -> Option<u16> {
+        Some(u16::from_le_bytes(
+            self.bytes.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    }
+
+    fn u32_le(&self, offset: usize) -> Option<u32> {
+        Some(u32::from_le_bytes(
+            self.bytes.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    }
+
+    fn u32_be(&self, offset: usize) -> Option<u32> {
+        Some(u32::from_be_bytes(
+            self.bytes.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    }
+
+    fn u64_be(&self, offset: usize) -> Option<u64> {
+        Some(u64::from_be_bytes(
+            self.bytes.get(offset..offset + 8)?.try_into().ok()?,
+        ))
+    }
+
+    /// Reads the header: piece order, group sizes, Huffman codes and where the blocks are.
+    fn set_up(&mut self) -> Option<()> {
+        let header = self.u8_at(4)?;
+        let split = !self.symmetric;
+        if (header & 1 != 0) != split || (header & 2 != 0) != self.has_pawns {
+            return None;
+        }
+        let sides = if self.kind == TableKind::Wdl && split {
+            2
+        } else {
+            1
+        };
+        let files = if self.has_pawns { 4 } else { 1 };
+        let pp = self.has_pawns && self.pawn_count[1] > 0;
+        self.items = vec![Default::default(); sides];
+
+        let mut data = 5;
+        for file in 0..files {
+            let order_byte = self.u8_at(data)?;
+            let pawn_order = if pp { self.u8_at(data + 1)? } else { 0xff };
+            let order = [
+                [(order_byte & 0xf) as usize, (pawn_order & 0xf) as usize],
+                [(order_byte >> 4) as usize, (pawn_order >> 4) as usize],
+            ];
+            data += 1 + pp as usize;
+            for k in 0..self.piece_count {
+                let byte = self.u8_at(data)?;
+                for side in 0..sides {
+                    self.items[side][file].pieces[k] =
+                        if side == 1 { byte >> 4 } else { byte & 0xf };
+                }
+                data += 1;
+            }
+            for (side, side_order) in order.iter().enumerate().take(sides) {
+                self.set_groups(side, file, *side_order)?;
+            }
+        }
+        data += data & 1;
+
+        for file in 0..files {
+            for side in 0..sides {
+                data = self.set_sizes(side, file, data)?;
+            }
+        }
+        if self.kind == TableKind::Dtz {
+            data = self.set_dtz_map(data, files)?;
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.sparse_index = data;
+                data += d.sparse_index_size * 6;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.block_length = data;
+                data += d.block_length_size * 2;
+            }
+        }
+        for file in 0..files {
+            for side in 0..sides {
+                data = (data + 0x3f) & !0x3f;
+                let d = &mut self.items[side][file];
+                d.data = data;
+                data += d.num_blocks * d.block_size as usize;
+            }
+        }
+        (data <= self.bytes.len()).then_some(())
+    }
+
+    /// Splits the pieces into groups encoded together and computes the factor of each group.
+    fn set_groups(&mut self, side: usize, file: usize, order: [usize; 2]) -> Option<()> {
+        let encoding = Encoding::get();
+        let (has_pawns, has_unique_pieces, piece_count) =
+            (self.has_pawns, self.has_unique_pieces, self.piece_count);
+        let pp = has_pawns && self.pawn_count[1] > 0;
+        let d = &mut self.items[side][file];
+
+        let mut first_len: i32 = if has_pawns {
+            0
+        } else if has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        let mut n = 0;
+        d.group_len[0] = 1;
+        for i in 1..piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        let mut next = if pp { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if pp { d.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                d.group_idx[0] = idx;
+                idx *= if has_pawns {
+                    encoding.lead_pawns_size[d.group_len[0]][file]
+                } else if has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                d.group_idx[1] = idx;
+                idx *= encoding.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= encoding.binomial.get(d.group_len[next])?[free_squares];
+                free_squares = free_squares.checked_sub(d.group_len[next])?;
+                next += 1;
+            }
+            k += 1;
+            if k > 8 {
+                return None;
+            }
+        }
+        d.group_idx[n] = idx;
+        Some(())
+    }
+
+    fn set_sizes(&mut self, side: usize, file: usize, mut data: usize) -> Option<usize> {
+        let flags = self.u8_at(data)?;
+        data += 1;
+        if flags & FLAG_SINGLE_VALUE != 0 {
+            let value = self.u8_at(data)?;
+            let d = &mut self.items[side][file];
+            d.flags = flags;
+            d.min_sym_len = value;
+            return Some(data + 1);
+        }
+
+        let block_size = 1u64 << self.u8_at(data)?;
+        let span = 1u64 << self.u8_at(data + 1)?;
+        let padding = self.u8_at(data + 2)? as usize;
+        let num_blocks = self.u32_le(data + 3)? as usize;
+        let max_sym_len = self.u8_at(data + 7)?;
+        let min_sym_len = self.u8_at(data + 8)?;
+        data += 9;
+        let lowest_sym = data;
+        if max_sym_len < min_sym_len || max_sym_len > 32 {
+            return None;
+        }
+        let lengths = (max_sym_len - min_sym_len + 1) as usize;
+        let mut base64 = vec![0u64; lengths];
+        for i in (0..lengths - 1).rev() {
+            let lowest = self.u16_le(lowest_sym + 2 * i)? as u64;
+            let next_lowest = self.u16_le(lowest_sym + 2 * (i + 1))? as u64;
+            base64[i] = (base64[i + 1] + lowest).checked_sub(next_lowest)? / 2;
+        }
+        for (i, base) in base64.iter_mut().enumerate() {
+            *base = base
+                .checked_shl(64 - i as u32 - min_sym_len as u32)
+                .unwrap_or(0);
+        }
+        data += lengths * 2;
+        let symbols = self.u16_le(data)? as usize;
+        data += 2;
+        let btree = data;
+
+        let d = &self.items[side][file];
+        let tb_size = d.group_idx[d.group_len.iter().position(|&len| len == 0)?];
+        let mut pairs = PairsData {
+            flags,
+            min_sym_len,
+            block_size,
+            span,
+            lowest_sym,
+            btree,
+            block_length_size: num_blocks + padding,
+            sparse_index_size: tb_size.div_ceil(span) as usize,
+            num_blocks,
+            base64,
+            symlen: vec![0; symbols],
+            ..d.clone()
+        };
+        let mut visited = vec![false; symbols];
+        for symbol in 0..symbols {
+            if !visited[symbol] {
+                pairs.symlen[symbol] = self.set_symlen(&mut pairs, symbol, &mut visited)?;
+            }
+        }
+        self.items[side][file] = pairs;
+        Some(data + symbols * 3 + (symbols & 1))
+    }
+
+    /// The number of values, minus one, a symbol expands into.
+    fn set_symlen(&self, d: &mut PairsData, symbol: usize, visited: &mut [bool]) -> Option<u8> {
+        visited[symbol] = true;
+        let right = self.right_symbol(d, symbol)?;
+        if right == 0xfff {
+            return Some(0);
+        }
+        let left = self.left_symbol(d, symbol)?;
+        for child in [left, right] {
+            if !*visited.get(child)? {
+                d.symlen[child] = self.set_symlen(d, child, visited)?;
+            }
+        }
+        Some(d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1))
+    }
+
+    fn left_symbol(&self, d: &PairsData, symbol: usize) -> Option<usize> {
+        let offset = d.btree + 3 * symbol;
+        Some(((self.u8_at(offset + 1)? as usize & 0xf) << 8) | self.u8_at(offset)? as usize)
+    }
+
+    fn right_symbol(&self, d: &PairsData, symbol: usize) -> Option<usize> {
+        let offset = d.btree + 3 * symbol;
+        Some(((self.u8_at(offset + 2)? as usize) << 4) | (self.u8_at(offset + 1)? as usize >> 4))
+    }
+
+    /// DTZ values are stored by frequency, the maps turn them back into distances.
+    fn set_dtz_map(&mut self, mut data: usize, files: usize) -> Option<usize> {
+        self.map = data;
+        for file in 0..files {
+            let flags = self.items[0][file].flags;
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+            if flags & FLAG_WIDE != 0 {
+                data += data & 1;
+                for i in 0..4 {
+                    self.items[0][file].map_idx[i] = (data - self.map) / 2 + 1;
+                    data += 2 * self.u16_le(data)? as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.items[0][file].map_idx[i] = data - self.map + 1;
+                    data += self.u8_at(data)? as usize + 1;
+                }
+            }
+        }
+        Some(data + (data & 1))
+    }
+
+    /// Finds the value stored at `idx`: locates its block through the sparse index,
+    /// walks the Huffman codes of the block, then expands the paired symbols.
+    fn decompress_pairs(&self, d: &PairsData, idx: u64) -> Option<usize> {
+        if d.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as usize);
+        }
+        let k = (idx / d.span) as usize;
+        let sparse = d.sparse_index + 6 * k;
+        let mut block = self.u32_le(sparse)? as usize;
+        let mut offset = self.u16_le(sparse + 4)? as i64;
+        offset += (idx % d.span) as i64 - (d.span / 2) as i64;
+
+        let block_length =
+            |block: usize| -> Option<i64> { Some(self.u16_le(d.block_length + 2 * block)? as i64) };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+
+        let mut ptr = d.data + block * d.block_size as usize;
+        let mut buf64 = self.u64_be(ptr)?;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let min_sym_len = d.min_sym_len as u32;
+        let mut symbol;
+        loop {
+            let mut len = 0;
+            while buf64 < *d.base64.get(len)? {
+                len += 1;
+            }
+            symbol = ((buf64 - d.base64[len]) >> (64 - len as u32 - min_sym_len)) as usize;
+            symbol += self.u16_le(d.lowest_sym + 2 * len)? as usize;
+            let expands = *d.symlen.get(symbol)? as i64 + 1;
+            if offset < expands {
+                break;
+            }
+            offset -= expands;
+            let consumed = len as u32 + min_sym_len;
+            buf64 = buf64.checked_shl(consumed).unwrap_or(0);
+            buf64_size -= consumed as i32;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (self.u32_be(ptr)? as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        while d.symlen[symbol] != 0 {
+            let left = self.left_symbol(d, symbol)?;
+            let expands = *d.symlen.get(left)? as i64 + 1;
+            if offset < expands {
+                symbol = left;
+            } else {
+                offset -= expands;
+                symbol = self.right_symbol(d, symbol)?;
+            }
+        }
+        self.left_symbol(d, symbol)
+    }
+
+    fn pairs(&self, stm: usize, file: usize) -> &PairsData {
+        &self.items[stm % self.items.len()][if self.has_pawns { file } else { 0 }]
+    }
+
+    /// Turns a stored DTZ value back into plies, `wdl` being the result of the position.
+    fn map_score(&self, file: usize, value: usize, wdl: i32) -> Option<i32> {
+        if self.kind == TableKind::Wdl {
+            return Some(value as i32 - 2);
+        }
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+        let d = self.pairs(0, file);
+        let mut value = value;
+        if d.flags & FLAG_MAPPED != 0 {
+            let index = d.map_idx[WDL_MAP[(wdl + 2) as usize]] + value;
+            value = if d.flags & FLAG_WIDE != 0 {
+                self.u16_le(self.map + 2 * index)? as usize
+            } else {
+                self.u8_at(self.map + index)? as usize
+            };
+        }
+        let plies = match wdl {
+            2 => d.flags & FLAG_WIN_PLIES != 0,
+            -2 => d.flags & FLAG_LOSS_PLIES != 0,
+            _ => false,
+        };
+        let value = value as i32;
+        Some(if plies { value } else { 2 * value } + 1)
+    }
+
+    /// Probes the position, `black_stronger` telling the table was named with black's material first.
+    pub fn probe(
+        &self,
+        position: &TbPosition,
+        black_stronger: bool,
+        wdl: i32,
+    ) -> Option<TableProbe> {
+        let encoding = Encoding::get();
+        let flip = (self.symmetric && !position.white_to_move) || black_stronger;
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = flip as usize ^ !position.white_to_move as usize;
+
+        let mut squares = Vec::with_capacity(7);
+        let mut pieces = Vec::with_capacity(7);
+        let mut lead_pawns = 0;
+        let mut file = 0;
+        let mut lead_pawn = 0;
+        if self.has_pawns {
+            lead_pawn = self.pairs(0, 0).pieces[0] ^ flip_color;
+            for &(square, piece) in &position.pieces {
+                if piece == lead_pawn {
+                    squares.push(square ^ flip_squares);
+                    pieces.push(piece ^ flip_color);
+                }
+            }
+            lead_pawns = squares.len();
+            let leading = (0..lead_pawns).max_by_key(|&i| encoding.map_pawns[squares[i]])?;
+            squares.swap(0, leading);
+            file = file_of(squares[0]).min(7 - file_of(squares[0]));
+        }
+
+        if self.kind == TableKind::Dtz
+            && (self.pairs(stm, file).flags & FLAG_STM) as usize != stm
+            && (!self.symmetric || self.has_pawns)
+        {
+            return Some(TableProbe::WrongSide);
+        }
+
+        for &(square, piece) in &position.pieces {
+            if !self.has_pawns || piece != lead_pawn {
+                squares.push(square ^ flip_squares);
+                pieces.push(piece ^ flip_color);
+            }
+        }
+        let size = squares.len();
+        if size != self.piece_count {
+            return None;
+        }
+        let d = self.pairs(stm, file);
+
+        for i in lead_pawns..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        if file_of(squares[0]) > 3 {
+            for square in squares.iter_mut() {
+                *square ^= 7;
+            }
+        }
+
+        let mut idx: u64;
+        if self.has_pawns {
+            idx = encoding.lead_pawn_idx[lead_pawns][squares[0]];
+            squares[1..lead_pawns].sort_by_key(|&square| encoding.map_pawns[square]);
+            for (i, &square) in squares.iter().enumerate().take(lead_pawns).skip(1) {
+                idx += encoding.binomial[i][encoding.map_pawns[square]];
+            }
+        } else {
+            if rank_of(squares[0]) > 3 {
+                for square in squares.iter_mut() {
+                    *square ^= 56;
+                }
+            }
+            for i in 0..d.group_len[0] {
+                match off_diagonal(squares[i]) {
+                    0 => continue,
+                    off if off > 0 => {
+                        for square in squares[i..].iter_mut() {
+                            *square = ((*square >> 3) | (*square << 3)) & 63;
+                        }
+                    }
+                    _ => {}
+                }
+                break;
+            }
+
+            idx = if self.has_unique_pieces {
+                let adjust1 = (squares[1] > squares[0]) as usize;
+                let adjust2 =
+                    (squares[2] > squares[0]) as usize + (squares[2] > squares[1]) as usize;
+                (if off_diagonal(squares[0]) != 0 {
+                    (encoding.map_a1d1d4[squares[0]] * 63 + (squares[1] - adjust1)) * 62
+                        + squares[2]
+                        - adjust2
+                } else if off_diagonal(squares[1]) != 0 {
+                    (6 * 63 + rank_of(squares[0]) * 28 + encoding.map_b1h1h7[squares[1]]) * 62
+                        + squares[2]
+                        - adjust2
+                } else if off_diagonal(squares[2]) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + rank_of(squares[0]) * 7 * 28
+                        + (rank_of(squares[1]) - adjust1) * 28
+                        + encoding.map_b1h1h7[squares[2]]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + rank_of(squares[0]) * 7 * 6
+                        + (rank_of(squares[1]) - adjust1) * 6
+                        + (rank_of(squares[2]) - adjust2)
+                }) as u64
+            } else {
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]]][squares[1]] as u64
+            };
+        }
+
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let group_end = group_start + d.group_len[next];
+            squares[group_start..group_end].sort_unstable();
+            let mut n = 0;
+            for i in 0..d.group_len[next] {
+                let square = squares[group_start + i];
+                let adjust = squares[..group_start]
+                    .iter()
+                    .filter(|&&s| square > s)
+                    .count();
+                let free = square - adjust - if remaining_pawns { 8 } else { 0 };
+                n += encoding.binomial[i + 1][free];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start = group_end;
+            next += 1;
+        }
+
+        let value = self.decompress_pairs(d, idx)?;
+        self.map_score(file, value, wdl).map(TableProbe::Value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material() {
+        let position = TbPosition {
+            pieces: vec![(4, 6), (8, 1), (12, 4), (60, 14), (59, 11)],
+            white_to_move: true,
+        };
+        assert_eq!(position.material(true), "KRP");
+        assert_eq!(position.material(false), "KB");
+    }
+
+    #[test]
+    fn test_invalid_table() {
+        assert!(Table::new(TableKind::Wdl, "KQvK", vec![0; 64]).is_err());
+        assert!(Table::new(TableKind::Wdl, "KQK", WDL_MAGIC.to_vec()).is_err());
+        let mut bytes = DTZ_MAGIC.to_vec();
+        bytes.extend([1, 0, 0x66]);
+        assert!(Table::new(TableKind::Dtz, "KQvK", bytes).is_err());
+    }
+}
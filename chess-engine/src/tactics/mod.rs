@@ -0,0 +1,430 @@
+use serde::Serialize;
+
+use crate::algebric_notation;
+use crate::board::Board;
+use crate::chess_move::Move;
+use crate::piece::{Piece, PieceTrait};
+use crate::utils;
+use crate::ChessEngine;
+
+#[derive(Serialize, Debug, PartialEq, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum MotifKind {
+    Fork,
+    /// A piece that cannot move without exposing its king.
+    AbsolutePin,
+    /// A piece that cannot move without exposing a more valuable piece.
+    RelativePin,
+    Skewer,
+    DiscoveredAttack,
+    DiscoveredCheck,
+    BackRankWeakness,
+    HangingPiece,
+    /// A piece that alone defends two attacked pieces.
+    OverloadedPiece,
+}
+
+/// A tactical motif with the squares involved, the acting piece first.
+#[derive(Serialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Motif {
+    pub kind: MotifKind,
+    /// Whether white is the side that can make use of it.
+    pub white: bool,
+    pub squares: Vec<String>,
+    /// A hint such as "Knight on e5 forks King on g6 and Rook on c6".
+    pub description: String,
+}
+
+impl Motif {
+    fn new(kind: MotifKind, white: bool, board: &Board, squares: &[usize]) -> Self {
+        let name = |pos: usize| {
+            format!(
+                "{} on {}",
+                piece_name(board[pos]),
+                algebric_notation::to_string(pos as u8)
+            )
+        };
+        let listed = |squares: &[usize]| {
+            let names: Vec<String> = squares.iter().map(|&pos| name(pos)).collect();
+            match names.split_last() {
+                Some((last, [])) => last.clone(),
+                Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+                None => String::new(),
+            }
+        };
+        let description = match kind {
+            MotifKind::Fork => format!("{} forks {}", name(squares[0]), listed(&squares[1..])),
+            MotifKind::AbsolutePin | MotifKind::RelativePin => format!(
+                "{} pins {} to the {}",
+                name(squares[0]),
+                name(squares[1]),
+                name(squares[2])
+            ),
+            MotifKind::Skewer => format!(
+                "{} skewers {} and {}",
+                name(squares[0]),
+                name(squares[1]),
+                name(squares[2])
+            ),
+            MotifKind::DiscoveredAttack => format!(
+                "{} attacks {} by discovery",
+                name(squares[0]),
+                name(squares[1])
+            ),
+            MotifKind::DiscoveredCheck => format!(
+                "{} gives discovered check to the {}",
+                name(squares[0]),
+                name(squares[1])
+            ),
+            MotifKind::BackRankWeakness => format!("{} has a weak back rank", name(squares[0])),
+            MotifKind::HangingPiece => format!("{} is hanging", name(squares[0])),
+            MotifKind::OverloadedPiece => format!(
+                "{} is overloaded defending {}",
+                name(squares[0]),
+                listed(&squares[1..])
+            ),
+        };
+        Self {
+            kind,
+            white,
+            squares: squares
+                .iter()
+                .map(|&pos| algebric_notation::to_string(pos as u8))
+                .collect(),
+            description,
+        }
+    }
+}
+
+fn piece_name(piece: Piece) -> &'static str {
+    if piece.is_king() {
+        "King"
+    } else if piece.is_queen() {
+        "Queen"
+    } else if piece.is_rook() {
+        "Rook"
+    } else if piece.is_bishop() {
+        "Bishop"
+    } else if piece.is_knight() {
+        "Knight"
+    } else {
+        "Pawn"
+    }
+}
+
+fn squares_of(board: &Board, white: bool) -> impl Iterator<Item = usize> + '_ {
+    (0..64)
+        .map(utils::convert_postion_to_0x88)
+        .filter(move |&pos| !board[pos].is_empty() && board[pos].is_white() == white)
+}
+
+fn is_defended(board: &Board, pos: usize) -> bool {
+    !board.attackers(pos, board[pos].is_white()).is_empty()
+}
+
+/// Whether attacking the piece on `target` with the piece on `from` wins something:
+/// it is the king, worth more than the attacker or undefended.
+fn is_worth_attacking(board: &Board, from: usize, target: usize) -> bool {
+    board[target].is_king()
+        || board[target].value() > board[from].value()
+        || !is_defended(board, target)
+}
+
+/// The squares from `from` to the edge of the board in the direction of `offset`.
+fn ray(from: usize, offset: isize) -> impl Iterator<Item = usize> {
+    (1..8)
+        .map(move |step| from as isize + step * offset)
+        .take_while(|&pos| pos >= 0 && pos & 0x88 == 0)
+        .map(|pos| pos as usize)
+}
+
+/// The squares strictly between `from` and `to` if they share a line.
+fn between(from: usize, to: usize) -> Option<Vec<usize>> {
+    [15, 17, 16, 1, -15, -17, -16, -1]
+        .into_iter()
+        .find_map(|offset| {
+            let line: Vec<usize> = ray(from, offset).collect();
+            let end = line.iter().position(|&pos| pos == to)?;
+            Some(line[..end].to_vec())
+        })
+}
+
+/// Lists the motifs the side `white` can make use of in the position.
+pub fn position_motifs(board: &Board, white: bool) -> Vec<Motif> {
+    let mut motifs = Vec::new();
+    for from in squares_of(board, white) {
+        let targets: Vec<usize> = board
+            .attacks(from)
+            .into_iter()
+            .filter(|&pos| !board[pos].is_empty() && board[pos].is_white() != white)
+            .filter(|&pos| is_worth_attacking(board, from, pos))
+            .collect();
+        if targets.len() >= 2 {
+            let mut squares = vec![from];
+            squares.extend(targets);
+            motifs.push(Motif::new(MotifKind::Fork, white, board, &squares));
+        }
+        motifs.extend(line_motifs(board, from));
+    }
+
+    for pos in squares_of(board, !white) {
+        let attackers = board.attackers(pos, white);
+        if !board[pos].is_king() && !attackers.is_empty() && !is_defended(board, pos) {
+            let mut squares = vec![pos];
+            squares.extend(attackers);
+            motifs.push(Motif::new(MotifKind::HangingPiece, white, board, &squares));
+        }
+        // pieces that are the only defender of two attacked pieces
+        let burdens: Vec<usize> = board
+            .attacks(pos)
+            .into_iter()
+            .filter(|&target| !board[target].is_empty() && board[target].is_white() != white)
+            .filter(|&target| !board.attackers(target, white).is_empty())
+            .filter(|&target| board.attackers(target, !white) == [pos])
+            .collect();
+        if burdens.len() >= 2 && !board[pos].is_king() {
+            let mut squares = vec![pos];
+            squares.extend(burdens);
+            motifs.push(Motif::new(
+                MotifKind::OverloadedPiece,
+                white,
+                board,
+                &squares,
+            ));
+        }
+    }
+
+    if let Some(king) = board.king(!white) {
+        motifs.extend(back_rank_weakness(board, king, white));
+    }
+    motifs
+}
+
+/// The pins and skewers of the sliding piece on `from`.
+fn line_motifs(board: &Board, from: usize) -> Vec<Motif> {
+    let piece = board[from];
+    let offsets: &[isize] = if piece.is_bishop() {
+        &[15, 17, -15, -17]
+    } else if piece.is_rook() {
+        &[16, 1, -16, -1]
+    } else if piece.is_queen() {
+        &[15, 17, 16, 1, -15, -17, -16, -1]
+    } else {
+        &[]
+    };
+    let white = piece.is_white();
+    let mut motifs = Vec::new();
+    for &offset in offsets {
+        let mut pieces = ray(from, offset).filter(|&pos| !board[pos].is_empty());
+        let (Some(front), Some(back)) = (pieces.next(), pieces.next()) else {
+            continue;
+        };
+        if board[front].is_white() == white || board[back].is_white() == white {
+            continue;
+        }
+        let kind = if board[back].is_king() {
+            MotifKind::AbsolutePin
+        } else if board[back].value() > board[front].value() {
+            MotifKind::RelativePin
+        } else if board[front].value() > board[back].value() && !board[back].is_pawn() {
+            MotifKind::Skewer
+        } else {
+            continue;
+        };
+        motifs.push(Motif::new(kind, white, board, &[from, front, back]));
+    }
+    motifs
+}
+
+/// The weakness of a king on its first rank whose way out is closed, when the other side
+/// has a rook or queen to give mate there and no rook or queen guards that rank.
+fn back_rank_weakness(board: &Board, king: usize, white: bool) -> Option<Motif> {
+    let back_rank = if white { 7 } else { 0 };
+    let forward: isize = if white { -16 } else { 16 };
+    let is_heavy = |pos: usize| board[pos].is_rook() || board[pos].is_queen();
+    let has_heavy_piece = squares_of(board, white).any(is_heavy);
+    let is_guarded = squares_of(board, !white).any(|pos| pos >> 4 == back_rank && is_heavy(pos));
+    if king >> 4 != back_rank || !has_heavy_piece || is_guarded {
+        return None;
+    }
+    let escapes: Vec<usize> = [-1, 0, 1]
+        .into_iter()
+        .map(|side| king as isize + forward + side)
+        .filter(|&pos| pos >= 0 && pos & 0x88 == 0)
+        .map(|pos| pos as usize)
+        .collect();
+    let blockers: Vec<usize> = escapes
+        .iter()
+        .copied()
+        .filter(|&pos| !board[pos].is_empty() && board[pos].is_white() != white)
+        .collect();
+    let closed = escapes.iter().all(|&pos| {
+        (!board[pos].is_empty() && board[pos].is_white() != white)
+            || !board.attackers(pos, white).is_empty()
+    });
+    let mut squares = vec![king];
+    squares.extend(blockers);
+    closed.then(|| Motif::new(MotifKind::BackRankWeakness, white, board, &squares))
+}
+
+impl ChessEngine {
+    /// Lists the motifs of the position for both sides.
+    pub(crate) fn motifs(&self) -> Vec<Motif> {
+        let mut motifs = position_motifs(&self.board, self.is_white_turn);
+        motifs.extend(position_motifs(&self.board, !self.is_white_turn));
+        motifs
+    }
+
+    /// Lists the motifs a legal move creates for the side playing it, including discovered
+    /// attacks and checks by the pieces it uncovers.
+    pub(crate) fn move_motifs(&mut self, r#move: Move) -> Vec<Motif> {
+        let white = self.is_white_turn;
+        let before_board = self.board;
+        let before = position_motifs(&before_board, white);
+        let undo = self.make(r#move);
+        let board = self.board;
+        let mut motifs: Vec<Motif> = position_motifs(&board, white)
+            .into_iter()
+            .filter(|motif| !before.contains(motif))
+            .collect();
+        for from in squares_of(&board, white).filter(|&from| from != r#move.to) {
+            for target in board.attacks(from) {
+                if board[target].is_empty()
+                    || board[target].is_white() == white
+                    || before_board.attacks(from).contains(&target)
+                    || !is_worth_attacking(&board, from, target)
+                    || !between(from, target).is_some_and(|line| line.contains(&r#move.from))
+                {
+                    continue;
+                }
+                let kind = if board[target].is_king() {
+                    MotifKind::DiscoveredCheck
+                } else {
+                    MotifKind::DiscoveredAttack
+                };
+                motifs.push(Motif::new(kind, white, &board, &[from, target]));
+            }
+        }
+        self.unmake(undo);
+        motifs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fen_trait::FenParser;
+
+    type Labels = Vec<(MotifKind, &'static str)>;
+
+    #[test]
+    fn test_position_motifs() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, Vec<(MotifKind, &str)>)> = vec![
+            (
+                "8/8/2r3k1/4N3/8/8/8/4K3 w - - 0 1",
+                vec![
+                    (
+                        MotifKind::Fork,
+                        "Knight on e5 forks Rook on c6 and King on g6",
+                    ),
+                    (MotifKind::HangingPiece, "Rook on c6 is hanging"),
+                ],
+            ),
+            (
+                "4k3/8/2n5/1B6/8/8/8/4K3 w - - 0 1",
+                vec![
+                    (
+                        MotifKind::AbsolutePin,
+                        "Bishop on b5 pins Knight on c6 to the King on e8",
+                    ),
+                    (MotifKind::HangingPiece, "Knight on c6 is hanging"),
+                ],
+            ),
+            (
+                "q7/8/8/k7/8/8/8/R3K3 b - - 0 1",
+                vec![(
+                    MotifKind::Skewer,
+                    "Rook on a1 skewers King on a5 and Queen on a8",
+                )],
+            ),
+            (
+                "6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+                vec![(
+                    MotifKind::BackRankWeakness,
+                    "King on g8 has a weak back rank",
+                )],
+            ),
+            (
+                "5q1k/4r3/5n2/8/8/2B5/8/4R1K1 w - - 0 1",
+                vec![
+                    (
+                        MotifKind::AbsolutePin,
+                        "Bishop on c3 pins Knight on f6 to the King on h8",
+                    ),
+                    (
+                        MotifKind::OverloadedPiece,
+                        "Queen on f8 is overloaded defending Rook on e7 and Knight on f6",
+                    ),
+                ],
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                vec![],
+            ),
+        ];
+        for (fen, expected) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let motifs: Vec<(MotifKind, String)> = chess_position
+                .motifs()
+                .into_iter()
+                .map(|motif| (motif.kind, motif.description))
+                .collect();
+            let expected: Vec<(MotifKind, String)> = expected
+                .into_iter()
+                .map(|(kind, description)| (kind, description.to_string()))
+                .collect();
+            assert_eq!(motifs, expected, "{fen}");
+        }
+    }
+
+    #[test]
+    fn test_move_motifs() {
+        let mut chess_position = ChessEngine::new();
+        let arr: Vec<(&str, Move, Labels)> = vec![
+            (
+                "4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1",
+                Move::new(52, 66, None),
+                vec![(MotifKind::DiscoveredCheck, "e1 e8")],
+            ),
+            (
+                "8/8/2r3k1/8/8/5N2/8/4K3 w - - 0 1",
+                Move::new(37, 68, None),
+                vec![
+                    (MotifKind::Fork, "e5 c6 g6"),
+                    (MotifKind::HangingPiece, "c6 e5"),
+                ],
+            ),
+            (
+                "3qk3/8/8/8/8/8/3B4/3RK3 w - - 0 1",
+                Move::new(19, 53, None),
+                vec![(MotifKind::DiscoveredAttack, "d1 d8")],
+            ),
+        ];
+        for (fen, r#move, expected) in arr {
+            chess_position.parse_fen(fen).unwrap();
+            let motifs: Vec<(MotifKind, String)> = chess_position
+                .move_motifs(r#move)
+                .into_iter()
+                .map(|motif| (motif.kind, motif.squares.join(" ")))
+                .collect();
+            let expected: Vec<(MotifKind, String)> = expected
+                .into_iter()
+                .map(|(kind, squares)| (kind, squares.to_string()))
+                .collect();
+            assert_eq!(motifs, expected, "{fen}");
+            assert_eq!(chess_position.get_fen(), fen);
+        }
+    }
+}
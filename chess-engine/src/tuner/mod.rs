@@ -0,0 +1,330 @@
+use std::sync::Arc;
+
+use crate::evaluation::{self, Weights};
+use crate::fen_trait::FenParser;
+use crate::search::Search;
+use crate::selfplay::{parse_result, TrainingPosition, RECORD_SIZE};
+use crate::ChessEngine;
+
+/// The decay of Adam's running averages of the gradient and of its square.
+const BETA1: f64 = 0.9;
+const BETA2: f64 = 0.999;
+const EPSILON: f64 = 1e-8;
+
+/// A quiet position with the result of its game from white's side, its evaluation being
+/// the sum of the weights of its features.
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    features: Vec<(usize, f64)>,
+    result: f64,
+}
+
+/// Tunes the weights of the handcrafted evaluation on positions labelled with the results
+/// of their games (Texel's method): the evaluation, turned into an expected result by a
+/// sigmoid, is brought closer to the results by gradient descent on the mean squared error.
+///
+/// Each position is first quieted by a quiescence search with the starting weights, and
+/// the position at the end of its line stands for it, so the weights are not tuned on
+/// positions in the middle of an exchange.
+#[derive(Debug)]
+pub struct Tuner {
+    /// Evaluates with the starting weights to quiet the positions.
+    engine: ChessEngine,
+    samples: Vec<Sample>,
+    parameters: Vec<f64>,
+    /// How steep the sigmoid is, see [`Tuner::fit_scale`].
+    scale: f64,
+    pub learning_rate: f64,
+    /// Adam's running averages of the gradient and of its square, and the steps taken.
+    momentum: Vec<f64>,
+    velocity: Vec<f64>,
+    steps: i32,
+}
+
+impl Tuner {
+    pub fn new(weights: Weights) -> Self {
+        let parameters: Vec<f64> = weights
+            .to_parameters()
+            .into_iter()
+            .map(|weight| weight as f64)
+            .collect();
+        Self {
+            engine: ChessEngine {
+                weights: Arc::new(weights),
+                ..Default::default()
+            },
+            samples: Vec::new(),
+            momentum: vec![0.0; parameters.len()],
+            velocity: vec![0.0; parameters.len()],
+            parameters,
+            scale: 1.0,
+            learning_rate: 1.0,
+            steps: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Adds a position with the result of its game, 1 for a white win, 0.5 for a draw and
+    /// 0 for a black win.
+    pub fn add_position(&mut self, fen: &str, result: f64) -> Result<(), String> {
+        self.engine.parse_fen(fen)?;
+        let (_, line) = Search::new().quiesce(&mut self.engine);
+        for r#move in line {
+            self.engine.make(r#move);
+        }
+        self.samples.push(Sample {
+            features: evaluation::features(&self.engine.board),
+            result,
+        });
+        Ok(())
+    }
+
+    /// Adds the positions of a text file, one a line as "FEN | result" or as written by
+    /// the self-play generator, "FEN | score | result". Returns how many were added.
+    pub fn add_text(&mut self, text: &str) -> Result<usize, String> {
+        let mut count = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                format!(
+                    "Invalid Labelled Position \"{line}\" at line {}",
+                    number + 1
+                )
+            };
+            let (fen, result) = match line.split('|').map(str::trim).collect::<Vec<_>>()[..] {
+                [fen, result] => (fen.to_string(), parse_result(result).ok_or_else(invalid)?),
+                [_, _, _] => {
+                    let position = TrainingPosition::from_text(line).map_err(|_| invalid())?;
+                    (position.fen, position.result)
+                }
+                _ => return Err(invalid()),
+            };
+            self.add_position(&fen, result)
+                .map_err(|err| format!("{err} at line {}", number + 1))?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Adds the positions of the binary records of the self-play generator.
+    pub fn add_records(&mut self, bytes: &[u8]) -> Result<usize, String> {
+        if !bytes.len().is_multiple_of(RECORD_SIZE) {
+            return Err(format!("Invalid Training Data Size \"{}\"", bytes.len()));
+        }
+        for record in bytes.chunks_exact(RECORD_SIZE) {
+            let position = TrainingPosition::from_bytes(record)?;
+            self.add_position(&position.fen, position.result)?;
+        }
+        Ok(bytes.len() / RECORD_SIZE)
+    }
+
+    /// Adds the positions of a local file, binary records if it ends in ".bin", else text.
+    pub fn add_file(&mut self, path: &str) -> Result<usize, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|err| format!("Cannot Read Positions \"{path}\": {err}"))?;
+        if path.ends_with(".bin") {
+            self.add_records(&bytes)
+        } else {
+            self.add_text(&String::from_utf8_lossy(&bytes))
+        }
+    }
+
+    /// The mean squared error between the results and the expected results of the
+    /// evaluations with the current weights.
+    pub fn error(&self) -> f64 {
+        self.error_with(self.scale)
+    }
+
+    fn error_with(&self, scale: f64) -> f64 {
+        let total: f64 = self
+            .samples
+            .iter()
+            .map(|sample| (sample.result - sigmoid(scale, self.evaluate(sample))).powi(2))
+            .sum();
+        total / self.samples.len().max(1) as f64
+    }
+
+    fn evaluate(&self, sample: &Sample) -> f64 {
+        sample
+            .features
+            .iter()
+            .map(|&(index, count)| self.parameters[index] * count)
+            .sum()
+    }
+
+    /// Fits how steep the sigmoid is to the positions with the current weights, by ternary
+    /// search for the least error, before the weights are tuned. Returns it.
+    pub fn fit_scale(&mut self) -> f64 {
+        let (mut low, mut high) = (0.0, 10.0);
+        for _ in 0..100 {
+            let third = (high - low) / 3.0;
+            if self.error_with(low + third) < self.error_with(high - third) {
+                high -= third;
+            } else {
+                low += third;
+            }
+        }
+        self.scale = (low + high) / 2.0;
+        self.scale
+    }
+
+    /// Moves the weights a step down the gradient of the error over all positions, with
+    /// Adam, and returns the error before the step.
+    pub fn step(&mut self) -> f64 {
+        let mut gradient = vec![0.0; self.parameters.len()];
+        let mut error = 0.0;
+        let ln_10 = std::f64::consts::LN_10;
+        for sample in &self.samples {
+            let expected = sigmoid(self.scale, self.evaluate(sample));
+            let difference = sample.result - expected;
+            error += difference * difference;
+            // the derivative of the squared error by the evaluation
+            let slope =
+                -2.0 * difference * expected * (1.0 - expected) * ln_10 * self.scale / 400.0;
+            for &(index, count) in &sample.features {
+                gradient[index] += slope * count;
+            }
+        }
+        let samples = self.samples.len().max(1) as f64;
+
+        self.steps += 1;
+        let momentum_correction = 1.0 - BETA1.powi(self.steps);
+        let velocity_correction = 1.0 - BETA2.powi(self.steps);
+        for (index, gradient) in gradient.into_iter().enumerate() {
+            let gradient = gradient / samples;
+            self.momentum[index] = BETA1 * self.momentum[index] + (1.0 - BETA1) * gradient;
+            self.velocity[index] =
+                BETA2 * self.velocity[index] + (1.0 - BETA2) * gradient * gradient;
+            let momentum = self.momentum[index] / momentum_correction;
+            let velocity = self.velocity[index] / velocity_correction;
+            self.parameters[index] -= self.learning_rate * momentum / (velocity.sqrt() + EPSILON);
+        }
+        error / samples
+    }
+
+    /// The tuned weights, rounded to centipawns.
+    pub fn weights(&self) -> Weights {
+        let parameters: Vec<i32> = self
+            .parameters
+            .iter()
+            .map(|weight| weight.round() as i32)
+            .collect();
+        Weights::from_parameters(&parameters).expect("a weight for each parameter")
+    }
+}
+
+/// The expected result of an evaluation in centipawns.
+fn sigmoid(scale: f64, score: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scale * score / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_positions() {
+        let mut tuner = Tuner::new(Weights::default());
+        let arr: Vec<(&str, &str)> = vec![
+            (
+                "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+                "4k3/8/8/3R4/8/8/8/4K3 b - - 0 1",
+            ),
+            (
+                "4k3/8/4p3/3q4/8/8/8/3RK3 w - - 0 1",
+                "4k3/8/8/3p4/8/8/8/4K3 w - - 0 2",
+            ),
+            (
+                "4k3/1P6/8/8/8/8/8/4K3 w - - 0 1",
+                "1Q2k3/8/8/8/8/8/8/4K3 b - - 0 1",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/3QK3 w - - 0 1",
+            ),
+        ];
+        for (fen, quiet) in arr {
+            tuner.add_position(fen, 1.0).unwrap();
+            let mut engine = ChessEngine::new();
+            engine.parse_fen(quiet).unwrap();
+            let features = evaluation::features(&engine.board);
+            assert_eq!(tuner.samples.last().unwrap().features, features, "{fen}");
+        }
+        assert!(tuner
+            .add_position("8/8/8/8/8/8/8/9 w - - 0 1", 1.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_text() {
+        let mut tuner = Tuner::new(Weights::default());
+        let text = "# labelled positions\n\
+                    4k3/8/8/8/8/8/8/3QK3 w - - 0 1 | 1-0\n\
+                    \n\
+                    4k3/8/8/8/8/8/8/3QK3 b - - 0 1 | 895 | 1.0\n";
+        assert_eq!(tuner.add_text(text), Ok(2));
+        assert_eq!(tuner.samples[0], tuner.samples[1]);
+        assert_eq!(
+            tuner.add_text("4k3/8/8/8/8/8/8/3QK3 w - - 0 1 | 2"),
+            Err(
+                "Invalid Labelled Position \"4k3/8/8/8/8/8/8/3QK3 w - - 0 1 | 2\" at line 1"
+                    .to_string()
+            )
+        );
+        let record = TrainingPosition {
+            fen: "4k3/8/8/8/8/8/8/3QK3 w - - 0 1".to_string(),
+            score: 895,
+            result: 1.0,
+        };
+        let bytes = record.to_bytes().unwrap();
+        assert_eq!(tuner.add_records(&bytes), Ok(1));
+        assert_eq!(tuner.samples[2], tuner.samples[0]);
+        assert!(tuner.add_records(&bytes[1..]).is_err());
+        assert_eq!(tuner.len(), 3);
+    }
+
+    #[test]
+    fn test_tune() {
+        let mut weights = Weights::default();
+        // knights are worth far too little to start with, and wins with an extra knight
+        // should bring them back up
+        weights.material[1] = 50;
+        let mut tuner = Tuner::new(weights);
+        let arr: Vec<(&str, f64)> = vec![
+            ("4k3/8/8/8/8/8/8/1N2K3 w - - 0 1", 1.0),
+            ("4k3/8/8/8/8/2N5/8/4K3 b - - 0 1", 1.0),
+            ("1n2k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.0),
+            ("4k3/8/5n2/8/8/8/8/4K3 b - - 0 1", 0.0),
+            ("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1", 0.5),
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", 0.5),
+        ];
+        for (fen, result) in arr {
+            tuner.add_position(fen, result).unwrap();
+        }
+        assert!(tuner.fit_scale() > 0.0);
+        let before = tuner.error();
+        tuner.learning_rate = 5.0;
+        for _ in 0..100 {
+            tuner.step();
+        }
+        assert!(tuner.error() < before);
+        assert!(tuner.weights().material[1] > 50);
+
+        let untouched = Tuner::new(Weights::default());
+        assert_eq!(untouched.weights(), Weights::default());
+    }
+}
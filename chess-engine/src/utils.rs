@@ -2,3 +2,17 @@
 pub fn convert_postion_to_0x88(pos: usize) -> usize {
     (pos & 0x7) | ((pos & 0x38) << 1)
 }
+
+/// Milliseconds since the Unix epoch.
+#[cfg(target_arch = "wasm32")]
+pub fn now() -> f64 {
+    js_sys::Date::now()
+}
+
+/// Milliseconds since the Unix epoch.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn now() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |duration| duration.as_millis() as f64)
+}